@@ -0,0 +1,113 @@
+//! A fountain of sparks that follows the mouse, with an explosion on every click.
+
+use std::{env, f32::consts::FRAC_PI_2, path};
+
+use ggez::{
+    event,
+    graphics::{
+        self,
+        particles::{Curve, Emitter, EmitterShape, ParticleSystem},
+        BlendMode, Color,
+    },
+    input::mouse::MouseButton,
+    Context, GameResult,
+};
+
+struct MainState {
+    sparks: ParticleSystem,
+    explosions: ParticleSystem,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> GameResult<MainState> {
+        // Without an image particles are 1x1 squares, so sizes are in pixels.
+        let mut sparks = ParticleSystem::new(ctx, None);
+        sparks.add_emitter(
+            Emitter::new()
+                .rate(300.)
+                .shape(EmitterShape::Circle { radius: 4. })
+                .lifetime(1.0..=1.5)
+                .speed(150.0..=250.)
+                .direction(-FRAC_PI_2, 0.5)
+                .gravity([0., 300.])
+                .color(
+                    Curve::constant(Color::new(1., 0.9, 0.4, 1.))
+                        .key(0.6, Color::new(1., 0.3, 0.1, 1.))
+                        .key(1., Color::new(0.5, 0., 0., 0.)),
+                )
+                .size(Curve::linear(4., 1.)),
+        );
+
+        let image = graphics::Image::from_path(ctx, "/shot.png")?;
+        let explosions = ParticleSystem::new(ctx, image);
+
+        Ok(MainState { sparks, explosions })
+    }
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let dt = ctx.time.delta();
+        self.sparks.emitters_mut()[0].set_position(ctx.mouse.position());
+        self.sparks.update(dt);
+
+        self.explosions.remove_finished();
+        self.explosions.update(dt);
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.05, 0.05, 0.1, 1.0]));
+        canvas.set_blend_mode(BlendMode::ADD);
+        canvas.draw(&self.sparks, [0., 0.]);
+        canvas.draw(&self.explosions, [0., 0.]);
+        canvas.set_blend_mode(BlendMode::ALPHA);
+
+        let stats = format!(
+            "{} particles, {:.0} FPS",
+            self.sparks.particle_count() + self.explosions.particle_count(),
+            ctx.time.fps()
+        );
+        canvas.draw(&graphics::Text::new(stats), [10., 10.]);
+
+        canvas.finish(ctx)
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        // A one-off burst: the emitter doesn't emit continuously and is removed
+        // once all its particles died.
+        let mut emitter = Emitter::new()
+            .with_position([x, y])
+            .lifetime(0.3..=0.8)
+            .speed(100.0..=400.)
+            .spin(-10.0..=10.)
+            .damping(3.)
+            .color(Curve::linear(Color::WHITE, Color::new(0.2, 0.5, 1., 0.)))
+            .size(Curve::linear(1., 0.2));
+        emitter.set_emitting(false);
+        emitter.burst(200);
+        self.explosions.add_emitter(emitter);
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        let mut path = path::PathBuf::from(manifest_dir);
+        path.push("resources");
+        path
+    } else {
+        path::PathBuf::from("./resources")
+    };
+
+    let cb = ggez::ContextBuilder::new("particles", "ggez").add_resource_path(resource_dir);
+    let (mut ctx, event_loop) = cb.build()?;
+    let state = MainState::new(&mut ctx)?;
+    event::run(ctx, event_loop, state)
+}
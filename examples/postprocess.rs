@@ -0,0 +1,123 @@
+//! Runs a scene through a chain of the built-in post-processing effects.
+//!
+//! Press 1 to 5 to toggle the effects.
+
+use std::{env, path};
+
+use ggez::{
+    event,
+    glam::Vec2,
+    graphics::{
+        self,
+        postprocess::{Bloom, ColorGrading, Crt, PostEffect, PostProcess, Vignette},
+        Color, DrawMode,
+    },
+    input::keyboard::KeyInput,
+    Context, GameResult,
+};
+use winit::keyboard::Key;
+
+const EFFECTS: [&str; 5] = ["bloom", "color grading", "crt", "vignette", "blur"];
+
+struct MainState {
+    post: PostProcess,
+    circle: graphics::Mesh,
+    image: graphics::Image,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> GameResult<MainState> {
+        let mut blur = PostEffect::blur(ctx, Default::default())?;
+        blur.set_enabled(false);
+        let post = PostProcess::new(ctx)
+            .effect(PostEffect::bloom(ctx, Bloom::default())?)
+            .effect(PostEffect::color_grading(
+                ctx,
+                ColorGrading {
+                    contrast: 1.1,
+                    saturation: 1.3,
+                    tint: Color::new(1., 0.95, 0.85, 1.),
+                    ..Default::default()
+                },
+            )?)
+            .effect(PostEffect::crt(ctx, Crt::default())?)
+            .effect(PostEffect::vignette(ctx, Vignette::default())?)
+            .effect(blur);
+
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            DrawMode::fill(),
+            Vec2::new(0., 0.),
+            60.,
+            1.,
+            Color::new(1., 0.8, 0.3, 1.),
+        )?;
+        let image = graphics::Image::from_path(ctx, "/dragon1.png")?;
+
+        Ok(MainState {
+            post,
+            circle,
+            image,
+        })
+    }
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // Let the glow pulse.
+        let bloom = Bloom {
+            intensity: 1.5 + ctx.time.time_since_start().as_secs_f32().sin(),
+            ..Default::default()
+        };
+        self.post.effects_mut()[0].set_uniforms(ctx, &bloom)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = self.post.begin(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+
+        canvas.draw(&self.image, Vec2::new(100., 150.));
+        let t = ctx.time.time_since_start().as_secs_f32();
+        canvas.draw(&self.circle, Vec2::new(550. + t.cos() * 100., 300.));
+
+        let status: String = EFFECTS
+            .iter()
+            .zip(self.post.effects())
+            .enumerate()
+            .map(|(i, (name, effect))| {
+                let state = if effect.is_enabled() { "on" } else { "off" };
+                format!("{}: {} ({})\n", i + 1, name, state)
+            })
+            .collect();
+        canvas.draw(&graphics::Text::new(status), Vec2::new(20., 20.));
+
+        self.post.finish(ctx, canvas)
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+        if let (Key::Character(c), false) = (input.event.logical_key, repeat) {
+            if let Some(effect) = c
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| self.post.effects_mut().get_mut(n.wrapping_sub(1)))
+            {
+                effect.set_enabled(!effect.is_enabled());
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        let mut path = path::PathBuf::from(manifest_dir);
+        path.push("resources");
+        path
+    } else {
+        path::PathBuf::from("./resources")
+    };
+
+    let cb = ggez::ContextBuilder::new("postprocess", "ggez").add_resource_path(resource_dir);
+    let (mut ctx, event_loop) = cb.build()?;
+    let state = MainState::new(&mut ctx)?;
+    event::run(ctx, event_loop, state)
+}
@@ -0,0 +1,402 @@
+use super::{EffectChain, Effects, FadeCurve, Ramp};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// How often a bus picks up changes to its gain, and a sound playing on it notes that it's
+/// producing output.
+const BUS_UPDATE_INTERVAL: Duration = Duration::from_millis(5);
+
+/// How long a bus counts as sounding after its last output, for ducking. The audio thread
+/// renders in bursts of a few milliseconds, so this has to span a few of them.
+const SIDECHAIN_HOLD: Duration = Duration::from_millis(100);
+
+/// A named mixer bus that sounds can be routed to, e.g. "music", "sfx" or "dialogue",
+/// so that they can be turned up, down or off as a group.
+///
+/// The sounds on a bus are mixed together first, and its [`effects`](Bus::effects) and gain
+/// are then applied once to the mix, like on a mixing desk.
+///
+/// Get one with [`AudioContext::bus`](super::AudioContext::bus) and route a sound to it
+/// with [`SoundSource::set_bus`](super::SoundSource::set_bus). It is a cheap handle, cloning
+/// it gives another handle to the same bus.
+///
+/// ```rust,no_run
+/// # use ggez::{audio::{self, Ducking, SoundSource}, Context, GameResult};
+/// # use std::time::Duration;
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let music = ctx.audio.bus("music");
+/// let dialogue = ctx.audio.bus("dialogue");
+/// // Dip the music while someone is talking.
+/// music.set_ducking(&dialogue, Ducking::default());
+///
+/// let mut song = audio::Source::new(ctx, "/song.ogg")?;
+/// song.set_bus(Some(music.clone()));
+/// song.play();
+///
+/// // Later, fade the music out over two seconds.
+/// music.fade_to(0.0, Duration::from_secs(2));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Bus(Arc<BusInner>);
+
+struct BusInner {
+    name: String,
+    voices: AtomicUsize,
+    created: Instant,
+    // When a sound on the bus last produced output, in nanoseconds after `created` plus one,
+    // or 0 if none did yet.
+    last_output: AtomicU64,
+    effects: Effects,
+    mixer: rodio::mixer::Mixer,
+    state: Mutex<BusState>,
+}
+
+#[derive(Debug)]
+struct BusState {
+    volume: Ramp,
+    muted: bool,
+    ducking: Option<(Bus, Ducking)>,
+    duck: Ramp,
+}
+
+/// Settings for sidechain ducking, see [`Bus::set_ducking`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ducking {
+    /// The factor the ducked bus is turned down to while the sidechain bus is playing.
+    pub level: f32,
+    /// How long it takes to turn down once the sidechain bus starts playing.
+    pub attack: Duration,
+    /// How long it takes to come back up once the sidechain bus stops playing.
+    pub release: Duration,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Ducking {
+            level: 0.3,
+            attack: Duration::from_millis(100),
+            release: Duration::from_millis(500),
+        }
+    }
+}
+
+impl Bus {
+    /// Creates a bus mixing its sounds in the given format, along with the output of the
+    /// mix with the bus effects and gain applied, to be played on the device.
+    pub(crate) fn new(
+        name: &str,
+        channels: rodio::ChannelCount,
+        sample_rate: rodio::SampleRate,
+    ) -> (Self, impl rodio::Source + Send + 'static) {
+        use rodio::Source;
+
+        let (mixer, mix) = rodio::mixer::mixer(channels, sample_rate);
+        // An empty mixer ends, which would drop the bus from the device until it's created
+        // again, so keep it playing silence.
+        mixer.add(rodio::source::Zero::new(channels, sample_rate));
+
+        let bus = Bus(Arc::new(BusInner {
+            name: name.to_string(),
+            voices: AtomicUsize::new(0),
+            created: Instant::now(),
+            last_output: AtomicU64::new(0),
+            effects: Effects::new(),
+            mixer,
+            state: Mutex::new(BusState {
+                volume: Ramp::constant(1.0),
+                muted: false,
+                ducking: None,
+                duck: Ramp::constant(1.0),
+            }),
+        }));
+
+        let output = EffectChain::new(mix, bus.effects().clone())
+            .amplify(bus.gain())
+            .periodic_access(BUS_UPDATE_INTERVAL, {
+                let bus = bus.clone();
+                move |source| source.set_factor(bus.gain())
+            });
+        (bus, output)
+    }
+
+    /// Returns the name of the bus.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Returns the effects applied to the mix of the sounds on the bus, after their own.
+    #[inline]
+    pub fn effects(&self) -> &Effects {
+        &self.0.effects
+    }
+
+    /// Returns the volume of the bus, part way through a fade if one is running.
+    pub fn volume(&self) -> f32 {
+        self.0.state.lock().unwrap().volume.value(Instant::now())
+    }
+
+    /// Sets the volume of the bus, cancelling any running fade.
+    pub fn set_volume(&self, volume: f32) {
+        self.0.state.lock().unwrap().volume = Ramp::constant(volume);
+    }
+
+    /// Fades the volume of the bus linearly from where it is now to `volume` over `duration`.
+    pub fn fade_to(&self, volume: f32, duration: Duration) {
+        let mut state = self.0.state.lock().unwrap();
+        state.volume = state
+            .volume
+            .retarget(volume, duration, FadeCurve::Linear, Instant::now());
+    }
+
+    /// Returns whether the bus is muted.
+    pub fn muted(&self) -> bool {
+        self.0.state.lock().unwrap().muted
+    }
+
+    /// Mutes or unmutes the bus. Its volume is kept, and fades keep running while muted.
+    pub fn set_muted(&self, muted: bool) {
+        self.0.state.lock().unwrap().muted = muted;
+    }
+
+    /// Turns this bus down to `ducking.level` whenever a sound is playing on `sidechain`,
+    /// e.g. to dip the music while dialogue plays.
+    ///
+    /// Only sounds that are producing output count, so pausing the dialogue, e.g. in a pause
+    /// menu, lets the music come back up.
+    pub fn set_ducking(&self, sidechain: &Bus, ducking: Ducking) {
+        self.0.state.lock().unwrap().ducking = Some((sidechain.clone(), ducking));
+    }
+
+    /// Stops ducking this bus; it comes back up over the release time if it was ducked.
+    pub fn clear_ducking(&self) {
+        let mut state = self.0.state.lock().unwrap();
+        if let Some((_, ducking)) = state.ducking.take() {
+            state.duck =
+                state
+                    .duck
+                    .retarget(1.0, ducking.release, FadeCurve::Linear, Instant::now());
+        }
+    }
+
+    /// Returns the number of sounds currently queued on the bus, including paused ones.
+    #[inline]
+    pub fn active_voices(&self) -> usize {
+        self.0.voices.load(Ordering::Relaxed)
+    }
+
+    fn since_created(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.0.created).as_nanos() as u64 + 1
+    }
+
+    /// Whether a sound on the bus produced output recently, as of `now`.
+    fn sounding(&self, now: Instant) -> bool {
+        let last = self.0.last_output.load(Ordering::Relaxed);
+        last != 0 && self.since_created(now) <= last + SIDECHAIN_HOLD.as_nanos() as u64
+    }
+
+    /// Returns the factor the mix of the sounds on the bus is currently multiplied by,
+    /// taking its volume, fade, mute and ducking into account.
+    pub fn gain(&self) -> f32 {
+        self.gain_at(Instant::now())
+    }
+
+    fn gain_at(&self, now: Instant) -> f32 {
+        let mut state = self.0.state.lock().unwrap();
+
+        if let Some((sidechain, ducking)) = &state.ducking {
+            let (target, duration) = if sidechain.sounding(now) {
+                (ducking.level, ducking.attack)
+            } else {
+                (1.0, ducking.release)
+            };
+            if state.duck.to != target {
+                state.duck = state
+                    .duck
+                    .retarget(target, duration, FadeCurve::Linear, now);
+            }
+        }
+
+        if state.muted {
+            0.0
+        } else {
+            state.volume.value(now) * state.duck.value(now)
+        }
+    }
+
+    /// Marks a sound as playing on this bus until the returned guard is dropped, which
+    /// happens when the sound finishes or is stopped.
+    fn voice(&self) -> BusVoice {
+        let _ = self.0.voices.fetch_add(1, Ordering::Relaxed);
+        BusVoice(self.clone())
+    }
+}
+
+/// Returns the mixer that players routed through `bus` are connected to, or the device
+/// mixer for `None`.
+pub(crate) fn bus_mixer<'a>(
+    device: &'a rodio::mixer::Mixer,
+    bus: Option<&'a Bus>,
+) -> &'a rodio::mixer::Mixer {
+    bus.map_or(device, |bus| &bus.0.mixer)
+}
+
+/// Whether `a` and `b` are the same bus, or both no bus.
+pub(crate) fn same_bus(a: Option<&Bus>, b: Option<&Bus>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(&a.0, &b.0),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl fmt::Debug for Bus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bus")
+            .field("name", &self.0.name)
+            .field("voices", &self.active_voices())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Counts `source` as playing on `bus`, if any, until the output drops it, and as sounding
+/// while it's producing output. The player it's appended to has to be connected to the
+/// [`bus_mixer`] for it to be heard through the bus.
+pub(crate) fn route<S>(source: S, bus: Option<&Bus>) -> impl rodio::Source + Send + 'static
+where
+    S: rodio::Source + Send + 'static,
+{
+    let voice = bus.map(Bus::voice);
+    source.periodic_access(BUS_UPDATE_INTERVAL, move |_| {
+        if let Some(voice) = &voice {
+            voice.mark(Instant::now());
+        }
+    })
+}
+
+/// A sound playing on a [`Bus`]; lives inside the rodio source so that it is dropped
+/// on the audio thread once the sound is done.
+#[derive(Debug)]
+struct BusVoice(Bus);
+
+impl BusVoice {
+    /// Notes that the sound produced output at `now`, which it's only asked to while it does.
+    fn mark(&self, now: Instant) {
+        let bus = &self.0;
+        let _ = bus
+            .0
+            .last_output
+            .fetch_max(bus.since_created(now), Ordering::Relaxed);
+    }
+}
+
+impl Drop for BusVoice {
+    fn drop(&mut self) {
+        let _ = self.0 .0.voices.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 0.01, "{a} != {b}");
+    }
+
+    fn stereo_bus(name: &str) -> (Bus, impl rodio::Source) {
+        Bus::new(
+            name,
+            rodio::ChannelCount::new(2).unwrap(),
+            rodio::SampleRate::new(44100).unwrap(),
+        )
+    }
+
+    #[test]
+    fn headless_test_bus_gain() {
+        let (music, _) = stereo_bus("music");
+        let (dialogue, _) = stereo_bus("dialogue");
+        let now = Instant::now();
+        assert_close(music.gain_at(now), 1.0);
+
+        music.set_volume(0.5);
+        assert_close(music.gain_at(now), 0.5);
+        music.set_muted(true);
+        assert_close(music.gain_at(now), 0.0);
+        music.set_muted(false);
+
+        music.fade_to(1.0, Duration::from_secs(10));
+        let now = Instant::now();
+        assert_close(music.gain_at(now + Duration::from_secs(5)), 0.75);
+        assert_close(music.gain_at(now + Duration::from_secs(20)), 1.0);
+        music.set_volume(1.0);
+
+        let ducking = Ducking {
+            level: 0.2,
+            attack: Duration::from_secs(1),
+            release: Duration::from_secs(2),
+        };
+        music.set_ducking(&dialogue, ducking);
+        let voice = dialogue.voice();
+        assert_eq!(dialogue.active_voices(), 1);
+        let now = Instant::now();
+
+        // The dialogue plays for a second, asking for its gain as it goes.
+        for ms in (0..=1000).step_by(50) {
+            voice.mark(now + Duration::from_millis(ms));
+        }
+        assert_close(music.gain_at(now), 1.0);
+        assert_close(music.gain_at(now + Duration::from_millis(500)), 0.6);
+        assert_close(music.gain_at(now + Duration::from_secs(1)), 0.2);
+
+        // Then it's paused; it's still queued, but doesn't duck the music anymore.
+        let now = now + Duration::from_secs(1) + SIDECHAIN_HOLD * 2;
+        assert_eq!(dialogue.active_voices(), 1);
+        assert_close(music.gain_at(now), 0.2);
+        assert_close(music.gain_at(now + Duration::from_secs(1)), 0.6);
+        assert_close(music.gain_at(now + Duration::from_secs(2)), 1.0);
+
+        drop(voice);
+        assert_eq!(dialogue.active_voices(), 0);
+    }
+
+    #[test]
+    fn headless_test_bus_mix() {
+        use super::super::Effect;
+        use rodio::{buffer::SamplesBuffer, Source};
+
+        let (sfx, mut output) = stereo_bus("sfx");
+        let _ = sfx
+            .effects()
+            .push(Effect::Distortion {
+                gain: 1.0,
+                threshold: 0.5,
+            })
+            .unwrap();
+        sfx.set_volume(0.5);
+
+        // Two sounds mixed on the bus are clipped together, then turned down.
+        for _ in 0..2 {
+            sfx.0.mixer.add(SamplesBuffer::new(
+                output.channels(),
+                output.sample_rate(),
+                vec![0.4; 4],
+            ));
+        }
+        let samples: Vec<f32> = output.by_ref().take(6).collect();
+        assert_eq!(samples, [0.25, 0.25, 0.25, 0.25, 0.0, 0.0]);
+
+        assert!(same_bus(Some(&sfx), Some(&sfx.clone())));
+        assert!(same_bus(None, None));
+        assert!(!same_bus(Some(&sfx), None));
+        assert!(!same_bus(Some(&sfx), Some(&stereo_bus("sfx").0)));
+    }
+}
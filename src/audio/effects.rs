@@ -0,0 +1,703 @@
+use crate::error::{GameError, GameResult};
+use std::{
+    f32::consts::{FRAC_1_SQRT_2, PI},
+    fmt, mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// The longest [`Effect::Delay`] time.
+pub const MAX_DELAY_TIME: Duration = Duration::from_secs(10);
+
+/// How long a sound keeps ringing out through delays and reverbs after it ends, at most.
+const MAX_TAIL: Duration = Duration::from_secs(10);
+
+/// The amplitude below which a ringing out sound counts as silent.
+const SILENCE: f32 = 1e-4;
+
+/// An audio effect, see [`Effects`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Effect {
+    /// Lets through frequencies below `cutoff`, muffling the sound, like behind a closed door
+    /// or underwater.
+    LowPass {
+        /// The cutoff frequency in Hz.
+        cutoff: f32,
+    },
+    /// Lets through frequencies above `cutoff`, thinning the sound, like through a telephone.
+    HighPass {
+        /// The cutoff frequency in Hz.
+        cutoff: f32,
+    },
+    /// Adds the reflections of a room.
+    Reverb {
+        /// The size of the room from 0 to 1; bigger rooms ring longer.
+        room_size: f32,
+        /// How much of the reverberated sound is heard, from 0 (dry) to 1 (wet only).
+        mix: f32,
+    },
+    /// Repeats the sound after a delay, like an echo.
+    Delay {
+        /// The time between the sound and its first echo, up to [`MAX_DELAY_TIME`].
+        time: Duration,
+        /// How much of each echo is fed back into the next one, from 0 to below 1.
+        feedback: f32,
+        /// The volume of the echoes.
+        mix: f32,
+    },
+    /// Moves stereo sound to the left (-1) or right (1). Mono sounds are left as they are.
+    Pan(f32),
+    /// Amplifies the sound by `gain` and clips it at `threshold`.
+    Distortion {
+        /// The factor the sound is amplified by before clipping.
+        gain: f32,
+        /// The amplitude the sound is clipped at, at least 0.
+        threshold: f32,
+    },
+}
+
+impl Effect {
+    /// Checks that the parameters are in range, so that the effect can't blow up.
+    fn check(&self) -> GameResult {
+        let valid = match *self {
+            Effect::LowPass { cutoff } | Effect::HighPass { cutoff } => cutoff.is_finite(),
+            Effect::Reverb { room_size, mix } => !room_size.is_nan() && !mix.is_nan(),
+            Effect::Delay {
+                time,
+                feedback,
+                mix,
+            } => time <= MAX_DELAY_TIME && (0. ..1.).contains(&feedback) && !mix.is_nan(),
+            Effect::Pan(pan) => (-1. ..=1.).contains(&pan),
+            Effect::Distortion { gain, threshold } => !gain.is_nan() && threshold >= 0.,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(GameError::AudioError(format!(
+                "Effect parameters out of range: {self:?}"
+            )))
+        }
+    }
+}
+
+/// A chain of [`Effect`]s, applied in order to a sound or to all the sounds on a
+/// [`Bus`](super::Bus).
+///
+/// It is a handle shared with the sounds it's applied to, so that effects can be added,
+/// changed and removed while they play, e.g. to lower the cutoff of a low-pass filter
+/// while the player dives underwater. Cloning it gives another handle to the same chain.
+///
+/// ```rust,no_run
+/// # use ggez::{audio::{self, Effect, SoundSource}, Context, GameResult};
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let sfx = ctx.audio.bus("sfx");
+/// let muffle = sfx.effects().push(Effect::LowPass { cutoff: 20_000. })?;
+///
+/// let door = audio::Source::new(ctx, "/door.ogg")?;
+/// door.effects().push(Effect::Pan(-0.5))?;
+///
+/// // Later, when diving underwater.
+/// sfx.effects().set(muffle, Effect::LowPass { cutoff: 500. })?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Effects(Arc<EffectsInner>);
+
+#[derive(Default)]
+struct EffectsInner {
+    version: AtomicU64,
+    list: Mutex<Vec<Effect>>,
+}
+
+impl Effects {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn modify<T>(&self, f: impl FnOnce(&mut Vec<Effect>) -> T) -> T {
+        let result = f(&mut self.0.list.lock().unwrap());
+        let _ = self.0.version.fetch_add(1, Ordering::Release);
+        result
+    }
+
+    /// Adds an effect to the end of the chain, returning its index.
+    ///
+    /// Fails if the effect's parameters are out of range, see [`Effect`].
+    pub fn push(&self, effect: Effect) -> GameResult<usize> {
+        effect.check()?;
+        Ok(self.modify(|list| {
+            list.push(effect);
+            list.len() - 1
+        }))
+    }
+
+    /// Replaces the effect at `index`, e.g. to change its parameters.
+    ///
+    /// Replacing an effect with one of the same kind keeps its state, so filters and echoes
+    /// carry on smoothly. Fails if the effect's parameters are out of range, see [`Effect`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&self, index: usize, effect: Effect) -> GameResult {
+        effect.check()?;
+        self.modify(|list| list[index] = effect);
+        Ok(())
+    }
+
+    /// Removes the effect at `index`, shifting the ones after it down.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&self, index: usize) -> Effect {
+        self.modify(|list| list.remove(index))
+    }
+
+    /// Removes all effects.
+    pub fn clear(&self) {
+        self.modify(Vec::clear)
+    }
+
+    /// Returns the effect at `index`.
+    pub fn get(&self, index: usize) -> Option<Effect> {
+        self.0.list.lock().unwrap().get(index).copied()
+    }
+
+    /// Returns the effects in the chain.
+    pub fn to_vec(&self) -> Vec<Effect> {
+        self.0.list.lock().unwrap().clone()
+    }
+
+    /// Returns the number of effects in the chain.
+    pub fn len(&self) -> usize {
+        self.0.list.lock().unwrap().len()
+    }
+
+    /// Returns whether the chain is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for Effects {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.list.lock().unwrap().iter())
+            .finish()
+    }
+}
+
+/// A source with an [`Effects`] chain applied to it, which picks up changes to the chain at
+/// the start of each frame.
+///
+/// Once the source ends, the chain plays on until its delays and reverbs have rung out.
+pub(crate) struct EffectChain<S> {
+    source: S,
+    effects: Effects,
+    version: u64,
+    stages: Vec<(Effect, Stage)>,
+    channel: usize,
+    channels: usize,
+    sample_rate: u32,
+    tail: Option<Tail>,
+}
+
+/// The ringing out of an [`EffectChain`] after its source ended, in samples.
+#[derive(Clone, Copy, Debug)]
+struct Tail {
+    left: usize,
+    quiet: usize,
+    // How long the output has to stay quiet to be done, so that a quiet gap before the next
+    // echo doesn't cut it off.
+    quiet_needed: usize,
+}
+
+impl<S: rodio::Source> EffectChain<S> {
+    pub fn new(source: S, effects: Effects) -> Self {
+        EffectChain {
+            source,
+            effects,
+            version: u64::MAX,
+            stages: Vec::new(),
+            channel: 0,
+            channels: 0,
+            sample_rate: 0,
+            tail: None,
+        }
+    }
+
+    fn start_tail(&self) -> Tail {
+        let gap = self
+            .stages
+            .iter()
+            .map(|(_, stage)| stage.longest_gap())
+            .max()
+            .unwrap_or(0);
+        Tail {
+            left: (MAX_TAIL.as_secs_f32() * self.sample_rate as f32) as usize * self.channels,
+            quiet: 0,
+            quiet_needed: gap * self.channels,
+        }
+    }
+
+    fn rings(&self) -> bool {
+        self.stages.iter().any(|(_, stage)| stage.longest_gap() > 0)
+    }
+
+    fn update(&mut self) {
+        let channels = usize::from(u16::from(self.source.channels()));
+        let sample_rate = u32::from(self.source.sample_rate());
+        let version = self.effects.0.version.load(Ordering::Acquire);
+        if version == self.version && channels == self.channels && sample_rate == self.sample_rate {
+            return;
+        }
+
+        let format_changed = channels != self.channels || sample_rate != self.sample_rate;
+        let mut old = mem::take(&mut self.stages).into_iter();
+        for effect in self.effects.to_vec() {
+            let stage = match old.next() {
+                Some((old_effect, mut stage))
+                    if !format_changed
+                        && mem::discriminant(&old_effect) == mem::discriminant(&effect) =>
+                {
+                    stage.configure(&effect, sample_rate);
+                    stage
+                }
+                _ => Stage::new(&effect, channels, sample_rate),
+            };
+            self.stages.push((effect, stage));
+        }
+
+        self.version = version;
+        self.channels = channels;
+        self.sample_rate = sample_rate;
+    }
+}
+
+impl<S: rodio::Source> Iterator for EffectChain<S> {
+    type Item = rodio::Sample;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.channel == 0 {
+            match &mut self.tail {
+                Some(tail) if tail.left == 0 || tail.quiet >= tail.quiet_needed => {
+                    tail.left = 0;
+                    return None;
+                }
+                Some(_) => {}
+                None => self.update(),
+            }
+        }
+
+        let mut sample = match self.tail {
+            Some(ref mut tail) => {
+                tail.left -= 1;
+                0.
+            }
+            None => match self.source.next() {
+                Some(sample) => sample,
+                None if self.channel == 0 && self.rings() => {
+                    let mut tail = self.start_tail();
+                    tail.left = tail.left.saturating_sub(1);
+                    self.tail = Some(tail);
+                    0.
+                }
+                None => return None,
+            },
+        };
+
+        let channel = self.channel;
+        self.channel = (self.channel + 1) % self.channels.max(1);
+        for (effect, stage) in &mut self.stages {
+            sample = stage.process(effect, channel, self.channels, sample);
+        }
+
+        if let Some(tail) = &mut self.tail {
+            if sample.abs() < SILENCE {
+                tail.quiet += 1;
+            } else {
+                tail.quiet = 0;
+            }
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S: rodio::Source> rodio::Source for EffectChain<S> {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        match self.tail {
+            Some(tail) => Some(tail.left),
+            None => self.source.current_span_len(),
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> rodio::ChannelCount {
+        // Keep the format the tail is played in, whatever the ended source reports.
+        match self.tail {
+            Some(_) => rodio::ChannelCount::new(self.channels as u16).unwrap(),
+            None => self.source.channels(),
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> rodio::SampleRate {
+        match self.tail {
+            Some(_) => rodio::SampleRate::new(self.sample_rate).unwrap(),
+            None => self.source.sample_rate(),
+        }
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        if self.rings() {
+            None
+        } else {
+            self.source.total_duration()
+        }
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.source.try_seek(pos)?;
+        // Don't carry echoes and filter state over to the new position.
+        self.stages.clear();
+        self.version = u64::MAX;
+        self.channel = 0;
+        self.tail = None;
+        Ok(())
+    }
+}
+
+/// The running state of an effect, with one filter or delay line per channel.
+enum Stage {
+    Filter(Vec<Biquad>),
+    Delay(Vec<DelayLine>),
+    Reverb(Vec<Reverb>),
+    Stateless,
+}
+
+impl Stage {
+    fn new(effect: &Effect, channels: usize, sample_rate: u32) -> Self {
+        let mut stage = match effect {
+            Effect::LowPass { .. } | Effect::HighPass { .. } => {
+                Stage::Filter(vec![Biquad::default(); channels])
+            }
+            Effect::Delay { .. } => {
+                Stage::Delay((0..channels).map(|_| DelayLine::new(1)).collect())
+            }
+            Effect::Reverb { .. } => Stage::Reverb(
+                (0..channels)
+                    .map(|channel| Reverb::new(channel, sample_rate))
+                    .collect(),
+            ),
+            Effect::Pan(_) | Effect::Distortion { .. } => Stage::Stateless,
+        };
+        stage.configure(effect, sample_rate);
+        stage
+    }
+
+    /// Returns the longest the stage can stay silent before more of its tail comes out, in
+    /// frames, or 0 if it has no tail.
+    fn longest_gap(&self) -> usize {
+        let longest = match self {
+            Stage::Delay(lines) => lines.iter().map(|line| line.buffer.len()).max(),
+            Stage::Reverb(reverbs) => reverbs
+                .iter()
+                .flat_map(|reverb| &reverb.combs)
+                .map(|(line, _)| line.buffer.len())
+                .max(),
+            Stage::Filter(_) | Stage::Stateless => None,
+        };
+        longest.unwrap_or(0)
+    }
+
+    fn configure(&mut self, effect: &Effect, sample_rate: u32) {
+        match (self, effect) {
+            (Stage::Filter(filters), Effect::LowPass { cutoff }) => {
+                for filter in filters {
+                    filter.configure(false, *cutoff, sample_rate);
+                }
+            }
+            (Stage::Filter(filters), Effect::HighPass { cutoff }) => {
+                for filter in filters {
+                    filter.configure(true, *cutoff, sample_rate);
+                }
+            }
+            (Stage::Delay(lines), Effect::Delay { time, .. }) => {
+                let len = (time.as_secs_f32() * sample_rate as f32).round() as usize;
+                for line in lines {
+                    line.resize(len);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn process(&mut self, effect: &Effect, channel: usize, channels: usize, x: f32) -> f32 {
+        match (self, *effect) {
+            (Stage::Filter(filters), _) => filters[channel].process(x),
+            (Stage::Delay(lines), Effect::Delay { feedback, mix, .. }) => {
+                let line = &mut lines[channel];
+                let echo = line.read();
+                line.write(x + echo * feedback);
+                x + echo * mix
+            }
+            (Stage::Reverb(reverbs), Effect::Reverb { room_size, mix }) => {
+                let wet = reverbs[channel].process(x, room_size);
+                x * (1. - mix) + wet * mix
+            }
+            (_, Effect::Pan(pan)) if channels >= 2 => match channel {
+                0 => x * (1. - pan).min(1.),
+                1 => x * (1. + pan).min(1.),
+                _ => x,
+            },
+            (_, Effect::Distortion { gain, threshold }) => (x * gain).clamp(-threshold, threshold),
+            _ => x,
+        }
+    }
+}
+
+/// A second order low or high-pass filter, from the Audio EQ Cookbook.
+#[derive(Clone, Copy, Debug, Default)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    fn configure(&mut self, high_pass: bool, cutoff: f32, sample_rate: u32) {
+        let sample_rate = sample_rate as f32;
+        let w0 = 2. * PI * cutoff.clamp(10., sample_rate * 0.49) / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2. * FRAC_1_SQRT_2);
+        let a0 = 1. + alpha;
+
+        let (b0, b1) = if high_pass {
+            ((1. + cos) / 2., -(1. + cos))
+        } else {
+            ((1. - cos) / 2., 1. - cos)
+        };
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b0 / a0;
+        self.a1 = -2. * cos / a0;
+        self.a2 = (1. - alpha) / a0;
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+#[derive(Debug)]
+struct DelayLine {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl DelayLine {
+    fn new(len: usize) -> Self {
+        DelayLine {
+            buffer: vec![0.; len.max(1)],
+            index: 0,
+        }
+    }
+
+    fn resize(&mut self, len: usize) {
+        if len.max(1) != self.buffer.len() {
+            *self = DelayLine::new(len);
+        }
+    }
+
+    /// Returns the sample written `len` samples ago.
+    #[inline]
+    fn read(&self) -> f32 {
+        self.buffer[self.index]
+    }
+
+    #[inline]
+    fn write(&mut self, x: f32) {
+        self.buffer[self.index] = x;
+        self.index = (self.index + 1) % self.buffer.len();
+    }
+}
+
+/// A small Schroeder reverb in the style of Freeverb: parallel damped comb filters
+/// followed by allpass filters.
+#[derive(Debug)]
+struct Reverb {
+    combs: Vec<(DelayLine, f32)>,
+    allpasses: Vec<DelayLine>,
+}
+
+impl Reverb {
+    const COMBS: [usize; 4] = [1116, 1188, 1277, 1356];
+    const ALLPASSES: [usize; 2] = [556, 441];
+    const DAMPING: f32 = 0.2;
+
+    fn new(channel: usize, sample_rate: u32) -> Self {
+        // The tunings are for 44.1kHz; every other channel is spread a little so that
+        // stereo sounds wider.
+        let spread = if channel % 2 == 1 { 23 } else { 0 };
+        let line = |len: usize| DelayLine::new((len + spread) * sample_rate as usize / 44_100);
+        Reverb {
+            combs: Self::COMBS.iter().map(|&len| (line(len), 0.)).collect(),
+            allpasses: Self::ALLPASSES.iter().map(|&len| line(len)).collect(),
+        }
+    }
+
+    fn process(&mut self, x: f32, room_size: f32) -> f32 {
+        let feedback = 0.7 + 0.28 * room_size.clamp(0., 1.);
+        let input = x * 0.015;
+
+        let mut wet = 0.;
+        for (line, damped) in &mut self.combs {
+            let out = line.read();
+            *damped = out * (1. - Self::DAMPING) + *damped * Self::DAMPING;
+            line.write(input + *damped * feedback);
+            wet += out;
+        }
+        for line in &mut self.allpasses {
+            let out = line.read();
+            line.write(wet + out * 0.5);
+            wet = out - wet;
+        }
+        wet * 3.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(effect: Effect, channels: usize, input: &[f32]) -> Vec<f32> {
+        let mut stage = Stage::new(&effect, channels, 44_100);
+        input
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| stage.process(&effect, i % channels, channels, x))
+            .collect()
+    }
+
+    #[test]
+    fn headless_test_effects() {
+        // A constant signal passes a low-pass filter and is removed by a high-pass one.
+        let dc = vec![1.; 4410];
+        let low = run(Effect::LowPass { cutoff: 1000. }, 1, &dc);
+        assert!((low.last().unwrap() - 1.).abs() < 0.01);
+        let high = run(Effect::HighPass { cutoff: 1000. }, 1, &dc);
+        assert!(high.last().unwrap().abs() < 0.01);
+
+        // An impulse comes back after the delay time, quieter each time.
+        let mut impulse = vec![0.; 100];
+        impulse[0] = 1.;
+        let delay = Effect::Delay {
+            time: Duration::from_secs_f32(10. / 44_100.),
+            feedback: 0.5,
+            mix: 1.,
+        };
+        let echoes = run(delay, 1, &impulse);
+        assert_eq!(echoes[0], 1.);
+        assert_eq!(echoes[10], 1.);
+        assert_eq!(echoes[20], 0.5);
+        assert_eq!(echoes[5], 0.);
+
+        // Panning turns down the other side of a stereo sound.
+        let panned = run(Effect::Pan(-0.5), 2, &[1., 1.]);
+        assert_eq!(panned, [1., 0.5]);
+        assert_eq!(run(Effect::Pan(-0.5), 1, &[1.]), [1.]);
+
+        let distorted = run(
+            Effect::Distortion {
+                gain: 4.,
+                threshold: 0.5,
+            },
+            1,
+            &[0.1, 0.5, -0.5],
+        );
+        assert_eq!(distorted, [0.4, 0.5, -0.5]);
+
+        // The reverb tail keeps ringing after the impulse.
+        let mut impulse = vec![0.; 4410];
+        impulse[0] = 1.;
+        let reverb = run(
+            Effect::Reverb {
+                room_size: 0.5,
+                mix: 1.,
+            },
+            1,
+            &impulse,
+        );
+        assert!(reverb[2000..].iter().any(|x| x.abs() > 1e-4));
+
+        // A sound rings out through its echoes after it ends, then stops.
+        let effects = Effects::new();
+        let _ = effects.push(delay).unwrap();
+        let source = rodio::buffer::SamplesBuffer::new(
+            rodio::ChannelCount::new(1).unwrap(),
+            rodio::SampleRate::new(44_100).unwrap(),
+            vec![1.],
+        );
+        let out: Vec<f32> = EffectChain::new(source, effects).collect();
+        assert!(out.len() > 40 && out.len() < 1000, "{}", out.len());
+        assert_eq!(out[10], 1.);
+        assert_eq!(out[20], 0.5);
+
+        // Effects that could blow up are rejected.
+        let effects = Effects::new();
+        let bad = [
+            Effect::Delay {
+                time: Duration::from_secs(1),
+                feedback: 1.,
+                mix: 1.,
+            },
+            Effect::Delay {
+                time: MAX_DELAY_TIME * 2,
+                feedback: 0.5,
+                mix: 1.,
+            },
+            Effect::Distortion {
+                gain: 2.,
+                threshold: -1.,
+            },
+            Effect::Distortion {
+                gain: 2.,
+                threshold: f32::NAN,
+            },
+            Effect::LowPass { cutoff: f32::NAN },
+        ];
+        for effect in bad {
+            assert!(effects.push(effect).is_err(), "{effect:?}");
+        }
+        assert!(effects.is_empty());
+        let index = effects.push(Effect::Pan(0.5)).unwrap();
+        assert!(effects.set(index, Effect::Pan(2.)).is_err());
+        assert_eq!(effects.get(index), Some(Effect::Pan(0.5)));
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    f32::consts::FRAC_PI_2,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How often a playing sound picks up changes to its fade.
+const FADE_UPDATE_INTERVAL: Duration = Duration::from_millis(5);
+
+/// The shape of a fade.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FadeCurve {
+    /// Changes the gain at a constant rate.
+    #[default]
+    Linear,
+    /// Keeps the combined power of two crossfading sounds constant, so that there is no dip
+    /// in loudness halfway through. Best for crossfading between unrelated tracks.
+    EqualPower,
+    /// Starts and ends slowly, changing fastest halfway through.
+    SCurve,
+}
+
+impl FadeCurve {
+    /// Returns the gain of a fade in with this curve at `t`, going from 0 to 1.
+    ///
+    /// Fading out follows the same curve backwards, i.e. `curve.apply(1. - t)`.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::EqualPower => (t * FRAC_PI_2).sin(),
+            FadeCurve::SCurve => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+/// A change in the gain of a playing sound, on top of its volume,
+/// see [`SoundSource::fade`](super::SoundSource::fade).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fade {
+    /// The gain to fade to.
+    pub gain: f32,
+    /// How long the fade takes.
+    pub duration: Duration,
+    /// The shape of the fade.
+    pub curve: FadeCurve,
+    /// The gain to start from, or `None` to start from wherever the gain is now.
+    pub start: Option<f32>,
+    /// Whether to stop the sound once it's done fading.
+    pub stop: bool,
+}
+
+impl Fade {
+    /// Fades to `gain` over `duration`.
+    pub fn to(gain: f32, duration: Duration) -> Self {
+        Fade {
+            gain,
+            duration,
+            curve: FadeCurve::Linear,
+            start: None,
+            stop: false,
+        }
+    }
+
+    /// Fades out to silence over `duration`.
+    pub fn out(duration: Duration) -> Self {
+        Fade::to(0., duration)
+    }
+
+    /// Sets the shape of the fade.
+    pub fn curve(mut self, curve: FadeCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Starts the fade from `gain` instead of from the current gain, e.g. 0 to fade in.
+    pub fn starting_at(mut self, gain: f32) -> Self {
+        self.start = Some(gain);
+        self
+    }
+
+    /// Stops the sound once it's done fading.
+    pub fn then_stop(mut self) -> Self {
+        self.stop = true;
+        self
+    }
+}
+
+/// A change of a value from `from` to `to` over `duration`, starting at `start`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Ramp {
+    pub from: f32,
+    pub to: f32,
+    start: Instant,
+    duration: Duration,
+    curve: FadeCurve,
+}
+
+impl Ramp {
+    pub fn constant(value: f32) -> Self {
+        Ramp {
+            from: value,
+            to: value,
+            start: Instant::now(),
+            duration: Duration::ZERO,
+            curve: FadeCurve::Linear,
+        }
+    }
+
+    /// Starts a new ramp towards `to` from wherever this one is at `now`.
+    pub fn retarget(&self, to: f32, duration: Duration, curve: FadeCurve, now: Instant) -> Self {
+        Ramp {
+            from: self.value(now),
+            to,
+            start: now,
+            duration,
+            curve,
+        }
+    }
+
+    pub fn finished(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.duration
+    }
+
+    pub fn value(&self, now: Instant) -> f32 {
+        if self.finished(now) {
+            return self.to;
+        }
+
+        let t =
+            now.saturating_duration_since(self.start).as_secs_f32() / self.duration.as_secs_f32();
+        // Fading down runs the curve backwards, so that the two halves of a crossfade mirror
+        // each other.
+        let k = if self.to >= self.from {
+            self.curve.apply(t)
+        } else {
+            1. - self.curve.apply(1. - t)
+        };
+        self.from + (self.to - self.from) * k
+    }
+}
+
+/// The fade of a source, shared with the sounds it plays so that it can be changed while
+/// they do.
+#[derive(Clone, Debug)]
+pub(crate) struct FadeHandle(Arc<Mutex<FadeState>>);
+
+#[derive(Debug)]
+struct FadeState {
+    ramp: Ramp,
+    stop: bool,
+}
+
+impl FadeHandle {
+    pub fn new() -> Self {
+        FadeHandle(Arc::new(Mutex::new(FadeState {
+            ramp: Ramp::constant(1.),
+            stop: false,
+        })))
+    }
+
+    pub fn start(&self, fade: Fade) {
+        let now = Instant::now();
+        let mut state = self.0.lock().unwrap();
+        if let Some(start) = fade.start {
+            state.ramp = Ramp::constant(start);
+        }
+        state.ramp = state
+            .ramp
+            .retarget(fade.gain, fade.duration, fade.curve, now);
+        state.stop = fade.stop;
+    }
+
+    /// Back to full gain, e.g. when the source is stopped and played again.
+    pub fn reset(&self) {
+        *self.0.lock().unwrap() = FadeState {
+            ramp: Ramp::constant(1.),
+            stop: false,
+        };
+    }
+
+    /// Applies the fade to `source`, stopping it when a fade that should stop it is done.
+    pub fn apply<S>(&self, source: S) -> impl rodio::Source + Send + 'static
+    where
+        S: rodio::Source + Send + 'static,
+    {
+        use rodio::Source;
+
+        let fade = self.clone();
+        source
+            .amplify(1.)
+            .stoppable()
+            .periodic_access(FADE_UPDATE_INTERVAL, move |source| {
+                let now = Instant::now();
+                let state = fade.0.lock().unwrap();
+                source.inner_mut().set_factor(state.ramp.value(now));
+                if state.stop && state.ramp.finished(now) {
+                    source.stop();
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_fade_curves() {
+        for curve in [FadeCurve::Linear, FadeCurve::EqualPower, FadeCurve::SCurve] {
+            assert_eq!(curve.apply(0.), 0.);
+            assert!((curve.apply(1.) - 1.).abs() < 1e-6);
+            assert!(curve.apply(0.25) < curve.apply(0.75));
+        }
+
+        // An equal power crossfade keeps the summed power constant.
+        let now = Instant::now();
+        let second = Duration::from_secs(1);
+        let fade_in = Ramp::constant(0.).retarget(1., second, FadeCurve::EqualPower, now);
+        let fade_out = Ramp::constant(1.).retarget(0., second, FadeCurve::EqualPower, now);
+        for ms in [0, 250, 500, 750, 1000] {
+            let t = now + Duration::from_millis(ms);
+            let power = fade_in.value(t).powi(2) + fade_out.value(t).powi(2);
+            assert!((power - 1.).abs() < 1e-3, "{power} at {ms}ms");
+        }
+        assert!(!fade_out.finished(now + second / 2));
+        assert!(fade_out.finished(now + second));
+        assert_eq!(fade_out.value(now + second * 2), 0.);
+    }
+}
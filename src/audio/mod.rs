@@ -3,21 +3,41 @@
 //! It consists of two main types: [`SoundData`](struct.SoundData.html)
 //! is just an array of raw sound data bytes, and a [`Source`](struct.Source.html) is a
 //! `SoundData` connected to a particular sound channel ready to be played.
+//!
+//! Long music tracks can be played with a [`StreamingSource`] instead, which decodes them
+//! from their file as they play. Sources can be routed to named mixer [`Bus`]es, to set the
+//! volume of all the music or all the sound effects at once, and sources and buses alike
+//! can have a chain of [`Effects`] such as filters, reverb and echo.
 #![cfg(feature = "audio")]
 
+use std::collections::HashMap;
 use std::fmt;
 use std::io;
 use std::path;
 use std::time;
 
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use crate::context::Has;
 use crate::error::GameError;
 use crate::error::GameResult;
 use crate::filesystem::Filesystem;
 
+mod bus;
+mod effects;
+mod fade;
+mod music;
+mod pool;
+mod stream;
+
+pub use bus::*;
+pub use effects::*;
+pub use fade::*;
+pub use music::*;
+pub use pool::*;
+pub use stream::*;
+
 /// A struct that contains all information for tracking sound info.
 ///
 /// You generally don't have to create this yourself, it will be part
@@ -25,6 +45,10 @@ use crate::filesystem::Filesystem;
 pub struct AudioContext {
     fs: Filesystem,
     stream: rodio::MixerDeviceSink,
+    buses: HashMap<String, Bus>,
+    music: MusicPlayer,
+    voices: VoiceCount,
+    max_voices: Option<usize>,
 }
 
 impl AudioContext {
@@ -38,6 +62,10 @@ impl AudioContext {
         Ok(Self {
             fs: fs.clone(),
             stream,
+            buses: HashMap::new(),
+            music: MusicPlayer::default(),
+            voices: VoiceCount::default(),
+            max_voices: None,
         })
     }
 }
@@ -48,6 +76,64 @@ impl AudioContext {
     pub fn device(&self) -> &rodio::MixerDeviceSink {
         &self.stream
     }
+
+    /// Returns the mixer bus called `name`, creating it at full volume if it doesn't exist yet.
+    pub fn bus(&mut self, name: &str) -> Bus {
+        let stream = &self.stream;
+        self.buses
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                let config = stream.config();
+                let (bus, output) = Bus::new(name, config.channel_count(), config.sample_rate());
+                stream.mixer().add(output);
+                bus
+            })
+            .clone()
+    }
+
+    /// Returns the mixer bus called `name`, if it was created.
+    pub fn get_bus(&self, name: &str) -> Option<&Bus> {
+        self.buses.get(name)
+    }
+
+    /// Returns an iterator over the mixer buses created so far.
+    pub fn buses(&self) -> impl Iterator<Item = &Bus> {
+        self.buses.values()
+    }
+
+    /// Returns the music player, which crossfades from one piece of music to the next.
+    #[inline]
+    pub fn music(&mut self) -> &mut MusicPlayer {
+        &mut self.music
+    }
+
+    /// Limits the number of voices all [`SoundPool`]s play together, or lifts the limit
+    /// with `None`. Once it's reached, pools cut off their own voices to play new sounds.
+    pub fn set_max_voices(&mut self, max_voices: Option<usize>) {
+        self.max_voices = max_voices;
+    }
+
+    /// Returns the limit on the number of voices played by all [`SoundPool`]s together.
+    #[inline]
+    pub fn max_voices(&self) -> Option<usize> {
+        self.max_voices
+    }
+
+    /// Returns the number of voices all [`SoundPool`]s are playing together.
+    #[inline]
+    pub fn active_voices(&self) -> usize {
+        self.voices.get()
+    }
+}
+
+/// Connects a new player to `mixer`, with the volume and pause state of `old`.
+fn reconnect(old: &rodio::Player, mixer: &rodio::mixer::Mixer) -> rodio::Player {
+    let sink = rodio::Player::connect_new(mixer);
+    sink.set_volume(old.volume());
+    if old.is_paused() {
+        sink.pause();
+    }
+    sink
 }
 
 impl fmt::Debug for AudioContext {
@@ -93,7 +179,7 @@ impl AsRef<[u8]> for SoundData {
 }
 
 /// A trait defining the operations possible on a sound;
-/// it is implemented by both `Source` and `SpatialSource`.
+/// it is implemented by `Source`, `SpatialSource` and `StreamingSource`.
 pub trait SoundSource {
     /// Plays the audio source; restarts the sound if currently playing
     fn play(&self) {
@@ -102,11 +188,22 @@ pub trait SoundSource {
         self.resume();
     }
 
+    /// Plays the audio source from the start like [`play()`](#method.play), with `fade` already
+    /// applied to its first sample, e.g. to fade it in from silence without a pop.
+    fn play_with_fade(&self, fade: Fade) {
+        self.stop();
+        self.fade(fade);
+        self.play_later();
+        self.resume();
+    }
+
     /// Plays the `SoundSource`; waits until done if the sound is currently playing
     fn play_later(&self);
 
     /// Play source "in the background"; cannot be stopped
-    fn play_detached(self);
+    fn play_detached(self)
+    where
+        Self: Sized;
 
     /// Sets the source to repeat playback infinitely on next [`play()`](#method.play)
     fn set_repeat(&mut self, repeat: bool);
@@ -137,9 +234,25 @@ pub trait SoundSource {
     /// Resumes playback
     fn resume(&self);
 
-    /// Stops playback
+    /// Stops playback, cutting the sound off; see [`stop_with_fade()`](#method.stop_with_fade)
+    /// to fade it out first.
     fn stop(&self);
 
+    /// Changes the gain of the sound, on top of its volume, over time. The fade is undone when
+    /// the source is stopped or played again with [`play()`](#method.play).
+    fn fade(&self, fade: Fade);
+
+    /// Fades the sound out to silence over `dur`. It keeps playing silently until it's stopped
+    /// or played again.
+    fn fade_out(&self, dur: time::Duration) {
+        self.fade(Fade::out(dur));
+    }
+
+    /// Fades the sound out over `dur`, then stops it.
+    fn stop_with_fade(&self, dur: time::Duration) {
+        self.fade(Fade::out(dur).then_stop());
+    }
+
     /// Returns whether or not the source is stopped
     /// -- that is, has no more data to play.
     fn stopped(&self) -> bool;
@@ -166,6 +279,21 @@ pub trait SoundSource {
     ///
     /// This parameter determines the precision of the time measured by [`elapsed()`](#method.elapsed).
     fn set_query_interval(&mut self, t: time::Duration);
+
+    /// Routes the source through a mixer [`Bus`], or straight to the output with `None`.
+    ///
+    /// Moving a source to another bus stops it if it's playing.
+    fn set_bus(&mut self, bus: Option<Bus>);
+
+    /// Returns the mixer bus the source is routed through, if any.
+    fn bus(&self) -> Option<&Bus>;
+
+    /// Returns the chain of effects applied to the source, which can be changed while it plays.
+    fn effects(&self) -> &Effects;
+
+    /// Replaces the chain of effects applied to the source from the next
+    /// [`play()`](#method.play) on, e.g. to share one chain between several sources.
+    fn set_effects(&mut self, effects: Effects);
 }
 
 /// Internal state used by audio sources.
@@ -178,6 +306,10 @@ struct SourceState {
     speed: f32,
     query_interval: time::Duration,
     play_time: Arc<AtomicU64>,
+    bus: Option<Bus>,
+    effects: Effects,
+    fade: FadeHandle,
+    voices: Option<VoiceCount>,
 }
 
 impl SourceState {
@@ -191,6 +323,10 @@ impl SourceState {
             speed: 1.0,
             query_interval: time::Duration::from_millis(100),
             play_time: Arc::new(AtomicU64::new(0)),
+            bus: None,
+            effects: Effects::new(),
+            fade: FadeHandle::new(),
+            voices: None,
         }
     }
 
@@ -234,10 +370,31 @@ impl SourceState {
         self.query_interval = t;
     }
 
+    /// Sets the mixer bus the source is routed through.
+    pub fn set_bus(&mut self, bus: Option<Bus>) {
+        self.bus = bus;
+    }
+
+    /// Returns the mixer bus the source is routed through.
+    pub fn bus(&self) -> Option<&Bus> {
+        self.bus.as_ref()
+    }
+
+    /// Returns the effects applied to the source.
+    pub fn effects(&self) -> &Effects {
+        &self.effects
+    }
+
+    /// Replaces the effects applied to the source from the next play on.
+    pub fn set_effects(&mut self, effects: Effects) {
+        self.effects = effects;
+    }
+
     fn to_source(&self) -> impl rodio::Source + Send + 'static {
         use rodio::Source;
 
         let counter = self.play_time.clone();
+        let ticket = self.voices.as_ref().map(VoiceCount::ticket);
         let period_mus = self.query_interval.as_micros() as u64;
         // We can't give zero here so give 1µs which is quite the same
         let fade_in = self.fade_in.max(time::Duration::from_micros(1));
@@ -254,13 +411,19 @@ impl SourceState {
             Box::new(decoder)
         };
 
-        source
+        let source = source
             .skip_duration(self.skip_duration)
             .speed(self.speed)
-            .fade_in(fade_in)
-            .periodic_access(self.query_interval, move |_| {
-                let _ = counter.fetch_add(period_mus, Ordering::Relaxed);
-            })
+            .fade_in(fade_in);
+        let source = self
+            .fade
+            .apply(EffectChain::new(source, self.effects.clone()));
+
+        route(source, self.bus.as_ref()).periodic_access(self.query_interval, move |_| {
+            // Holds on to the pool voice ticket for as long as the sound plays.
+            let _ = &ticket;
+            let _ = counter.fetch_add(period_mus, Ordering::Relaxed);
+        })
     }
 }
 
@@ -272,6 +435,7 @@ impl SourceState {
 // but for now it is just an in-memory SoundData structure.
 pub struct Source {
     sink: rodio::Player,
+    device: rodio::mixer::Mixer,
     state: SourceState,
 }
 
@@ -286,8 +450,13 @@ impl Source {
     /// Creates a new `Source` using the given `SoundData` object.
     pub fn from_data(audio: &impl Has<AudioContext>, data: SoundData) -> GameResult<Self> {
         let state = SourceState::new(data);
-        let sink = rodio::Player::connect_new(audio.retrieve().stream.mixer());
-        Ok(Source { sink, state })
+        let device = audio.retrieve().stream.mixer().clone();
+        let sink = rodio::Player::connect_new(&device);
+        Ok(Source {
+            sink,
+            device,
+            state,
+        })
     }
 }
 
@@ -325,9 +494,14 @@ impl SoundSource for Source {
 
     fn stop(&self) {
         self.state.play_time.store(0, Ordering::SeqCst);
+        self.state.fade.reset();
         self.sink.clear();
     }
 
+    fn fade(&self, fade: Fade) {
+        self.state.fade.start(fade);
+    }
+
     fn stopped(&self) -> bool {
         self.sink.empty()
     }
@@ -355,6 +529,26 @@ impl SoundSource for Source {
     fn set_query_interval(&mut self, t: time::Duration) {
         self.state.set_query_interval(t)
     }
+
+    fn set_bus(&mut self, bus: Option<Bus>) {
+        if !same_bus(self.state.bus(), bus.as_ref()) {
+            self.stop();
+            self.sink = reconnect(&self.sink, bus_mixer(&self.device, bus.as_ref()));
+        }
+        self.state.set_bus(bus)
+    }
+
+    fn bus(&self) -> Option<&Bus> {
+        self.state.bus()
+    }
+
+    fn effects(&self) -> &Effects {
+        self.state.effects()
+    }
+
+    fn set_effects(&mut self, effects: Effects) {
+        self.state.set_effects(effects)
+    }
 }
 
 impl fmt::Debug for Source {
@@ -367,6 +561,9 @@ impl fmt::Debug for Source {
 /// Will stop playing when dropped.
 pub struct SpatialSource {
     sink: rodio::SpatialPlayer,
+    device: rodio::mixer::Mixer,
+    // The emitter, left ear and right ear positions, for connecting a new player.
+    positions: Mutex<[[f32; 3]; 3]>,
     state: SourceState,
 }
 
@@ -383,14 +580,34 @@ impl SpatialSource {
         let audio = audio.retrieve();
 
         let state = SourceState::new(data);
+        let device = audio.stream.mixer().clone();
+        let positions = [[0.0, 0.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]];
+        let [emitter, left_ear, right_ear] = positions;
+        let sink = rodio::SpatialPlayer::connect_new(&device, emitter, left_ear, right_ear);
+
+        Ok(SpatialSource {
+            sink,
+            device,
+            positions: Mutex::new(positions),
+            state,
+        })
+    }
+
+    /// Connects a new player to `bus`, or to the device with `None`, placed like the current
+    /// one and with its volume and pause state.
+    fn connect(&self, bus: Option<&Bus>) -> rodio::SpatialPlayer {
+        let [emitter, left_ear, right_ear] = *self.positions.lock().unwrap();
         let sink = rodio::SpatialPlayer::connect_new(
-            audio.stream.mixer(),
-            [0.0, 0.0, 0.0],
-            [-1.0, 0.0, 0.0],
-            [1.0, 0.0, 0.0],
+            bus_mixer(&self.device, bus),
+            emitter,
+            left_ear,
+            right_ear,
         );
-
-        Ok(SpatialSource { sink, state })
+        sink.set_volume(self.sink.volume());
+        if self.sink.is_paused() {
+            sink.pause();
+        }
+        sink
     }
 }
 
@@ -435,9 +652,14 @@ impl SoundSource for SpatialSource {
 
     fn stop(&self) {
         self.state.play_time.store(0, Ordering::SeqCst);
+        self.state.fade.reset();
         self.sink.clear();
     }
 
+    fn fade(&self, fade: Fade) {
+        self.state.fade.start(fade);
+    }
+
     fn stopped(&self) -> bool {
         self.sink.empty()
     }
@@ -465,6 +687,26 @@ impl SoundSource for SpatialSource {
     fn set_query_interval(&mut self, t: time::Duration) {
         self.state.set_query_interval(t)
     }
+
+    fn set_bus(&mut self, bus: Option<Bus>) {
+        if !same_bus(self.state.bus(), bus.as_ref()) {
+            self.stop();
+            self.sink = self.connect(bus.as_ref());
+        }
+        self.state.set_bus(bus)
+    }
+
+    fn bus(&self) -> Option<&Bus> {
+        self.state.bus()
+    }
+
+    fn effects(&self) -> &Effects {
+        self.state.effects()
+    }
+
+    fn set_effects(&mut self, effects: Effects) {
+        self.state.set_effects(effects)
+    }
 }
 
 impl SpatialSource {
@@ -473,7 +715,9 @@ impl SpatialSource {
     where
         P: Into<mint::Point3<f32>>,
     {
-        self.sink.set_emitter_position(pos.into().into());
+        let pos = pos.into().into();
+        self.positions.lock().unwrap()[0] = pos;
+        self.sink.set_emitter_position(pos);
     }
 
     /// Set locations of the listener's ears
@@ -481,8 +725,12 @@ impl SpatialSource {
     where
         P: Into<mint::Point3<f32>>,
     {
-        self.sink.set_left_ear_position(left.into().into());
-        self.sink.set_right_ear_position(right.into().into());
+        let (left, right) = (left.into().into(), right.into().into());
+        let mut positions = self.positions.lock().unwrap();
+        positions[1] = left;
+        positions[2] = right;
+        self.sink.set_left_ear_position(left);
+        self.sink.set_right_ear_position(right);
     }
 }
 
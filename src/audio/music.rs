@@ -0,0 +1,84 @@
+use super::{Fade, FadeCurve, SoundSource};
+use std::{fmt, time::Duration};
+
+/// Plays one piece of music at a time, crossfading from one to the next, e.g. when moving
+/// from the overworld into a battle. Get it with
+/// [`AudioContext::music`](super::AudioContext::music).
+///
+/// ```rust,no_run
+/// # use ggez::{audio::{self, FadeCurve}, Context, GameResult};
+/// # use std::time::Duration;
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let battle = audio::StreamingSource::new(ctx, "/battle.ogg")?;
+/// ctx.audio.music().set_curve(FadeCurve::EqualPower);
+/// ctx.audio.music().play(battle, Duration::from_secs(2));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct MusicPlayer {
+    current: Option<Box<dyn SoundSource>>,
+    // Kept alive until they're done fading out, since dropping a source stops it.
+    fading: Vec<Box<dyn SoundSource>>,
+    curve: FadeCurve,
+}
+
+impl MusicPlayer {
+    /// Plays `music` from the start, fading it in over `crossfade` while the music that was
+    /// playing fades out and stops.
+    pub fn play(&mut self, music: impl SoundSource + 'static, crossfade: Duration) {
+        self.fading.retain(|music| !music.stopped());
+        self.fade_out_current(crossfade);
+
+        music.play_with_fade(Fade::to(1., crossfade).starting_at(0.).curve(self.curve));
+        self.current = Some(Box::new(music));
+    }
+
+    /// Fades out the music over `fade` and stops it.
+    pub fn stop(&mut self, fade: Duration) {
+        self.fading.retain(|music| !music.stopped());
+        self.fade_out_current(fade);
+    }
+
+    fn fade_out_current(&mut self, duration: Duration) {
+        if let Some(current) = self.current.take() {
+            current.fade(Fade::out(duration).curve(self.curve).then_stop());
+            self.fading.push(current);
+        }
+    }
+
+    /// Returns the music that is playing, or was played last.
+    pub fn current(&self) -> Option<&dyn SoundSource> {
+        self.current.as_deref()
+    }
+
+    /// Returns the music that is playing, or was played last, e.g. to pause it.
+    pub fn current_mut(&mut self) -> Option<&mut (dyn SoundSource + 'static)> {
+        self.current.as_deref_mut()
+    }
+
+    /// Returns the shape of the crossfades.
+    #[inline]
+    pub fn curve(&self) -> FadeCurve {
+        self.curve
+    }
+
+    /// Sets the shape of the crossfades, [`FadeCurve::Linear`] by default.
+    #[inline]
+    pub fn set_curve(&mut self, curve: FadeCurve) {
+        self.curve = curve;
+    }
+}
+
+impl fmt::Debug for MusicPlayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MusicPlayer")
+            .field(
+                "playing",
+                &self.current.as_ref().is_some_and(|music| music.playing()),
+            )
+            .field("fading", &self.fading.len())
+            .field("curve", &self.curve)
+            .finish()
+    }
+}
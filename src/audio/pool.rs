@@ -0,0 +1,307 @@
+use super::{AudioContext, Bus, SoundData, SoundSource, Source};
+use crate::{context::Has, error::GameResult, rng::Rng};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// Which voice a [`SoundPool`] cuts off to play a new sound when it has none to spare.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StealPolicy {
+    /// Cuts off the voice that started playing first.
+    #[default]
+    Oldest,
+    /// Cuts off the voice that was played at the lowest volume.
+    Quietest,
+    /// Doesn't cut anything off; the new sound isn't played.
+    None,
+}
+
+/// Plays one [`SoundData`] on a limited number of voices, for sound effects that are
+/// triggered rapidly, like gunshots or footsteps, where
+/// [`SoundSource::play_detached`] would pile up hundreds of overlapping sounds.
+///
+/// When all voices are busy, or the [voice budget](AudioContext::set_max_voices) shared by
+/// all pools is used up, a playing voice is cut off according to the [`StealPolicy`].
+/// Each play can vary its pitch and volume a little so repeated sounds don't get monotonous.
+///
+/// ```rust,no_run
+/// # use ggez::{audio::{self, SoundPool, StealPolicy}, Context, GameResult};
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let data = audio::SoundData::new(ctx, "/pew.ogg")?;
+/// let mut pew = SoundPool::new(data, 8)
+///     .steal(StealPolicy::Oldest)
+///     .pitch_variation(0.1)
+///     .volume_variation(0.2);
+///
+/// // Whenever the player shoots.
+/// pew.play(ctx)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SoundPool {
+    data: SoundData,
+    voices: Vec<Voice>,
+    max_voices: usize,
+    steal: StealPolicy,
+    volume: f32,
+    pitch_variation: f32,
+    volume_variation: f32,
+    bus: Option<Bus>,
+    rng: Rng,
+}
+
+struct Voice {
+    source: Source,
+    started: Instant,
+    volume: f32,
+}
+
+impl SoundPool {
+    /// Creates a pool playing `data` on at most `max_voices` voices at once, stealing the
+    /// oldest voice when they're all busy, without any variation.
+    ///
+    /// The pool is randomly seeded; see [`SoundPool::seed`] to make it deterministic.
+    pub fn new(data: SoundData, max_voices: usize) -> Self {
+        SoundPool {
+            data,
+            voices: Vec::new(),
+            max_voices: max_voices.max(1),
+            steal: StealPolicy::Oldest,
+            volume: 1.,
+            pitch_variation: 0.,
+            volume_variation: 0.,
+            bus: None,
+            rng: Rng::from_entropy(),
+        }
+    }
+
+    /// Sets which voice is cut off when there are none to spare.
+    #[must_use]
+    pub fn steal(mut self, steal: StealPolicy) -> Self {
+        self.steal = steal;
+        self
+    }
+
+    /// Sets the volume the sounds are played at, before variation.
+    #[must_use]
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Varies the pitch of each play randomly by up to this fraction up or down,
+    /// e.g. 0.1 for between 0.9 and 1.1.
+    #[must_use]
+    pub fn pitch_variation(mut self, variation: f32) -> Self {
+        self.pitch_variation = variation.clamp(0., 1.);
+        self
+    }
+
+    /// Varies the volume of each play randomly by up to this fraction up or down.
+    #[must_use]
+    pub fn volume_variation(mut self, variation: f32) -> Self {
+        self.volume_variation = variation.clamp(0., 1.);
+        self
+    }
+
+    /// Routes the sounds through a mixer bus.
+    #[must_use]
+    pub fn bus(mut self, bus: Bus) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Sets the seed of the random number generator, which makes the variation deterministic.
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Plays the sound on a free voice, or one cut off according to the [`StealPolicy`],
+    /// returning whether it was played.
+    pub fn play(&mut self, audio: &impl Has<AudioContext>) -> GameResult<bool> {
+        let audio = audio.retrieve();
+        let voices = self
+            .voices
+            .iter()
+            .map(|voice| (voice.started, voice.volume, !voice.source.stopped()))
+            .collect::<Vec<_>>();
+        let budget_left = audio.max_voices.is_none_or(|max| audio.voices.get() < max);
+
+        let index = match pick_voice(&voices, self.max_voices, budget_left, self.steal) {
+            Some(PickedVoice::Existing(index)) => index,
+            Some(PickedVoice::New) => {
+                let mut source = Source::from_data(audio, self.data.clone())?;
+                source.set_bus(self.bus.clone());
+                source.state.voices = Some(audio.voices.clone());
+                self.voices.push(Voice {
+                    source,
+                    started: Instant::now(),
+                    volume: 0.,
+                });
+                self.voices.len() - 1
+            }
+            None => return Ok(false),
+        };
+
+        let mut vary = |variation: f32| 1. + variation * (self.rng.rand_float() * 2. - 1.);
+        let pitch = vary(self.pitch_variation);
+        let volume = self.volume * vary(self.volume_variation);
+
+        let voice = &mut self.voices[index];
+        voice.source.set_pitch(pitch);
+        voice.source.set_volume(volume);
+        voice.source.play();
+        voice.started = Instant::now();
+        voice.volume = volume;
+        Ok(true)
+    }
+
+    /// Stops all voices.
+    pub fn stop(&self) {
+        for voice in &self.voices {
+            voice.source.stop();
+        }
+    }
+
+    /// Returns the number of voices currently playing.
+    pub fn playing_voices(&self) -> usize {
+        self.voices
+            .iter()
+            .filter(|voice| !voice.source.stopped())
+            .count()
+    }
+
+    /// Returns the most voices the pool plays at once.
+    #[inline]
+    pub fn max_voices(&self) -> usize {
+        self.max_voices
+    }
+}
+
+impl fmt::Debug for SoundPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SoundPool")
+            .field("playing_voices", &self.playing_voices())
+            .field("max_voices", &self.max_voices)
+            .field("steal", &self.steal)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum PickedVoice {
+    Existing(usize),
+    New,
+}
+
+/// Picks the voice to play on from the pool's `(started, volume, playing)` voices: one that
+/// finished playing, else a new one if there's room in the pool and the global budget, else
+/// one to steal.
+fn pick_voice(
+    voices: &[(Instant, f32, bool)],
+    max_voices: usize,
+    budget_left: bool,
+    steal: StealPolicy,
+) -> Option<PickedVoice> {
+    if let Some(index) = voices.iter().position(|&(_, _, playing)| !playing) {
+        // Reusing a finished voice still needs room in the budget for it to play again.
+        if budget_left {
+            return Some(PickedVoice::Existing(index));
+        }
+    } else if voices.len() < max_voices && budget_left {
+        return Some(PickedVoice::New);
+    }
+
+    let playing = voices.iter().enumerate().filter(|(_, voice)| voice.2);
+    let stolen = match steal {
+        StealPolicy::Oldest => playing.min_by_key(|(_, voice)| voice.0),
+        StealPolicy::Quietest => playing.min_by(|(_, a), (_, b)| a.1.total_cmp(&b.1)),
+        StealPolicy::None => None,
+    };
+    stolen.map(|(index, _)| PickedVoice::Existing(index))
+}
+
+/// Counts the voices played by all [`SoundPool`]s together, for the budget set with
+/// [`AudioContext::set_max_voices`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct VoiceCount(Arc<AtomicUsize>);
+
+impl VoiceCount {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Counts a voice as playing until the returned ticket is dropped along with its sound.
+    pub fn ticket(&self) -> VoiceTicket {
+        let _ = self.0.fetch_add(1, Ordering::Relaxed);
+        VoiceTicket(self.clone())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct VoiceTicket(VoiceCount);
+
+impl Drop for VoiceTicket {
+    fn drop(&mut self) {
+        let _ = self.0 .0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn headless_test_pick_voice() {
+        use PickedVoice::*;
+
+        let now = Instant::now();
+        let later = now + Duration::from_millis(100);
+        let busy = [(now, 0.2, true), (later, 0.8, true), (later, 0.1, true)];
+        let free = [(now, 1., true), (later, 1., false)];
+
+        assert_eq!(pick_voice(&[], 2, true, StealPolicy::Oldest), Some(New));
+        assert_eq!(
+            pick_voice(&free, 2, true, StealPolicy::Oldest),
+            Some(Existing(1))
+        );
+        assert_eq!(pick_voice(&busy, 4, true, StealPolicy::None), Some(New));
+
+        // A full pool steals, or gives up.
+        assert_eq!(
+            pick_voice(&busy, 3, true, StealPolicy::Oldest),
+            Some(Existing(0))
+        );
+        assert_eq!(
+            pick_voice(&busy, 3, true, StealPolicy::Quietest),
+            Some(Existing(2))
+        );
+        assert_eq!(pick_voice(&busy, 3, true, StealPolicy::None), None);
+
+        // So does one that is out of global budget, even with room to spare.
+        assert_eq!(
+            pick_voice(&busy, 4, false, StealPolicy::Oldest),
+            Some(Existing(0))
+        );
+        assert_eq!(
+            pick_voice(&free, 2, false, StealPolicy::Oldest),
+            Some(Existing(0))
+        );
+        assert_eq!(pick_voice(&free, 2, false, StealPolicy::None), None);
+        assert_eq!(pick_voice(&[], 2, false, StealPolicy::Oldest), None);
+
+        let count = VoiceCount::default();
+        let ticket = count.ticket();
+        assert_eq!(count.get(), 1);
+        drop(ticket);
+        assert_eq!(count.get(), 0);
+    }
+}
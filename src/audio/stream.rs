@@ -0,0 +1,552 @@
+use super::{
+    bus_mixer, reconnect, route, same_bus, AudioContext, Bus, EffectChain, Effects, Fade,
+    FadeHandle, SoundSource,
+};
+use crate::{
+    context::Has,
+    error::{GameError, GameResult},
+    filesystem::{File, Filesystem},
+};
+use std::{
+    collections::VecDeque,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// The part of a track that repeats. Playback runs from the start of the track up to `end`
+/// (or the end of the file), then jumps back to `start`, so a song can have an intro that
+/// only plays once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoopPoints {
+    /// Where each repetition starts.
+    pub start: Duration,
+    /// Where each repetition ends, or `None` for the end of the file.
+    pub end: Option<Duration>,
+}
+
+impl LoopPoints {
+    /// Repeats the section from `start` to `end`.
+    pub fn new(start: Duration, end: Option<Duration>) -> Self {
+        LoopPoints { start, end }
+    }
+
+    /// Checks that the section isn't empty and lies within a track that is `length` long,
+    /// if that is known.
+    fn check(&self, length: Option<Duration>) -> GameResult {
+        if let Some(end) = self.end.or(length) {
+            if self.start >= end {
+                return Err(GameError::AudioError(format!(
+                    "Loop start {:?} is not before the loop end {end:?}",
+                    self.start
+                )));
+            }
+        }
+        if let (Some(end), Some(length)) = (self.end, length) {
+            if end > length {
+                return Err(GameError::AudioError(format!(
+                    "Loop end {end:?} is past the end of the track at {length:?}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A long piece of audio, such as a music track, that is decoded bit by bit from its
+/// [`File`] while it plays instead of being loaded into memory up front like
+/// [`SoundData`](super::SoundData).
+///
+/// Besides what every [`SoundSource`] can do, it can seek, repeat a loop section after an
+/// intro, and move on to queued tracks without a gap between them.
+///
+/// Files inside the resources zip are read out of the archive as they play too. Seeking
+/// backwards in a compressed one decompresses it again from the start, so music that seeks
+/// a lot is best stored in the zip uncompressed.
+pub struct StreamingSource {
+    sink: rodio::Player,
+    device: rodio::mixer::Mixer,
+    state: StreamState,
+}
+
+#[derive(Debug)]
+struct StreamState {
+    fs: Filesystem,
+    playlist: Arc<Mutex<Playlist>>,
+    fade_in: Duration,
+    start: Duration,
+    speed: f32,
+    query_interval: Duration,
+    position: Arc<AtomicU64>,
+    bus: Option<Bus>,
+    effects: Effects,
+    fade: FadeHandle,
+}
+
+/// The track being played and the ones queued after it; shared with the audio thread,
+/// which moves on to the next track when the current one reaches its end or loop end.
+#[derive(Debug)]
+struct Playlist {
+    current: Track,
+    queue: VecDeque<QueuedTrack>,
+}
+
+#[derive(Clone, Debug)]
+struct Track {
+    path: PathBuf,
+    loop_points: Option<LoopPoints>,
+    // The length of the track, if its decoder knows it.
+    length: Option<Duration>,
+}
+
+/// A queued track, opened when it's queued so that the audio thread doesn't have to.
+struct QueuedTrack {
+    track: Track,
+    decoder: rodio::Decoder<File>,
+}
+
+impl StreamingSource {
+    /// Creates a new `StreamingSource` playing the file at `path`.
+    pub fn new(ctx: &impl Has<AudioContext>, path: impl AsRef<Path>) -> GameResult<Self> {
+        let audio = ctx.retrieve();
+        let mut track = Track {
+            path: path.as_ref().to_path_buf(),
+            loop_points: None,
+            length: None,
+        };
+        track.length = rodio::Source::total_duration(&track.decoder(&audio.fs)?);
+
+        Ok(StreamingSource {
+            sink: rodio::Player::connect_new(audio.stream.mixer()),
+            device: audio.stream.mixer().clone(),
+            state: StreamState {
+                fs: audio.fs.clone(),
+                playlist: Arc::new(Mutex::new(Playlist {
+                    current: track,
+                    queue: VecDeque::new(),
+                })),
+                fade_in: Duration::ZERO,
+                start: Duration::ZERO,
+                speed: 1.0,
+                query_interval: Duration::from_millis(100),
+                position: Arc::new(AtomicU64::new(0)),
+                bus: None,
+                effects: Effects::new(),
+                fade: FadeHandle::new(),
+            },
+        })
+    }
+
+    /// Returns the path of the track currently playing.
+    pub fn path(&self) -> PathBuf {
+        self.state.playlist.lock().unwrap().current.path.clone()
+    }
+
+    /// Returns the loop section of the current track, if it repeats.
+    pub fn loop_points(&self) -> Option<LoopPoints> {
+        self.state.playlist.lock().unwrap().current.loop_points
+    }
+
+    /// Sets the loop section of the current track, or lets it play out to its end with `None`.
+    ///
+    /// A playing track picks up the change once it reaches its current loop end.
+    /// Fails if the loop section is empty or runs past the end of the track.
+    pub fn set_loop_points(&mut self, loop_points: Option<LoopPoints>) -> GameResult {
+        let mut playlist = self.state.playlist.lock().unwrap();
+        if let Some(loop_points) = &loop_points {
+            loop_points.check(playlist.current.length)?;
+        }
+        playlist.current.loop_points = loop_points;
+        Ok(())
+    }
+
+    /// Queues the track at `path` to follow the current one without a gap, once that reaches
+    /// its end, or its loop end if it repeats. `loop_points` apply to the queued track.
+    ///
+    /// The track is opened right away, so that it's ready to play when its turn comes.
+    pub fn queue(&self, path: impl AsRef<Path>, loop_points: Option<LoopPoints>) -> GameResult {
+        let mut track = Track {
+            path: path.as_ref().to_path_buf(),
+            loop_points,
+            length: None,
+        };
+        let decoder = track.decoder(&self.state.fs)?;
+        track.length = rodio::Source::total_duration(&decoder);
+        if let Some(loop_points) = &loop_points {
+            loop_points.check(track.length)?;
+        }
+        self.state
+            .playlist
+            .lock()
+            .unwrap()
+            .queue
+            .push_back(QueuedTrack { track, decoder });
+        Ok(())
+    }
+
+    /// Returns the number of tracks queued after the current one.
+    pub fn queued(&self) -> usize {
+        self.state.playlist.lock().unwrap().queue.len()
+    }
+
+    /// Drops the queued tracks.
+    pub fn clear_queue(&self) {
+        self.state.playlist.lock().unwrap().queue.clear();
+    }
+
+    /// Continues playback of the current track from `pos`, keeping it paused if it was.
+    pub fn seek(&self, pos: Duration) -> GameResult {
+        let source = self.state.to_source(pos)?;
+        let paused = self.sink.is_paused();
+        self.sink.clear();
+        self.sink.append(source);
+        if !paused {
+            self.sink.play();
+        }
+        Ok(())
+    }
+}
+
+impl StreamState {
+    fn to_source(&self, from: Duration) -> GameResult<impl rodio::Source + Send + 'static> {
+        use rodio::Source;
+
+        let current = self.playlist.lock().unwrap().current.clone();
+        let mut decoder = current.decoder(&self.fs)?;
+        if !from.is_zero() {
+            decoder.try_seek(from).map_err(|e| {
+                GameError::AudioError(format!(
+                    "Could not seek to {from:?} in {}: {e}",
+                    current.path.display()
+                ))
+            })?;
+        }
+
+        let mut sections = Sections {
+            playlist: self.playlist.clone(),
+            position: self.position.clone(),
+            query_interval: self.query_interval,
+            first: None,
+        };
+        // Open the first section here, so that a missing or broken file is reported to the caller.
+        sections.first = Some(sections.section(&current, decoder, from));
+
+        // We can't give zero here so give 1µs which is quite the same
+        let fade_in = self.fade_in.max(Duration::from_micros(1));
+        let source = rodio::source::from_iter(sections)
+            .speed(self.speed)
+            .fade_in(fade_in);
+        let source = self
+            .fade
+            .apply(EffectChain::new(source, self.effects.clone()));
+        Ok(route(source, self.bus.as_ref()))
+    }
+}
+
+/// Yields the tracks of the playlist to play back to back: the current one from where
+/// playback starts, then the queued ones, which were opened when they were queued.
+struct Sections {
+    playlist: Arc<Mutex<Playlist>>,
+    position: Arc<AtomicU64>,
+    query_interval: Duration,
+    first: Option<Section>,
+}
+
+type Section = Box<dyn rodio::Source + Send>;
+
+impl Sections {
+    fn section(&self, track: &Track, decoder: rodio::Decoder<File>, from: Duration) -> Section {
+        use rodio::Source;
+
+        self.position
+            .store(from.as_micros() as u64, Ordering::Relaxed);
+        let mut looped = Looped {
+            path: track.path.clone(),
+            decoder,
+            playlist: self.playlist.clone(),
+            position: self.position.clone(),
+            left: None,
+            played: true,
+        };
+        // Seeking past the loop end plays on to the end of the track before looping.
+        looped.left = track
+            .loop_points
+            .and_then(|loop_points| loop_points.end)
+            .filter(|&end| end > from)
+            .map(|end| looped.samples(end - from));
+
+        let position = self.position.clone();
+        let period_mus = self.query_interval.as_micros() as u64;
+        Box::new(looped.periodic_access(self.query_interval, move |_| {
+            let _ = position.fetch_add(period_mus, Ordering::Relaxed);
+        }))
+    }
+}
+
+impl Iterator for Sections {
+    type Item = Section;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(first) = self.first.take() {
+            return Some(first);
+        }
+
+        let next = {
+            let mut playlist = self.playlist.lock().unwrap();
+            let next = playlist.queue.pop_front()?;
+            playlist.current = next.track.clone();
+            next
+        };
+        Some(self.section(&next.track, next.decoder, Duration::ZERO))
+    }
+}
+
+/// Plays a track, seeking back to its loop start whenever it reaches its loop end, for as
+/// long as it repeats and nothing is queued after it.
+struct Looped {
+    path: PathBuf,
+    decoder: rodio::Decoder<File>,
+    playlist: Arc<Mutex<Playlist>>,
+    position: Arc<AtomicU64>,
+    // The samples left until the loop end, or `None` to play to the end of the track.
+    left: Option<u64>,
+    // Whether anything was played since the last rewind, so that an empty loop can't spin.
+    played: bool,
+}
+
+impl Looped {
+    fn samples(&self, duration: Duration) -> u64 {
+        use rodio::Source;
+
+        let frames = (duration.as_secs_f64() * f64::from(self.decoder.sample_rate().get())) as u64;
+        frames * u64::from(self.decoder.channels().get())
+    }
+
+    /// Seeks back to the loop start, returning whether the track goes on playing.
+    fn rewind(&mut self) -> bool {
+        use rodio::Source;
+
+        let loop_points = {
+            let playlist = self.playlist.lock().unwrap();
+            if !playlist.queue.is_empty() {
+                return false;
+            }
+            playlist.current.loop_points
+        };
+        let Some(loop_points) = loop_points else {
+            return false;
+        };
+        if !self.played {
+            warn!("Stopped looping {}: the loop is empty", self.path.display());
+            return false;
+        }
+        if let Err(e) = self.decoder.try_seek(loop_points.start) {
+            warn!("Stopped looping {}: {e}", self.path.display());
+            return false;
+        }
+
+        self.position
+            .store(loop_points.start.as_micros() as u64, Ordering::Relaxed);
+        self.left = loop_points
+            .end
+            .map(|end| self.samples(end.saturating_sub(loop_points.start)));
+        self.played = false;
+        true
+    }
+}
+
+impl Iterator for Looped {
+    type Item = rodio::Sample;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.left != Some(0) {
+                if let Some(sample) = self.decoder.next() {
+                    if let Some(left) = &mut self.left {
+                        *left -= 1;
+                    }
+                    self.played = true;
+                    return Some(sample);
+                }
+            }
+            if !self.rewind() {
+                return None;
+            }
+        }
+    }
+}
+
+impl rodio::Source for Looped {
+    fn current_span_len(&self) -> Option<usize> {
+        self.decoder.current_span_len()
+    }
+
+    fn channels(&self) -> rodio::ChannelCount {
+        self.decoder.channels()
+    }
+
+    fn sample_rate(&self) -> rodio::SampleRate {
+        self.decoder.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl fmt::Debug for QueuedTrack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueuedTrack")
+            .field("track", &self.track)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Track {
+    fn decoder(&self, fs: &Filesystem) -> GameResult<rodio::Decoder<File>> {
+        rodio::Decoder::new(fs.open(&self.path)?).map_err(|e| {
+            GameError::AudioError(format!("Could not decode {}: {e}", self.path.display()))
+        })
+    }
+}
+
+impl SoundSource for StreamingSource {
+    fn play_later(&self) {
+        match self.state.to_source(self.state.start) {
+            Ok(source) => self.sink.append(source),
+            Err(e) => warn!("Could not play stream: {e}"),
+        }
+    }
+
+    fn play_detached(self) {
+        self.play();
+        self.sink.detach();
+    }
+
+    /// Repeats the whole track; see [`StreamingSource::set_loop_points`] to repeat only part of it.
+    fn set_repeat(&mut self, repeat: bool) {
+        // Repeating the whole track is always valid.
+        self.state.playlist.lock().unwrap().current.loop_points = repeat.then(LoopPoints::default);
+    }
+
+    fn set_fade_in(&mut self, dur: Duration) {
+        self.state.fade_in = dur;
+    }
+
+    fn set_start(&mut self, dur: Duration) {
+        self.state.start = dur;
+    }
+
+    fn set_pitch(&mut self, ratio: f32) {
+        self.state.speed = ratio;
+    }
+
+    fn repeat(&self) -> bool {
+        self.loop_points().is_some()
+    }
+
+    fn pause(&self) {
+        self.sink.pause()
+    }
+
+    fn resume(&self) {
+        self.sink.play()
+    }
+
+    fn stop(&self) {
+        self.state.position.store(0, Ordering::SeqCst);
+        self.state.fade.reset();
+        self.sink.clear();
+    }
+
+    fn fade(&self, fade: Fade) {
+        self.state.fade.start(fade);
+    }
+
+    fn stopped(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.sink.set_volume(value)
+    }
+
+    fn paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn playing(&self) -> bool {
+        !self.paused() && !self.stopped()
+    }
+
+    /// Returns the playback position in the current track, which jumps back at loop points.
+    fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.state.position.load(Ordering::Relaxed))
+    }
+
+    fn set_query_interval(&mut self, t: Duration) {
+        self.state.query_interval = t;
+    }
+
+    fn set_bus(&mut self, bus: Option<Bus>) {
+        if !same_bus(self.state.bus.as_ref(), bus.as_ref()) {
+            self.stop();
+            self.sink = reconnect(&self.sink, bus_mixer(&self.device, bus.as_ref()));
+        }
+        self.state.bus = bus;
+    }
+
+    fn bus(&self) -> Option<&Bus> {
+        self.state.bus.as_ref()
+    }
+
+    fn effects(&self) -> &Effects {
+        &self.state.effects
+    }
+
+    fn set_effects(&mut self, effects: Effects) {
+        self.state.effects = effects;
+    }
+}
+
+impl fmt::Debug for StreamingSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Streaming audio source: {self:p}>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_loop_points() {
+        let secs = Duration::from_secs;
+        let length = Some(secs(10));
+
+        assert!(LoopPoints::default().check(length).is_ok());
+        assert!(LoopPoints::default().check(None).is_ok());
+        assert!(LoopPoints::new(secs(2), Some(secs(10)))
+            .check(length)
+            .is_ok());
+        assert!(LoopPoints::new(secs(20), Some(secs(30)))
+            .check(None)
+            .is_ok());
+
+        // Empty loops, and ones running past the end of the track.
+        assert!(LoopPoints::new(secs(5), Some(secs(5))).check(None).is_err());
+        assert!(LoopPoints::new(secs(10), None).check(length).is_err());
+        assert!(LoopPoints::new(secs(12), None).check(length).is_err());
+        assert!(LoopPoints::new(secs(2), Some(secs(11)))
+            .check(length)
+            .is_err());
+    }
+}
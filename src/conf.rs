@@ -240,6 +240,7 @@ impl WindowMode {
 ///     vsync: true,
 ///     icon: "".to_owned(),
 ///     srgb: true,
+///     drag_and_drop: false,
 /// }
 /// # , WindowSetup::default()); }
 /// ```
@@ -258,6 +259,13 @@ pub struct WindowSetup {
     /// Whether or not to enable sRGB (gamma corrected color)
     /// handling on the display.
     pub srgb: bool,
+    /// Whether or not files can be dropped onto the window on Windows, see
+    /// [`EventHandler::dropped_file_event`](crate::event::EventHandler::dropped_file_event).
+    ///
+    /// It's disabled by default since it doesn't work together with the audio backend
+    /// on Windows, which initializes COM differently. Other platforms always allow it.
+    #[serde(default)]
+    pub drag_and_drop: bool,
 }
 
 impl Default for WindowSetup {
@@ -268,6 +276,7 @@ impl Default for WindowSetup {
             vsync: true,
             icon: String::new(),
             srgb: true,
+            drag_and_drop: false,
         }
     }
 }
@@ -307,6 +316,13 @@ impl WindowSetup {
         self.srgb = active;
         self
     }
+
+    /// Set whether files can be dropped onto the window on Windows.
+    #[must_use]
+    pub fn drag_and_drop(mut self, drag_and_drop: bool) -> Self {
+        self.drag_and_drop = drag_and_drop;
+        self
+    }
 }
 
 /// Possible graphics backends.
@@ -335,6 +351,43 @@ pub enum Backend {
     BrowserWebGpu,
 }
 
+/// How often [`event::run()`](crate::event::run) calls
+/// [`EventHandler::update`](crate::event::EventHandler::update).
+/// The default is `Variable`.
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Default)]
+#[serde(tag = "type")]
+pub enum Timestep {
+    /// `update` is called once per frame, so the time between updates varies with the frame rate.
+    #[default]
+    Variable,
+    /// `update` is called `updates_per_second` times per second, i.e. zero or more times per frame,
+    /// in steps of exactly `1 / updates_per_second` seconds.
+    ///
+    /// If a frame takes so long that more than `max_updates_per_frame` updates would be needed
+    /// to catch up, the remaining time is dropped instead, so that a slow update can't cause
+    /// ever more updates to be run (the "spiral of death").
+    ///
+    /// Use [`TimeContext::alpha()`](crate::timer::TimeContext::alpha) in `draw` to interpolate
+    /// between the last two updates for smooth rendering.
+    Fixed {
+        /// The number of updates per second, at least 1.
+        updates_per_second: u32,
+        /// The maximum number of updates run in a single frame, at least 1.
+        max_updates_per_frame: u32,
+    },
+}
+
+impl Timestep {
+    /// Creates a fixed timestep with the given number of updates per second,
+    /// allowing up to 5 updates per frame.
+    pub fn fixed(updates_per_second: u32) -> Self {
+        Timestep::Fixed {
+            updates_per_second,
+            max_updates_per_frame: 5,
+        }
+    }
+}
+
 /// The possible number of samples for multisample anti-aliasing.
 #[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
 pub enum NumSamples {
@@ -388,6 +441,7 @@ impl From<NumSamples> for u8 {
 ///     window_mode: WindowMode::default(),
 ///     window_setup: WindowSetup::default(),
 ///     backend: Backend::default(),
+///     timestep: Timestep::default(),
 /// }
 /// # , Conf::default()); }
 /// ```
@@ -399,6 +453,9 @@ pub struct Conf {
     pub window_setup: WindowSetup,
     /// Graphics backend configuration
     pub backend: Backend,
+    /// How often the game logic is updated
+    #[serde(default)]
+    pub timestep: Timestep,
 }
 
 impl Conf {
@@ -437,6 +494,13 @@ impl Conf {
         self.backend = backend;
         self
     }
+
+    /// Sets the timestep
+    #[must_use]
+    pub fn timestep(mut self, timestep: Timestep) -> Self {
+        self.timestep = timestep;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -228,14 +228,39 @@ impl Context {
         conf: conf::Conf,
         fs: Filesystem,
     ) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
+        let events_loop = winit::event_loop::EventLoop::new()?;
+        let ctx = Self::from_parts(conf, fs, |conf, fs| {
+            graphics::context::GraphicsContext::new(game_id, &events_loop, conf, fs)
+        })?;
+
+        Ok((ctx, events_loop))
+    }
+
+    /// Tries to create a new headless Context, which renders into an offscreen
+    /// frame of the given size instead of a window.
+    /// Usually called by [`ContextBuilder::build_headless()`](struct.ContextBuilder.html#method.build_headless).
+    fn from_conf_headless(
+        conf: conf::Conf,
+        fs: Filesystem,
+        width: u32,
+        height: u32,
+    ) -> GameResult<Context> {
+        Self::from_parts(conf, fs, |conf, fs| {
+            graphics::context::GraphicsContext::new_headless(width, height, conf, fs)
+        })
+    }
+
+    fn from_parts(
+        conf: conf::Conf,
+        fs: Filesystem,
+        new_gfx: impl FnOnce(&conf::Conf, &Filesystem) -> GameResult<GraphicsContext>,
+    ) -> GameResult<Context> {
         #[cfg(feature = "audio")]
         let audio_context = audio::AudioContext::new(&fs)?;
-        let events_loop = winit::event_loop::EventLoop::new()?;
         let timer_context = timer::TimeContext::new();
-        let graphics_context =
-            graphics::context::GraphicsContext::new(game_id, &events_loop, &conf, &fs)?;
+        let graphics_context = new_gfx(&conf, &fs)?;
 
-        let ctx = Context {
+        Ok(Context {
             fs,
             gfx: graphics_context,
             time: timer_context,
@@ -249,9 +274,7 @@ impl Context {
                 continuing: true,
                 quit_requested: false,
             },
-        };
-
-        Ok((ctx, events_loop))
+        })
     }
 }
 
@@ -307,10 +330,19 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets how often the game logic is updated by [`event::run()`](crate::event::run),
+    /// see [`conf::Timestep`].
+    #[must_use]
+    pub fn timestep(mut self, timestep: conf::Timestep) -> Self {
+        self.conf.timestep = timestep;
+        self
+    }
+
     /// Sets all the config options, overriding any previous
     /// ones from [`window_setup()`](#method.window_setup),
-    /// [`window_mode()`](#method.window_mode), and
-    /// [`backend()`](#method.backend).  These are used as
+    /// [`window_mode()`](#method.window_mode),
+    /// [`backend()`](#method.backend), and
+    /// [`timestep()`](#method.timestep).  These are used as
     /// defaults and are overridden by any external config
     /// file found.
     #[must_use]
@@ -378,28 +410,33 @@ impl ContextBuilder {
 
     /// Build a `Context`
     pub fn build(self) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
-        let fs = Filesystem::new(
-            self.game_id.as_ref(),
-            self.author.as_ref(),
-            &self.resources_dir_name,
-            &self.resources_zip_name,
-        )?;
-
-        for path in &self.paths {
-            fs.mount(path, true);
-        }
-
-        for zipfile_bytes in self.memory_zip_files {
-            fs.add_zip_file(std::io::Cursor::new(zipfile_bytes))?;
-        }
-
-        let config = if self.load_conf_file {
-            fs.read_config().unwrap_or(self.conf)
-        } else {
-            self.conf
-        };
+        let (game_id, config, fs) = self.prepare()?;
+        Context::from_conf(game_id.as_ref(), config, fs)
+    }
 
-        Context::from_conf(self.game_id.as_ref(), config, fs)
+    /// Build a headless `Context`, which has no window or event loop and renders into an
+    /// offscreen frame of `width` x `height` pixels instead.
+    ///
+    /// Drawing works as usual between calls to
+    /// [`GraphicsContext::begin_frame()`](crate::graphics::GraphicsContext::begin_frame) and
+    /// [`GraphicsContext::end_frame()`](crate::graphics::GraphicsContext::end_frame), after which
+    /// the result can be read back from [`GraphicsContext::frame()`](crate::graphics::GraphicsContext::frame).
+    ///
+    /// ```rust,no_run
+    /// # use ggez::{graphics::{Canvas, Color}, ContextBuilder, GameResult};
+    /// # fn t() -> GameResult {
+    /// let mut ctx = ContextBuilder::new("test", "ggez").build_headless(64, 64)?;
+    /// ctx.gfx.begin_frame()?;
+    /// let canvas = Canvas::from_frame(&ctx, Color::RED);
+    /// canvas.finish(&mut ctx)?;
+    /// ctx.gfx.end_frame()?;
+    /// let pixels = ctx.gfx.frame().to_pixels(&ctx)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn build_headless(self, width: u32, height: u32) -> GameResult<Context> {
+        let (_game_id, config, fs) = self.prepare()?;
+        Context::from_conf_headless(config, fs, width, height)
     }
 
     /// Build a Custom `Context`.
@@ -418,6 +455,12 @@ impl ContextBuilder {
             + HasMut<input::mouse::MouseContext>
             + HasMut<GamepadContext>,
     {
+        let (game_id, config, fs) = self.prepare()?;
+        from_conf(game_id, config, fs)
+    }
+
+    /// Sets up the filesystem and loads the config, as needed by every kind of build.
+    fn prepare(self) -> GameResult<(String, conf::Conf, Filesystem)> {
         let fs = Filesystem::new(
             self.game_id.as_ref(),
             self.author.as_ref(),
@@ -439,7 +482,7 @@ impl ContextBuilder {
             self.conf
         };
 
-        from_conf(self.game_id, config, fs)
+        Ok((self.game_id, config, fs))
     }
 }
 
@@ -447,8 +490,8 @@ impl ContextBuilder {
 mod tests {
     use crate::{
         context::{Has, HasMut},
-        graphics::GraphicsContext,
-        ContextBuilder,
+        graphics::{Canvas, Color, GraphicsContext},
+        ContextBuilder, GameError,
     };
 
     // This will fail when testing if not running using one thread but is actually fine
@@ -464,4 +507,28 @@ mod tests {
         takes_mut_gfx(&mut ctx);
         takes_mut_gfx(&mut ctx.gfx);
     }
+
+    #[test]
+    fn headless_test_build_headless() {
+        let mut ctx = match ContextBuilder::new("ggez_headless_test", "ggez").build_headless(4, 2) {
+            Ok(ctx) => ctx,
+            // No adapter at all, not even a software one, or no audio device to open.
+            Err(GameError::GraphicsInitializationError | GameError::AudioError(_)) => return,
+            Err(e) => panic!("{e}"),
+        };
+        assert!(ctx.gfx.is_headless());
+        assert!(ctx.gfx.window_position().is_err());
+
+        ctx.gfx.begin_frame().unwrap();
+        let canvas = Canvas::from_frame(&ctx, Color::RED);
+        canvas.finish(&mut ctx).unwrap();
+        ctx.gfx.end_frame().unwrap();
+
+        let frame = ctx.gfx.frame();
+        assert_eq!((frame.width(), frame.height()), (4, 2));
+        let pixels = frame.to_pixels(&ctx).unwrap();
+        assert_eq!(pixels.len(), 4 * 2 * 4);
+        // The frame is BGRA.
+        assert!(pixels.chunks(4).all(|pixel| pixel == [0, 0, 255, 255]));
+    }
 }
@@ -39,6 +39,8 @@ pub enum GameError {
     FontError(glyph_brush::ab_glyph::InvalidFont),
     /// Shader encoding error (not valid utf-8)
     ShaderEncodingError(FromUtf8Error),
+    /// A WGSL shader failed to parse or validate; see [`ShaderError`] for where.
+    ShaderCompileError(ShaderError),
     /// Something went wrong applying video settings.
     VideoError(String),
     /// Something went wrong with the `gilrs` gamepad-input library.
@@ -77,6 +79,7 @@ impl fmt::Display for GameError {
             GameError::GlyphBrushError(ref e) => write!(f, "Text rendering error: {e}"),
             GameError::FontSelectError(ref e) => write!(f, "No such font '{e}'"),
             GameError::BufferAsyncError(ref e) => write!(f, "Async buffer map error: {e}"),
+            GameError::ShaderCompileError(ref e) => write!(f, "Shader compile error: {e}"),
             _ => write!(f, "GameError {self:?}"),
         }
     }
@@ -92,11 +95,42 @@ impl Error for GameError {
             GameError::GlyphBrushError(ref e) => Some(e),
             GameError::BufferAsyncError(ref e) => Some(e),
             GameError::EventLoopError(ref e) => Some(e),
+            GameError::ShaderCompileError(ref e) => Some(e),
             _ => None,
         }
     }
 }
 
+/// Where and why a WGSL shader failed to compile.
+///
+/// Its `Display` output is the full report, with the offending source annotated, which is
+/// what's best shown to shader authors in game or in logs.
+#[derive(Debug, Clone)]
+pub struct ShaderError {
+    /// Resource path of the shader, or `None` if it was built from code.
+    pub path: Option<std::path::PathBuf>,
+    /// What's wrong with the shader.
+    pub message: String,
+    /// 1-based line and column (in bytes) of the error, if known.
+    pub location: Option<(u32, u32)>,
+    /// The line of source code the error is on, if known.
+    pub snippet: Option<String>,
+    /// The report as emitted by the shader compiler, including the annotated source.
+    pub report: String,
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.report.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}", self.report)
+        }
+    }
+}
+
+impl Error for ShaderError {}
+
 /// A convenient result type consisting of a return type and a `GameError`
 pub type GameResult<T = ()> = Result<T, GameError>;
 
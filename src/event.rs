@@ -10,14 +10,15 @@
 //! source code for this module, or the [`eventloop`
 //! example](https://github.com/ggez/ggez/blob/master/examples/eventloop.rs).
 
-use std::marker::PhantomData;
+use std::{marker::PhantomData, path::PathBuf};
 use winit::{
     dpi,
-    event::{ElementState, Event, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
+    event::{ElementState, Event, Ime, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     keyboard::{Key, NamedKey},
 };
 
+use crate::conf::Timestep;
 use crate::graphics::GraphicsContext;
 use crate::input::{self, keyboard::KeyInput};
 use crate::{
@@ -60,6 +61,12 @@ pub enum ErrorOrigin {
     KeyDownEvent,
     /// error originated in `key_up_event()`
     KeyUpEvent,
+    /// error originated in `text_input_event()`
+    TextInputEvent,
+    /// error originated in `ime_preedit_event()`
+    ImePreeditEvent,
+    /// error originated in `ime_commit_event()`
+    ImeCommitEvent,
     /// error originated in `touch_event()`
     TouchEvent,
     /// error originated in `gamepad_button_down_event()`
@@ -74,6 +81,12 @@ pub enum ErrorOrigin {
     QuitEvent,
     /// error originated in `resize_event()`
     ResizeEvent,
+    /// error originated in `dropped_file_event()`
+    DroppedFileEvent,
+    /// error originated in `hovered_file_event()`
+    HoveredFileEvent,
+    /// error originated in `hovered_file_cancelled_event()`
+    HoveredFileCancelledEvent,
 }
 
 /// A trait defining event callbacks.  This is your primary interface with
@@ -96,6 +109,9 @@ where
 {
     /// Called upon each logic update to the game.
     /// This should be where the game's logic takes place.
+    ///
+    /// By default this is called once per frame; with a
+    /// [`Timestep::Fixed`](crate::conf::Timestep::Fixed) it's called at a fixed rate instead.
     fn update(&mut self, _ctx: &mut C) -> Result<(), E>;
 
     /// Called to do the drawing of your game.
@@ -172,6 +188,40 @@ where
         Ok(())
     }
 
+    /// Text was entered, either by pressing (or holding) a key or through an IME.
+    ///
+    /// This already takes the keyboard layout, modifiers and dead keys into account,
+    /// so it's what you want for text fields, as opposed to
+    /// [`key_down_event()`](#method.key_down_event). Control characters,
+    /// such as the ones produced by backspace or enter, are not reported here.
+    fn text_input_event(&mut self, _ctx: &mut C, _text: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// The IME is composing text, which hasn't been committed yet; you'll usually want to
+    /// display it at the text cursor. `cursor` is the byte range of the IME cursor within `text`,
+    /// if it should be shown.
+    ///
+    /// An empty `text` means the composition has been cleared.
+    /// IME events are only sent after enabling IME with
+    /// [`GraphicsContext::set_ime_allowed()`](../graphics/struct.GraphicsContext.html#method.set_ime_allowed).
+    fn ime_preedit_event(
+        &mut self,
+        _ctx: &mut C,
+        _text: &str,
+        _cursor: Option<(usize, usize)>,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// The IME committed some text.
+    ///
+    /// The default implementation of this passes the text on to
+    /// [`text_input_event()`](#method.text_input_event).
+    fn ime_commit_event(&mut self, ctx: &mut C, text: &str) -> Result<(), E> {
+        self.text_input_event(ctx, text)
+    }
+
     /// An event from a touchscreen has been triggered; it provides the x and y location
     /// inside the window as well as the state of the tap (such as Started, Moved, Ended, etc)
     /// By default, touch events will trigger mouse behavior
@@ -249,6 +299,31 @@ where
         Ok(())
     }
 
+    /// A file was dropped onto the window. If multiple files are dropped at once,
+    /// this is called once for each of them.
+    ///
+    /// `path` is a native path, which may lie outside of the mounted
+    /// [`filesystem`](../filesystem/index.html) roots, but it can still be read through
+    /// [`Filesystem::open`](../filesystem/struct.Filesystem.html#method.open) and friends.
+    ///
+    /// On Windows, this requires enabling
+    /// [`WindowSetup::drag_and_drop`](../conf/struct.WindowSetup.html#structfield.drag_and_drop).
+    fn dropped_file_event(&mut self, _ctx: &mut C, _path: PathBuf) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// A file is being dragged over the window. If multiple files are dragged at once,
+    /// this is called once for each of them.
+    fn hovered_file_event(&mut self, _ctx: &mut C, _path: PathBuf) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// The files being dragged over the window left it or the drag was cancelled,
+    /// without dropping them.
+    fn hovered_file_cancelled_event(&mut self, _ctx: &mut C) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Something went wrong, causing a `GameError` (or some other kind of error, depending on what you specified).
     /// If this returns true, the error was fatal, so the event loop ends, aborting the game.
     fn on_error(&mut self, _ctx: &mut C, _origin: ErrorOrigin, _e: E) -> bool {
@@ -400,6 +475,10 @@ where
 
                 let repeat = event.repeat;
                 let key_state = event.state;
+                let text = event
+                    .text
+                    .clone()
+                    .filter(|text| !text.chars().all(char::is_control));
                 let input = KeyInput { event, mods };
                 let (res, origin) = match key_state {
                     ElementState::Pressed => (
@@ -411,6 +490,33 @@ where
                         ErrorOrigin::KeyUpEvent,
                     ),
                 };
+                if catch_error(&mut self.ctx, res, &mut self.state, event_loop, origin) {
+                    return;
+                }
+
+                if let (ElementState::Pressed, Some(text)) = (key_state, text) {
+                    let res = self.state.text_input_event(&mut self.ctx, &text);
+                    if catch_error(
+                        &mut self.ctx,
+                        res,
+                        &mut self.state,
+                        event_loop,
+                        ErrorOrigin::TextInputEvent,
+                    ) {}
+                }
+            }
+            WindowEvent::Ime(ime) => {
+                let (res, origin) = match ime {
+                    Ime::Preedit(text, cursor) => (
+                        self.state.ime_preedit_event(&mut self.ctx, &text, cursor),
+                        ErrorOrigin::ImePreeditEvent,
+                    ),
+                    Ime::Commit(text) => (
+                        self.state.ime_commit_event(&mut self.ctx, &text),
+                        ErrorOrigin::ImeCommitEvent,
+                    ),
+                    Ime::Enabled | Ime::Disabled => return,
+                };
                 if catch_error(&mut self.ctx, res, &mut self.state, event_loop, origin) {}
             }
             WindowEvent::MouseWheel { delta, .. } => {
@@ -418,7 +524,7 @@ where
                 let (x, y) = match delta {
                     MouseScrollDelta::LineDelta(x, y) => (x, y),
                     MouseScrollDelta::PixelDelta(pos) => {
-                        let scale_factor = gfx.window.scale_factor();
+                        let scale_factor = gfx.window().scale_factor();
                         let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
                         (x, y)
                     }
@@ -507,6 +613,36 @@ where
                     ErrorOrigin::TouchEvent,
                 ) {}
             }
+            WindowEvent::DroppedFile(path) => {
+                let res = self.state.dropped_file_event(&mut self.ctx, path);
+                if catch_error(
+                    &mut self.ctx,
+                    res,
+                    &mut self.state,
+                    event_loop,
+                    ErrorOrigin::DroppedFileEvent,
+                ) {}
+            }
+            WindowEvent::HoveredFile(path) => {
+                let res = self.state.hovered_file_event(&mut self.ctx, path);
+                if catch_error(
+                    &mut self.ctx,
+                    res,
+                    &mut self.state,
+                    event_loop,
+                    ErrorOrigin::HoveredFileEvent,
+                ) {}
+            }
+            WindowEvent::HoveredFileCancelled => {
+                let res = self.state.hovered_file_cancelled_event(&mut self.ctx);
+                if catch_error(
+                    &mut self.ctx,
+                    res,
+                    &mut self.state,
+                    event_loop,
+                    ErrorOrigin::HoveredFileCancelledEvent,
+                ) {}
+            }
             WindowEvent::CursorEntered { device_id: _ } => {
                 let res = self.state.mouse_enter_or_leave(&mut self.ctx, true);
                 if catch_error(
@@ -623,17 +759,31 @@ where
             }
         }
 
-        let res = self.state.update(&mut self.ctx);
-        if catch_error(
-            &mut self.ctx,
-            res,
-            &mut self.state,
-            event_loop,
-            ErrorOrigin::Update,
-        ) {
-            return;
+        let updates = match HasMut::<ContextFields>::retrieve_mut(&mut self.ctx)
+            .conf
+            .timestep
+        {
+            Timestep::Variable => 1,
+            Timestep::Fixed {
+                updates_per_second,
+                max_updates_per_frame,
+            } => HasMut::<crate::timer::TimeContext>::retrieve_mut(&mut self.ctx)
+                .consume_fixed_updates(updates_per_second, max_updates_per_frame),
         };
 
+        for _ in 0..updates {
+            let res = self.state.update(&mut self.ctx);
+            if catch_error(
+                &mut self.ctx,
+                res,
+                &mut self.state,
+                event_loop,
+                ErrorOrigin::Update,
+            ) {
+                return;
+            };
+        }
+
         if let Err(e) = HasMut::<GraphicsContext>::retrieve_mut(&mut self.ctx).begin_frame() {
             error!("Error on GraphicsContext::begin_frame(): {e:?}");
             eprintln!("Error on GraphicsContext::begin_frame(): {e:?}");
@@ -761,6 +911,10 @@ where
             };
             mouse.set_button(*button, pressed);
         }
+        WindowEvent::DroppedFile(path) => {
+            let gfx = HasMut::<GraphicsContext>::retrieve_mut(ctx);
+            gfx.fs.add_dropped_file(path.clone());
+        }
         WindowEvent::ModifiersChanged(mods) => {
             let keyboard = HasMut::<input::keyboard::KeyboardContext>::retrieve_mut(ctx);
             keyboard.active_modifiers = mods.state();
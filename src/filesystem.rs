@@ -38,7 +38,12 @@ use crate::{
     GameError, GameResult,
 };
 use directories::ProjectDirs;
+#[cfg(feature = "hot-reload")]
+use notify::Watcher;
+#[cfg(feature = "hot-reload")]
+use std::{collections::HashMap, sync::Mutex};
 use std::{
+    collections::VecDeque,
     env,
     io::{self, Read},
     path,
@@ -49,6 +54,9 @@ pub use crate::vfs::OpenOptions;
 
 const CONFIG_NAME: &str = "/conf.toml";
 
+/// How many of the files dropped onto the window most recently can be opened.
+const MAX_DROPPED_FILES: usize = 64;
+
 /// A structure that contains the filesystem state and cache.
 #[derive(Clone, Debug)]
 pub struct Filesystem {
@@ -57,6 +65,45 @@ pub struct Filesystem {
     zip_dir: path::PathBuf,
     user_config_dir: path::PathBuf,
     user_data_dir: path::PathBuf,
+    /// Native paths of the files dropped onto the window most recently, oldest first,
+    /// which may be opened even though they're outside of the mounted roots.
+    dropped_files: Arc<RwLock<VecDeque<path::PathBuf>>>,
+    #[cfg(feature = "hot-reload")]
+    watcher: Arc<Mutex<FileWatcher>>,
+}
+
+/// Watches the physical directories mounted in a [`Filesystem`] for changes,
+/// see [`Filesystem::watch`].
+#[cfg(feature = "hot-reload")]
+#[derive(Debug, Default)]
+struct FileWatcher {
+    watcher: Option<notify::RecommendedWatcher>,
+    // Shared with the watcher's event thread.
+    changes: Arc<RwLock<FileChanges>>,
+}
+
+#[cfg(feature = "hot-reload")]
+#[derive(Debug, Default)]
+struct FileChanges {
+    roots: Vec<path::PathBuf>,
+    // Only the files asked about with `Filesystem::change_count` are counted, so that
+    // e.g. a build writing thousands of files into a watched directory doesn't grow this.
+    counts: HashMap<path::PathBuf, u64>,
+}
+
+#[cfg(feature = "hot-reload")]
+impl FileChanges {
+    /// Counts a change to the file at the given native path, under each root it's in, if
+    /// it's being tracked there.
+    fn record(&mut self, native: &path::Path) {
+        for root in &self.roots {
+            if let Ok(relative) = native.strip_prefix(root) {
+                if let Some(count) = self.counts.get_mut(&path::Path::new("/").join(relative)) {
+                    *count += 1;
+                }
+            }
+        }
+    }
 }
 
 /// Represents a file, either in the filesystem, or in the resources zip file,
@@ -211,6 +258,9 @@ impl Filesystem {
             zip_dir: resources_zip_path,
             user_config_dir: user_config_path.to_path_buf(),
             user_data_dir: user_data_path.to_path_buf(),
+            dropped_files: Arc::default(),
+            #[cfg(feature = "hot-reload")]
+            watcher: Arc::default(),
         };
 
         Ok(fs)
@@ -226,8 +276,39 @@ impl Filesystem {
 
     /// Opens the given `path` and returns the resulting `File`
     /// in read-only mode.
+    ///
+    /// Files that were dropped onto the window (see
+    /// [`EventHandler::dropped_file_event`](crate::event::EventHandler::dropped_file_event))
+    /// can be opened by their native path as well, even if it's outside of the mounted roots.
+    /// Only the last 64 dropped files are remembered.
     pub fn open<P: AsRef<path::Path>>(&self, path: P) -> GameResult<File> {
-        self.vfs().open(path.as_ref()).map(File)
+        let path = path.as_ref();
+        if self.is_dropped_file(path) {
+            return std::fs::File::open(path)
+                .map(|f| File(Box::new(f)))
+                .map_err(GameError::from);
+        }
+        self.vfs().open(path).map(File)
+    }
+
+    /// Allows the dropped file at the given native path to be opened, see [`Filesystem::open`].
+    pub(crate) fn add_dropped_file(&self, path: path::PathBuf) {
+        let mut dropped_files = self.dropped_files.write().unwrap();
+        dropped_files.retain(|dropped| *dropped != path);
+        if dropped_files.len() == MAX_DROPPED_FILES {
+            let _ = dropped_files.pop_front();
+        }
+        dropped_files.push_back(path);
+    }
+
+    /// Returns whether the given native path was dropped onto the window,
+    /// and is still among the last 64 dropped files.
+    pub fn is_dropped_file<P: AsRef<path::Path>>(&self, path: P) -> bool {
+        self.dropped_files
+            .read()
+            .unwrap()
+            .iter()
+            .any(|dropped| dropped == path.as_ref())
     }
 
     /// Opens a file in the user directory with the given
@@ -292,12 +373,24 @@ impl Filesystem {
     }
 
     /// Check whether a file or directory exists.
+    ///
+    /// Like [`Filesystem::open`], this includes files dropped onto the window.
     pub fn exists<P: AsRef<path::Path>>(&self, path: P) -> bool {
-        self.vfs().exists(path.as_ref())
+        let path = path.as_ref();
+        if self.is_dropped_file(path) {
+            return path.exists();
+        }
+        self.vfs().exists(path)
     }
 
     /// Check whether a path points at a file.
+    ///
+    /// Like [`Filesystem::open`], this includes files dropped onto the window.
     pub fn is_file<P: AsRef<path::Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        if self.is_dropped_file(path) {
+            return path.is_file();
+        }
         self.vfs()
             .metadata(path.as_ref())
             .map(|m| m.is_file())
@@ -305,7 +398,13 @@ impl Filesystem {
     }
 
     /// Check whether a path points at a directory.
+    ///
+    /// Like [`Filesystem::open`], this includes directories dropped onto the window.
     pub fn is_dir<P: AsRef<path::Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        if self.is_dropped_file(path) {
+            return path.is_dir();
+        }
         self.vfs()
             .metadata(path.as_ref())
             .map(|m| m.is_dir())
@@ -365,6 +464,92 @@ impl Filesystem {
         let physfs = vfs::PhysicalFS::new(path, readonly);
         trace!("Mounting new path: {physfs:?}");
         self.vfs_mut().push_back(Box::new(physfs));
+
+        #[cfg(feature = "hot-reload")]
+        {
+            let mut watcher = self.watcher.lock().unwrap();
+            if watcher.watcher.is_some() {
+                if let Err(e) = Self::watch_root(&mut watcher, path) {
+                    warn!("Failed to watch {}: {e}", path.display());
+                }
+            }
+        }
+    }
+
+    /// Starts watching the physical directories mounted in this filesystem (such as the
+    /// `resources/` directory) for changes to their files, for hot-reloading during development.
+    /// Directories mounted later on are watched too. Calling this again does nothing.
+    ///
+    /// See [`Filesystem::change_count`] and [`graphics::hot_reload`](crate::graphics::hot_reload).
+    /// Only available with the `hot-reload` feature.
+    #[cfg(feature = "hot-reload")]
+    pub fn watch(&self) -> GameResult {
+        let mut watcher = self.watcher.lock().unwrap();
+        if watcher.watcher.is_some() {
+            return Ok(());
+        }
+
+        let changes = watcher.changes.clone();
+        watcher.watcher = Some(
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if !event.kind.is_access() => {
+                    let mut changes = changes.write().unwrap();
+                    for path in &event.paths {
+                        changes.record(path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Error watching the filesystem: {e}"),
+            })
+            .map_err(|e| GameError::FilesystemError(format!("failed to watch files: {e}")))?,
+        );
+
+        let roots = self
+            .vfs()
+            .roots()
+            .iter()
+            .filter_map(|root| root.to_path_buf())
+            .collect::<Vec<_>>();
+        for root in roots {
+            // Skips zip files and directories that don't exist.
+            if root.is_dir() {
+                Self::watch_root(&mut watcher, &root)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "hot-reload")]
+    fn watch_root(watcher: &mut FileWatcher, root: &path::Path) -> GameResult {
+        watcher
+            .watcher
+            .as_mut()
+            .unwrap(/* only called while watching */)
+            .watch(root, notify::RecursiveMode::Recursive)
+            .map_err(|e| {
+                GameError::FilesystemError(format!("failed to watch {}: {e}", root.display()))
+            })?;
+        watcher
+            .changes
+            .write()
+            .unwrap()
+            .roots
+            .push(root.to_path_buf());
+        Ok(())
+    }
+
+    /// Returns how many times the file at `path` has changed on disk since this was first called
+    /// for it, or 0 if it isn't being watched. Only the files asked about are tracked, so call this
+    /// once up front, before the changes you want to notice.
+    ///
+    /// Editors may touch a file several times when saving it, so only compare this against an
+    /// earlier count rather than relying on its exact value.
+    #[cfg(feature = "hot-reload")]
+    pub fn change_count<P: AsRef<path::Path>>(&self, path: P) -> u64 {
+        let path = path::Path::new("/").join(path);
+        let watcher = self.watcher.lock().unwrap();
+        let mut changes = watcher.changes.write().unwrap();
+        *changes.counts.entry(path).or_default()
     }
 
     /// Adds any object that implements Read + Seek as a zip file.
@@ -438,7 +623,9 @@ impl Filesystem {
 mod tests {
     use crate::conf;
     use crate::error::GameError;
-    use crate::filesystem::{env, vfs, Arc, Filesystem, RwLock, CONFIG_NAME};
+    #[cfg(feature = "hot-reload")]
+    use crate::filesystem::FileChanges;
+    use crate::filesystem::{env, vfs, Arc, Filesystem, RwLock, CONFIG_NAME, MAX_DROPPED_FILES};
     use std::io::{Read, Write};
     use std::path;
 
@@ -455,6 +642,9 @@ mod tests {
             zip_dir: "".into(),
             user_config_dir: "".into(),
             user_data_dir: "".into(),
+            dropped_files: Arc::default(),
+            #[cfg(feature = "hot-reload")]
+            watcher: Arc::default(),
         }
     }
 
@@ -523,6 +713,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn headless_test_open_dropped_file() {
+        let fs = dummy_fs_for_tests();
+        let mut native_path = path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        native_path.push("Cargo.toml");
+
+        // Outside of the mounted roots, so not readable until it's dropped onto the window.
+        assert!(fs.open(&native_path).is_err());
+        assert!(!fs.exists(&native_path));
+
+        fs.add_dropped_file(native_path.clone());
+        assert!(fs.is_dropped_file(&native_path));
+        assert!(fs.exists(&native_path));
+        assert!(fs.is_file(&native_path));
+        assert!(!fs.is_dir(&native_path));
+        let contents = fs.read_to_string(&native_path).unwrap();
+        assert!(contents.contains("[package]"));
+
+        // Only the most recently dropped files are remembered.
+        for i in 0..MAX_DROPPED_FILES {
+            fs.add_dropped_file(path::PathBuf::from(format!("/dropped/{i}")));
+        }
+        assert!(!fs.is_dropped_file(&native_path));
+    }
+
     #[test]
     fn headless_test_write_config() {
         let f = dummy_fs_for_tests();
@@ -536,4 +751,24 @@ mod tests {
         // Remove the config file!
         f.delete(CONFIG_NAME).unwrap();
     }
+
+    #[cfg(feature = "hot-reload")]
+    #[test]
+    fn headless_test_file_changes() {
+        let mut changes = FileChanges {
+            roots: vec!["/game/resources".into(), "/game/resources/shaders".into()],
+            ..Default::default()
+        };
+        for tracked in ["/shaders/post.wgsl", "/post.wgsl"] {
+            let _ = changes.counts.insert(tracked.into(), 0);
+        }
+        changes.record(path::Path::new("/game/resources/shaders/post.wgsl"));
+        changes.record(path::Path::new("/game/resources/shaders/post.wgsl"));
+        changes.record(path::Path::new("/game/resources/shaders/blur.wgsl"));
+        changes.record(path::Path::new("/elsewhere/tile.png"));
+
+        assert_eq!(changes.counts[path::Path::new("/shaders/post.wgsl")], 2);
+        assert_eq!(changes.counts[path::Path::new("/post.wgsl")], 2);
+        assert_eq!(changes.counts.len(), 2);
+    }
 }
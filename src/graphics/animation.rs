@@ -0,0 +1,517 @@
+use super::{
+    atlas::{from_json, resolve_relative},
+    context::GraphicsContext,
+    Atlas, Canvas, DrawParam, Drawable, Image, Rect,
+};
+use crate::{context::Has, GameError, GameResult};
+use std::{collections::BTreeMap, fmt, marker::PhantomData, path::Path, time::Duration};
+
+/// How an [`Animation`] continues after its last frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum AnimationMode {
+    /// Start over at the first frame.
+    #[default]
+    Loop,
+    /// Play the frames backwards down to the first one, then forwards again, and so on.
+    PingPong,
+    /// Stop at the last frame.
+    Once,
+}
+
+/// A single frame of an [`Animation`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFrame {
+    /// The part of the image shown in this frame, in pixels.
+    pub rect: Rect,
+    /// How long this frame is shown.
+    pub duration: Duration,
+    /// An event that's fired whenever this frame is reached, see [`AnimatedSprite::events`].
+    pub event: Option<String>,
+}
+
+/// A sequence of frames from a sprite sheet, played back by an [`AnimatedSprite`].
+#[derive(Debug, Clone)]
+pub struct Animation {
+    image: Image,
+    frames: Vec<AnimationFrame>,
+    mode: AnimationMode,
+}
+
+impl Animation {
+    /// Creates an animation of `image` without any frames.
+    pub fn new(image: Image, mode: AnimationMode) -> Self {
+        Animation {
+            image,
+            frames: Vec::new(),
+            mode,
+        }
+    }
+
+    /// Creates an animation from the regions of `atlas` with the given names,
+    /// each shown for `frame_duration`.
+    pub fn from_atlas<'a>(
+        atlas: &Atlas,
+        regions: impl IntoIterator<Item = &'a str>,
+        frame_duration: Duration,
+        mode: AnimationMode,
+    ) -> GameResult<Self> {
+        let mut animation = Animation::new(atlas.image().clone(), mode);
+        for name in regions {
+            let rect = atlas.region(name).ok_or_else(|| {
+                GameError::ResourceNotFound(format!("atlas region {name:?}"), Vec::new())
+            })?;
+            animation.push_frame(rect, frame_duration);
+        }
+        Ok(animation)
+    }
+
+    /// Loads the animations of a sprite sheet exported by Aseprite as JSON, in either the
+    /// hash or array format, along with its image. This needs the `json` feature.
+    ///
+    /// Every frame tag becomes an animation named after the tag, honoring its direction.
+    /// If there are no tags, all frames become a single looping animation named `""`.
+    pub fn from_aseprite(
+        gfx: &impl Has<GraphicsContext>,
+        path: impl AsRef<Path>,
+    ) -> GameResult<BTreeMap<String, Animation>> {
+        let gfx = gfx.retrieve();
+        let path = path.as_ref();
+
+        let sheet: aseprite::Sheet = from_json(&gfx.fs.read_to_string(path)?).map_err(|e| {
+            GameError::ResourceLoadError(format!("invalid Aseprite sheet {path:?}: {e}"))
+        })?;
+        let image = Image::from_path(gfx, resolve_relative(path, &sheet.meta.image))?;
+        sheet.into_animations(&image)
+    }
+
+    /// Appends a frame showing the given pixel rect of the image for `duration`.
+    pub fn push_frame(&mut self, rect: Rect, duration: Duration) {
+        self.frames.push(AnimationFrame {
+            rect,
+            duration,
+            event: None,
+        });
+    }
+
+    /// Sets the event fired whenever the frame at `index` is reached.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there's no frame at `index`.
+    pub fn set_event(&mut self, index: usize, event: impl Into<String>) {
+        self.frames[index].event = Some(event.into());
+    }
+
+    /// Sets the mode of this animation.
+    #[must_use]
+    pub fn mode(mut self, mode: AnimationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns the image the frames are taken from.
+    #[inline]
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Returns the frames of this animation.
+    #[inline]
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    /// Returns the time it takes to play each frame once.
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+}
+
+/// Plays an [`Animation`], advancing it by calling [`AnimatedSprite::update`] every frame.
+///
+/// It's drawn like an [`Image`] of its current frame, with [`DrawParam::src`]
+/// relative to that frame.
+///
+/// ```rust,no_run
+/// # use ggez::{graphics::{Animation, AnimatedSprite, Canvas}, Context, GameResult};
+/// # fn t(ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+/// let mut animations = Animation::from_aseprite(ctx, "/hero.json")?;
+/// let mut hero = AnimatedSprite::new(animations.remove("run").unwrap());
+///
+/// // in `update`
+/// hero.update(ctx.time.delta());
+/// for event in hero.events() {
+///     println!("{event}");
+/// }
+///
+/// // in `draw`
+/// canvas.draw(&hero, [100., 100.]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnimatedSprite {
+    animation: Animation,
+    playback: Playback,
+    events: Vec<String>,
+}
+
+impl AnimatedSprite {
+    /// Creates a sprite playing `animation` from its first frame.
+    pub fn new(animation: Animation) -> Self {
+        let mut sprite = AnimatedSprite {
+            animation,
+            playback: Playback::default(),
+            events: Vec::new(),
+        };
+        sprite.reset();
+        sprite
+    }
+
+    /// Switches to another animation, starting at its first frame.
+    pub fn set_animation(&mut self, animation: Animation) {
+        self.animation = animation;
+        self.reset();
+    }
+
+    /// Returns the animation being played.
+    #[inline]
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// Restarts the animation at its first frame.
+    pub fn reset(&mut self) {
+        self.playback = Playback::default();
+        self.events.clear();
+        self.push_event();
+    }
+
+    /// Advances the animation by `dt`, possibly skipping frames if `dt` is long enough.
+    pub fn update(&mut self, dt: Duration) {
+        let events = &mut self.events;
+        self.playback
+            .update(&self.animation.frames, self.animation.mode, dt, |frame| {
+                if let Some(event) = &frame.event {
+                    events.push(event.clone());
+                }
+            });
+    }
+
+    /// Returns the events of the frames reached since the last call, draining them.
+    pub fn events(&mut self) -> impl Iterator<Item = String> + '_ {
+        self.events.drain(..)
+    }
+
+    /// Returns the index of the current frame.
+    #[inline]
+    pub fn frame_index(&self) -> usize {
+        self.playback.frame
+    }
+
+    /// Returns the current frame, if the animation has any frames.
+    pub fn frame(&self) -> Option<&AnimationFrame> {
+        self.animation.frames.get(self.playback.frame)
+    }
+
+    /// Returns whether an animation in [`AnimationMode::Once`] has reached its end.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.playback.finished
+    }
+
+    fn push_event(&mut self) {
+        if let Some(event) = self.frame().and_then(|f| f.event.clone()) {
+            self.events.push(event);
+        }
+    }
+}
+
+impl Drawable for AnimatedSprite {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        let Some(frame) = self.frame() else {
+            return;
+        };
+        let image = &self.animation.image;
+        let (w, h) = (image.width() as f32, image.height() as f32);
+
+        let mut param = param.into();
+        param.src = Rect::new(
+            (frame.rect.x + param.src.x * frame.rect.w) / w,
+            (frame.rect.y + param.src.y * frame.rect.h) / h,
+            param.src.w * frame.rect.w / w,
+            param.src.h * frame.rect.h / h,
+        );
+        image.draw(canvas, param);
+    }
+
+    fn dimensions(&self, _gfx: &impl Has<GraphicsContext>) -> Rect {
+        self.frame()
+            .map_or_else(Rect::default, |f| Rect::new(0., 0., f.rect.w, f.rect.h))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Playback {
+    frame: usize,
+    elapsed: Duration,
+    backwards: bool,
+    finished: bool,
+}
+
+impl Playback {
+    /// Advances by `dt`, calling `on_frame` for every frame reached.
+    fn update(
+        &mut self,
+        frames: &[AnimationFrame],
+        mode: AnimationMode,
+        dt: Duration,
+        mut on_frame: impl FnMut(&AnimationFrame),
+    ) {
+        if frames.is_empty() || self.finished {
+            return;
+        }
+
+        self.elapsed += dt;
+        loop {
+            // Zero-length frames would never let us leave this loop otherwise.
+            let duration = frames[self.frame].duration.max(Duration::from_millis(1));
+            if self.elapsed < duration {
+                break;
+            }
+
+            if !self.advance(frames.len(), mode) {
+                self.finished = true;
+                self.elapsed = Duration::ZERO;
+                break;
+            }
+            self.elapsed -= duration;
+            on_frame(&frames[self.frame]);
+        }
+    }
+
+    /// Moves to the next frame, returning `false` if there is none.
+    fn advance(&mut self, len: usize, mode: AnimationMode) -> bool {
+        match mode {
+            AnimationMode::Loop => self.frame = (self.frame + 1) % len,
+            AnimationMode::Once if self.frame + 1 < len => self.frame += 1,
+            AnimationMode::Once => return false,
+            AnimationMode::PingPong if len == 1 => {}
+            AnimationMode::PingPong => {
+                if (self.backwards && self.frame == 0) || (!self.backwards && self.frame + 1 == len)
+                {
+                    self.backwards = !self.backwards;
+                }
+                if self.backwards {
+                    self.frame -= 1;
+                } else {
+                    self.frame += 1;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// The parts of Aseprite's JSON export we need.
+mod aseprite {
+    use super::*;
+
+    #[derive(Debug, serde::Deserialize)]
+    pub(super) struct Sheet {
+        pub frames: Frames,
+        pub meta: Meta,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum Frames {
+        Hash(OrderedMap<Frame>),
+        Array(Vec<Frame>),
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    pub(super) struct Frame {
+        frame: FrameRect,
+        duration: u64,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    pub(super) struct FrameRect {
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    pub(super) struct Meta {
+        pub image: String,
+        #[serde(default, rename = "frameTags")]
+        frame_tags: Vec<FrameTag>,
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct FrameTag {
+        name: String,
+        from: usize,
+        to: usize,
+        #[serde(default)]
+        direction: String,
+    }
+
+    impl Sheet {
+        pub fn into_animations(self, image: &Image) -> GameResult<BTreeMap<String, Animation>> {
+            let frames: Vec<AnimationFrame> = match self.frames {
+                Frames::Hash(OrderedMap(frames)) => frames.into_iter().map(|(_, f)| f).collect(),
+                Frames::Array(frames) => frames,
+            }
+            .into_iter()
+            .map(|f| AnimationFrame {
+                rect: Rect::new(f.frame.x, f.frame.y, f.frame.w, f.frame.h),
+                duration: Duration::from_millis(f.duration),
+                event: None,
+            })
+            .collect();
+
+            if self.meta.frame_tags.is_empty() {
+                let mut animation = Animation::new(image.clone(), AnimationMode::Loop);
+                animation.frames = frames;
+                return Ok(BTreeMap::from([(String::new(), animation)]));
+            }
+
+            self.meta
+                .frame_tags
+                .into_iter()
+                .map(|tag| {
+                    let tagged = frames.get(tag.from..=tag.to).ok_or_else(|| {
+                        GameError::ResourceLoadError(format!(
+                            "Aseprite tag {:?} refers to missing frames {}..={}",
+                            tag.name, tag.from, tag.to
+                        ))
+                    })?;
+
+                    let mut animation = Animation::new(image.clone(), AnimationMode::Loop);
+                    animation.frames = tagged.to_vec();
+                    match tag.direction.as_str() {
+                        "reverse" => animation.frames.reverse(),
+                        "pingpong" => animation.mode = AnimationMode::PingPong,
+                        "pingpong_reverse" => {
+                            animation.frames.reverse();
+                            animation.mode = AnimationMode::PingPong;
+                        }
+                        _ => {}
+                    }
+                    Ok((tag.name, animation))
+                })
+                .collect()
+        }
+    }
+
+    /// A JSON object whose entries keep the order they're written in.
+    #[derive(Debug)]
+    pub(super) struct OrderedMap<T>(pub Vec<(String, T)>);
+
+    impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for OrderedMap<T> {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct Visitor<T>(PhantomData<T>);
+
+            impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for Visitor<T> {
+                type Value = OrderedMap<T>;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a map")
+                }
+
+                fn visit_map<A: serde::de::MapAccess<'de>>(
+                    self,
+                    mut map: A,
+                ) -> Result<Self::Value, A::Error> {
+                    let mut entries = Vec::new();
+                    while let Some(entry) = map.next_entry()? {
+                        entries.push(entry);
+                    }
+                    Ok(OrderedMap(entries))
+                }
+            }
+
+            deserializer.deserialize_map(Visitor(PhantomData))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(events: &[Option<&str>]) -> Vec<AnimationFrame> {
+        events
+            .iter()
+            .map(|event| AnimationFrame {
+                rect: Rect::new(0., 0., 1., 1.),
+                duration: Duration::from_millis(100),
+                event: event.map(String::from),
+            })
+            .collect()
+    }
+
+    fn play(mode: AnimationMode, len: usize, steps: usize) -> Vec<usize> {
+        let frames = frames(&vec![None; len]);
+        let mut playback = Playback::default();
+        let mut visited = vec![playback.frame];
+        for _ in 0..steps {
+            playback.update(&frames, mode, Duration::from_millis(100), |_| {});
+            visited.push(playback.frame);
+        }
+        visited
+    }
+
+    #[test]
+    fn headless_test_animation_modes() {
+        assert_eq!(play(AnimationMode::Loop, 3, 4), [0, 1, 2, 0, 1]);
+        assert_eq!(play(AnimationMode::PingPong, 3, 5), [0, 1, 2, 1, 0, 1]);
+        assert_eq!(play(AnimationMode::Once, 3, 4), [0, 1, 2, 2, 2]);
+        assert_eq!(play(AnimationMode::PingPong, 1, 2), [0, 0, 0]);
+    }
+
+    #[test]
+    fn headless_test_animation_events() {
+        let frames = frames(&[None, Some("step"), None]);
+        let mut playback = Playback::default();
+        let mut events = Vec::new();
+
+        // Skips two frames at once, still firing the event of the one skipped over.
+        playback.update(
+            &frames,
+            AnimationMode::Once,
+            Duration::from_millis(250),
+            |f| events.extend(f.event.clone()),
+        );
+        assert_eq!(playback.frame, 2);
+        assert_eq!(events, ["step"]);
+
+        playback.update(&frames, AnimationMode::Once, Duration::from_secs(1), |_| {});
+        assert!(playback.finished);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn headless_test_aseprite_frame_order() {
+        let json = r#"{
+            "frames": {
+                "hero 10.png": { "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "duration": 100 },
+                "hero 2.png": { "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 50 }
+            },
+            "meta": { "image": "hero.png", "frameTags": [] }
+        }"#;
+        let sheet: aseprite::Sheet = from_json(json).unwrap();
+        assert_eq!(sheet.meta.image, "hero.png");
+        match sheet.frames {
+            aseprite::Frames::Hash(map) => {
+                let names: Vec<_> = map.0.iter().map(|(name, _)| name.as_str()).collect();
+                assert_eq!(names, ["hero 10.png", "hero 2.png"]);
+            }
+            aseprite::Frames::Array(_) => panic!("parsed as the wrong format"),
+        }
+    }
+}
@@ -0,0 +1,441 @@
+use super::{context::GraphicsContext, DrawParam, Image, InstanceArray, Rect};
+use crate::{context::Has, GameError, GameResult};
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+};
+
+/// Layout of a sprite sheet made up of equally sized tiles, see [`Atlas::from_grid`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct AtlasGrid {
+    /// Width of a tile in pixels.
+    pub tile_width: u32,
+    /// Height of a tile in pixels.
+    pub tile_height: u32,
+    /// Space around the whole grid in pixels.
+    #[serde(default)]
+    pub margin: u32,
+    /// Space between two tiles in pixels.
+    #[serde(default)]
+    pub spacing: u32,
+    /// Names of the tiles, going row by row, from left to right.
+    /// Tiles without a name are named by their index instead, i.e. `"0"`, `"1"` etc.
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+impl AtlasGrid {
+    /// Creates a grid of tiles with the given size, without margin or spacing.
+    pub fn new(tile_width: u32, tile_height: u32) -> Self {
+        AtlasGrid {
+            tile_width,
+            tile_height,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the space around the whole grid.
+    #[must_use]
+    pub fn margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Sets the space between two tiles.
+    #[must_use]
+    pub fn spacing(mut self, spacing: u32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Sets the names of the tiles, going row by row, from left to right.
+    #[must_use]
+    pub fn names(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.names = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Returns the named tiles that fit into an image of the given size, as pixel rectangles.
+    fn regions(&self, width: u32, height: u32) -> impl Iterator<Item = (String, Rect)> + '_ {
+        let count = |size: u32, tile: u32| {
+            if tile == 0 {
+                return 0;
+            }
+            (size.saturating_sub(2 * self.margin) + self.spacing) / (tile + self.spacing)
+        };
+        let columns = count(width, self.tile_width);
+        let rows = count(height, self.tile_height);
+
+        (0..rows * columns).map(move |i| {
+            let (column, row) = (i % columns, i / columns);
+            let name = self
+                .names
+                .get(i as usize)
+                .cloned()
+                .unwrap_or_else(|| i.to_string());
+            let rect = Rect::new(
+                (self.margin + column * (self.tile_width + self.spacing)) as f32,
+                (self.margin + row * (self.tile_height + self.spacing)) as f32,
+                self.tile_width as f32,
+                self.tile_height as f32,
+            );
+            (name, rect)
+        })
+    }
+}
+
+/// An [`Image`] together with named sub-rectangles ("regions") of it, such as the frames of
+/// a sprite sheet.
+///
+/// Regions are stored in pixels and handed out as [`DrawParam`]s with the matching
+/// [`src`](DrawParam::src) rect, so drawing the atlas image with one of them draws just
+/// that region:
+///
+/// ```rust,no_run
+/// # use ggez::{graphics::{Atlas, AtlasGrid, Canvas, Image}, Context, GameResult};
+/// # fn t(ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+/// let image = Image::from_path(ctx, "/tiles.png")?;
+/// let atlas = Atlas::from_grid(image, &AtlasGrid::new(16, 16).names(["grass", "water"]));
+///
+/// let water = atlas.param("water").unwrap();
+/// canvas.draw(atlas.image(), water.dest([32., 0.]));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Atlas {
+    image: Image,
+    regions: BTreeMap<String, Rect>,
+}
+
+impl Atlas {
+    /// Creates an atlas without any regions.
+    pub fn new(image: Image) -> Self {
+        Atlas {
+            image,
+            regions: BTreeMap::new(),
+        }
+    }
+
+    /// Creates an atlas with a region for every tile of the grid which fits into the image.
+    pub fn from_grid(image: Image, grid: &AtlasGrid) -> Self {
+        let regions = grid.regions(image.width(), image.height()).collect();
+        Atlas { image, regions }
+    }
+
+    /// Loads an atlas description from the given path, along with the image it refers to.
+    ///
+    /// The description is parsed as TOML if the path ends in `.toml`, and as JSON otherwise.
+    /// Image paths in the description are relative to the description itself, unless they start
+    /// with a `/`. The description either has an `image`, `regions` (pixel rects) and an optional
+    /// [`grid`](AtlasGrid), e.g.
+    ///
+    /// ```toml
+    /// image = "characters.png"
+    ///
+    /// [regions]
+    /// player = { x = 0, y = 0, w = 16, h = 24 }
+    /// enemy = { x = 16, y = 0, w = 16, h = 24 }
+    /// ```
+    ///
+    /// or is in the JSON hash or array format exported by TexturePacker, Aseprite and other tools,
+    /// which needs the `json` feature. Rotated frames of the latter aren't supported.
+    pub fn from_path(gfx: &impl Has<GraphicsContext>, path: impl AsRef<Path>) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        let path = path.as_ref();
+
+        let description = AtlasFile::parse(path, &gfx.fs.read_to_string(path)?)?;
+        let (image_path, grid, regions) = match description {
+            AtlasFile::Ggez {
+                image,
+                grid,
+                regions,
+            } => (image, grid, regions),
+            AtlasFile::Packer { frames, meta } => {
+                let regions = frames.into_regions().map_err(|e| {
+                    GameError::ResourceLoadError(format!("invalid atlas description {path:?}: {e}"))
+                })?;
+                (meta.image, None, regions)
+            }
+        };
+
+        let image = Image::from_path(gfx, resolve_relative(path, &image_path))?;
+        let mut atlas = match grid {
+            Some(grid) => Atlas::from_grid(image, &grid),
+            None => Atlas::new(image),
+        };
+        atlas.regions.extend(regions);
+        Ok(atlas)
+    }
+
+    /// Adds a region with the given name and pixel rect, replacing any region of the same name.
+    pub fn add_region(&mut self, name: impl Into<String>, rect: Rect) {
+        let _ = self.regions.insert(name.into(), rect);
+    }
+
+    /// Returns the image of this atlas.
+    #[inline]
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Returns the pixel rect of the region with the given name.
+    pub fn region(&self, name: &str) -> Option<Rect> {
+        self.regions.get(name).copied()
+    }
+
+    /// Returns an iterator over the names and pixel rects of all regions, ordered by name.
+    pub fn regions(&self) -> impl Iterator<Item = (&str, Rect)> {
+        self.regions
+            .iter()
+            .map(|(name, rect)| (name.as_str(), *rect))
+    }
+
+    /// Returns the rect of the region with the given name in UV coordinates,
+    /// as used by [`DrawParam::src`].
+    pub fn uv_rect(&self, name: &str) -> Option<Rect> {
+        self.region(name).map(|r| {
+            Rect::new(
+                r.x / self.image.width() as f32,
+                r.y / self.image.height() as f32,
+                r.w / self.image.width() as f32,
+                r.h / self.image.height() as f32,
+            )
+        })
+    }
+
+    /// Returns a default [`DrawParam`] with its `src` set to the region with the given name.
+    pub fn param(&self, name: &str) -> Option<DrawParam> {
+        self.uv_rect(name).map(|src| DrawParam::new().src(src))
+    }
+
+    /// Creates an [`InstanceArray`] using the image of this atlas, to be filled with
+    /// [`Atlas::push`].
+    pub fn instance_array(&self, gfx: &impl Has<GraphicsContext>) -> InstanceArray {
+        InstanceArray::new(gfx, self.image.clone())
+    }
+
+    /// Pushes an instance of the region with the given name to `instances`, with
+    /// `param`'s `src` replaced by the region.
+    ///
+    /// `instances` should use the image of this atlas, see [`Atlas::instance_array`].
+    pub fn push(
+        &self,
+        instances: &mut InstanceArray,
+        name: &str,
+        param: impl Into<DrawParam>,
+    ) -> GameResult {
+        let src = self.uv_rect(name).ok_or_else(|| {
+            GameError::ResourceNotFound(format!("atlas region {name:?}"), Vec::new())
+        })?;
+        instances.push(param.into().src(src));
+        Ok(())
+    }
+}
+
+/// Resolves `relative` against the directory of `path`, unless it's absolute already.
+///
+/// `.` and `..` components are folded, since the virtual filesystem doesn't understand them.
+pub(crate) fn resolve_relative(path: &Path, relative: &str) -> PathBuf {
+    let joined = if relative.starts_with('/') {
+        PathBuf::from(relative)
+    } else {
+        path.parent()
+            .unwrap_or_else(|| Path::new("/"))
+            .join(relative)
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let _ = resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Parses a JSON document.
+#[cfg(feature = "json")]
+pub(crate) fn from_json<T: serde::de::DeserializeOwned>(s: &str) -> Result<T, String> {
+    serde_json::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Fails, as parsing JSON needs the `json` feature.
+#[cfg(not(feature = "json"))]
+pub(crate) fn from_json<T: serde::de::DeserializeOwned>(_s: &str) -> Result<T, String> {
+    Err(String::from(
+        "reading JSON requires the `json` feature of ggez",
+    ))
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum AtlasFile {
+    Ggez {
+        image: String,
+        #[serde(default)]
+        grid: Option<AtlasGrid>,
+        #[serde(default)]
+        regions: BTreeMap<String, Rect>,
+    },
+    Packer {
+        frames: PackerFrames,
+        meta: PackerMeta,
+    },
+}
+
+impl AtlasFile {
+    fn parse(path: &Path, s: &str) -> GameResult<Self> {
+        let is_toml = path.extension().is_some_and(|ext| ext == "toml");
+        let result = if is_toml {
+            toml::from_str(s).map_err(|e| e.to_string())
+        } else {
+            from_json(s)
+        };
+        result.map_err(|e| {
+            GameError::ResourceLoadError(format!("invalid atlas description {path:?}: {e}"))
+        })
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum PackerFrames {
+    Hash(BTreeMap<String, PackerFrame>),
+    Array(Vec<PackerFrame>),
+}
+
+impl PackerFrames {
+    fn into_regions(self) -> Result<BTreeMap<String, Rect>, String> {
+        let frames: Vec<_> = match self {
+            PackerFrames::Hash(frames) => frames.into_iter().collect(),
+            PackerFrames::Array(frames) => frames
+                .into_iter()
+                .enumerate()
+                .map(|(i, frame)| {
+                    (
+                        frame.filename.clone().unwrap_or_else(|| i.to_string()),
+                        frame,
+                    )
+                })
+                .collect(),
+        };
+
+        frames
+            .into_iter()
+            .map(|(name, frame)| {
+                if frame.rotated {
+                    Err(format!("frame {name:?} is rotated, which isn't supported"))
+                } else {
+                    Ok((name, frame.frame.into()))
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PackerFrame {
+    #[serde(default)]
+    filename: Option<String>,
+    frame: PackerRect,
+    #[serde(default)]
+    rotated: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PackerRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+impl From<PackerRect> for Rect {
+    fn from(r: PackerRect) -> Self {
+        Rect::new(r.x, r.y, r.w, r.h)
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PackerMeta {
+    image: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_atlas_grid_regions() {
+        let grid = AtlasGrid::new(16, 8).margin(1).spacing(2).names(["a", "b"]);
+        // (52 - 2 + 2) / 18 = 2 columns and (30 - 2 + 2) / 10 = 3 rows
+        let regions: Vec<_> = grid.regions(52, 30).collect();
+        assert_eq!(regions.len(), 6);
+        assert_eq!(regions[0], ("a".to_string(), Rect::new(1., 1., 16., 8.)));
+        assert_eq!(regions[1], ("b".to_string(), Rect::new(19., 1., 16., 8.)));
+        assert_eq!(regions[5], ("5".to_string(), Rect::new(19., 21., 16., 8.)));
+    }
+
+    #[test]
+    fn headless_test_atlas_descriptions() {
+        let toml = r#"
+            image = "sheet.png"
+            grid = { tile_width = 8, tile_height = 8 }
+            [regions]
+            player = { x = 0, y = 0, w = 16, h = 24 }
+        "#;
+        match AtlasFile::parse(Path::new("/a.toml"), toml).unwrap() {
+            AtlasFile::Ggez {
+                image,
+                grid,
+                regions,
+            } => {
+                assert_eq!(image, "sheet.png");
+                assert_eq!(grid, Some(AtlasGrid::new(8, 8)));
+                assert_eq!(regions["player"], Rect::new(0., 0., 16., 24.));
+            }
+            other => panic!("parsed as the wrong format: {other:?}"),
+        }
+
+        assert_eq!(
+            resolve_relative(Path::new("/sprites/a.json"), "walk.png"),
+            PathBuf::from("/sprites/walk.png")
+        );
+        assert_eq!(
+            resolve_relative(Path::new("/maps/level.tmx"), "../tiles/./ground.png"),
+            PathBuf::from("/tiles/ground.png")
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn headless_test_atlas_packer_json() {
+        let json = r#"{
+            "frames": { "walk 0": { "frame": { "x": 4, "y": 0, "w": 4, "h": 4 }, "rotated": false } },
+            "meta": { "image": "walk.png", "size": { "w": 8, "h": 4 } }
+        }"#;
+        match AtlasFile::parse(Path::new("/a.json"), json).unwrap() {
+            AtlasFile::Packer { frames, meta } => {
+                assert_eq!(meta.image, "walk.png");
+                let regions = frames.into_regions().unwrap();
+                assert_eq!(regions.len(), 1);
+                assert_eq!(regions["walk 0"], Rect::new(4., 0., 4., 4.));
+            }
+            other => panic!("parsed as the wrong format: {other:?}"),
+        }
+
+        let rotated = r#"{
+            "frames": [{ "filename": "walk 0", "frame": { "x": 4, "y": 0, "w": 4, "h": 4 }, "rotated": true }],
+            "meta": { "image": "walk.png" }
+        }"#;
+        match AtlasFile::parse(Path::new("/a.json"), rotated).unwrap() {
+            AtlasFile::Packer { frames, .. } => assert!(frames.into_regions().is_err()),
+            other => panic!("parsed as the wrong format: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use super::{internal_canvas::screen_to_mat, Canvas, Rect};
+use crate::{glam::*, input::mouse::MouseContext};
+use std::time::Duration;
+
+/// A 2d camera looking at a point of the world, with zoom, rotation, smoothed following
+/// and screen shake.
+///
+/// Apply it to a [`Canvas`] with [`Camera2d::apply`] to draw in world coordinates,
+/// and convert between screen and world coordinates (e.g. for mouse picking) with
+/// [`Camera2d::screen_to_world`] and [`Camera2d::world_to_screen`].
+///
+/// ```rust,no_run
+/// # use ggez::{graphics::{Camera2d, Canvas, Color}, Context, GameResult};
+/// # fn t(ctx: &mut Context, camera: &mut Camera2d, player: glam::Vec2) -> GameResult {
+/// // in `update`
+/// let dt = ctx.time.delta();
+/// camera.follow(player, dt);
+/// camera.update(dt);
+///
+/// // in `draw`
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// camera.apply(&mut canvas);
+/// let cursor = camera.mouse_to_world(&ctx.mouse);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2d {
+    /// The point of the world shown at the center of the viewport.
+    pub position: mint::Point2<f32>,
+    /// How much the world is scaled up, i.e. `2.0` shows everything twice as big.
+    /// Zooms below [`Camera2d::MIN_ZOOM`], which would make the view collapse to a point
+    /// or flip it, are treated as `MIN_ZOOM`.
+    pub zoom: f32,
+    /// The rotation of the camera in radians. The world appears rotated the opposite way.
+    pub rotation: f32,
+    /// The size of the area drawn to in screen coordinates, usually the size of the canvas.
+    pub viewport: mint::Vector2<f32>,
+    /// The part of the world the camera is kept inside of, if any. If the view is bigger than
+    /// the bounds, the camera is centered on them instead. Rotation isn't taken into account.
+    pub bounds: Option<Rect>,
+    /// How quickly [`Camera2d::follow`] catches up with its target; roughly the fraction of the
+    /// remaining distance covered per 1/`follow_speed` seconds. `f32::INFINITY` snaps to it.
+    pub follow_speed: f32,
+    /// The maximum offset of a shake in screen units, reached at full trauma.
+    pub max_shake_offset: f32,
+    /// The maximum rotation of a shake in radians, reached at full trauma.
+    pub max_shake_rotation: f32,
+    /// How fast shakes are, in oscillations per second.
+    pub shake_frequency: f32,
+    /// How much trauma wears off per second.
+    pub trauma_decay: f32,
+    trauma: f32,
+    shake_time: f32,
+}
+
+impl Camera2d {
+    /// The smallest zoom the camera views the world with.
+    pub const MIN_ZOOM: f32 = 1e-4;
+
+    /// Creates a camera for a viewport of the given size, looking at its center, so that
+    /// it initially matches the default coordinates of a [`Canvas`] of that size.
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+        Camera2d {
+            position: [viewport_width / 2., viewport_height / 2.].into(),
+            zoom: 1.,
+            rotation: 0.,
+            viewport: [viewport_width, viewport_height].into(),
+            bounds: None,
+            follow_speed: 5.,
+            max_shake_offset: 16.,
+            max_shake_rotation: 0.1,
+            shake_frequency: 15.,
+            trauma_decay: 1.,
+            trauma: 0.,
+            shake_time: 0.,
+        }
+    }
+
+    /// Sets the point of the world shown at the center of the viewport.
+    #[must_use]
+    pub fn position(mut self, position: impl Into<mint::Point2<f32>>) -> Self {
+        self.position = position.into();
+        self
+    }
+
+    /// Sets the zoom.
+    #[must_use]
+    pub fn zoom(mut self, zoom: f32) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// Sets the rotation in radians.
+    #[must_use]
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the part of the world the camera is kept inside of.
+    #[must_use]
+    pub fn bounds(mut self, bounds: impl Into<Option<Rect>>) -> Self {
+        self.bounds = bounds.into();
+        self
+    }
+
+    /// Sets how quickly [`Camera2d::follow`] catches up with its target.
+    #[must_use]
+    pub fn follow_speed(mut self, follow_speed: f32) -> Self {
+        self.follow_speed = follow_speed;
+        self
+    }
+
+    /// Moves the camera towards `target`, smoothed according to
+    /// [`follow_speed`](#structfield.follow_speed), independent of the frame rate.
+    pub fn follow(&mut self, target: impl Into<mint::Point2<f32>>, dt: Duration) {
+        let target = Vec2::from(target.into());
+        let position = Vec2::from(self.position);
+        let t = if self.follow_speed.is_infinite() {
+            // Also avoids `inf * 0` when a frame takes no time.
+            1.
+        } else {
+            1. - (-self.follow_speed * dt.as_secs_f32()).exp()
+        };
+        self.position = position.lerp(target, t).into();
+    }
+
+    /// Adds trauma, from `0.0` to `1.0` in total, which makes the camera shake.
+    /// The shake grows with the square of the trauma, so small hits stay subtle.
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0., 1.);
+    }
+
+    /// Returns the current trauma.
+    #[inline]
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Advances the shake and lets trauma wear off. Call this once per update.
+    pub fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        self.shake_time += dt;
+        self.trauma = (self.trauma - self.trauma_decay * dt).max(0.);
+    }
+
+    /// Returns the point the camera is centered on, after clamping it to the bounds.
+    pub fn center(&self) -> mint::Point2<f32> {
+        let position = Vec2::from(self.position);
+        let Some(bounds) = self.bounds else {
+            return position.into();
+        };
+
+        let half_view = Vec2::from(self.viewport) / (2. * self.effective_zoom());
+        let clamp = |p: f32, half: f32, min: f32, size: f32| {
+            if 2. * half >= size {
+                min + size / 2.
+            } else {
+                p.clamp(min + half, min + size - half)
+            }
+        };
+        Vec2::new(
+            clamp(position.x, half_view.x, bounds.x, bounds.w),
+            clamp(position.y, half_view.y, bounds.y, bounds.h),
+        )
+        .into()
+    }
+
+    /// Returns the transform from world to screen coordinates, including the current shake.
+    pub fn view_matrix(&self) -> mint::ColumnMatrix4<f32> {
+        self.view().into()
+    }
+
+    /// Returns the projection matrix for drawing the world through this camera,
+    /// as used by [`Canvas::set_projection`].
+    pub fn to_matrix(&self) -> mint::ColumnMatrix4<f32> {
+        let screen = Rect::new(0., 0., self.viewport.x, self.viewport.y);
+        (screen_to_mat(screen) * self.view()).into()
+    }
+
+    /// Returns the smallest rect in world coordinates containing everything visible in
+    /// the viewport, e.g. for culling.
+    pub fn visible_rect(&self) -> Rect {
+        let corners = [
+            [0., 0.],
+            [self.viewport.x, 0.],
+            [0., self.viewport.y],
+            [self.viewport.x, self.viewport.y],
+        ]
+        .map(|corner| Vec2::from(self.screen_to_world(corner)));
+
+        let min = corners.iter().fold(Vec2::INFINITY, |min, &c| min.min(c));
+        let max = corners
+            .iter()
+            .fold(Vec2::NEG_INFINITY, |max, &c| max.max(c));
+        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    /// Makes `canvas` draw the world through this camera.
+    pub fn apply(&self, canvas: &mut Canvas) {
+        canvas.set_projection(self.to_matrix());
+    }
+
+    /// Converts a point in world coordinates to screen coordinates.
+    pub fn world_to_screen(&self, point: impl Into<mint::Point2<f32>>) -> mint::Point2<f32> {
+        let point = Vec2::from(point.into());
+        self.view()
+            .transform_point3(point.extend(0.))
+            .truncate()
+            .into()
+    }
+
+    /// Converts a point in screen coordinates to world coordinates.
+    pub fn screen_to_world(&self, point: impl Into<mint::Point2<f32>>) -> mint::Point2<f32> {
+        let point = Vec2::from(point.into());
+        self.view()
+            .inverse()
+            .transform_point3(point.extend(0.))
+            .truncate()
+            .into()
+    }
+
+    /// Returns the position of the mouse cursor in world coordinates.
+    ///
+    /// This assumes the viewport covers the whole window, with one screen unit per pixel.
+    pub fn mouse_to_world(&self, mouse: &MouseContext) -> mint::Point2<f32> {
+        self.screen_to_world(mouse.position())
+    }
+
+    fn effective_zoom(&self) -> f32 {
+        // `max` also turns a NaN zoom into `MIN_ZOOM`.
+        self.zoom.max(Self::MIN_ZOOM)
+    }
+
+    fn view(&self) -> Mat4 {
+        let zoom = self.effective_zoom();
+        let (offset, rotation) = self.shake();
+        let half_viewport = Vec2::from(self.viewport) / 2.;
+        Mat4::from_translation((half_viewport + offset).extend(0.))
+            * Mat4::from_rotation_z(-(self.rotation + rotation))
+            * Mat4::from_scale(Vec3::new(zoom, zoom, 1.))
+            * Mat4::from_translation(-Vec2::from(self.center()).extend(0.))
+    }
+
+    /// Returns the current shake offset in screen units and rotation in radians.
+    fn shake(&self) -> (Vec2, f32) {
+        let shake = self.trauma * self.trauma;
+        if shake == 0. {
+            return (Vec2::ZERO, 0.);
+        }
+
+        let t = self.shake_time * self.shake_frequency * std::f32::consts::TAU;
+        let offset = Vec2::new(noise(t, 0.), noise(t, 1.)) * self.max_shake_offset * shake;
+        (offset, noise(t, 2.) * self.max_shake_rotation * shake)
+    }
+}
+
+/// Cheap, smooth pseudo-noise in `[-1, 1]`, with independent-looking channels per `seed`.
+fn noise(t: f32, seed: f32) -> f32 {
+    let phase = seed * 12.9898;
+    let sum = (t + phase).sin()
+        + 0.5 * (2.17 * t + 1.3 * phase).sin()
+        + 0.25 * (4.31 * t + 2.1 * phase).sin();
+    sum / 1.75
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: mint::Point2<f32>, b: mint::Point2<f32>) {
+        assert!(
+            Vec2::from(a).distance(Vec2::from(b)) < 1e-3,
+            "{a:?} is not near {b:?}"
+        );
+    }
+
+    #[test]
+    fn headless_test_camera2d_round_trip() {
+        let camera = Camera2d::new(800., 600.)
+            .position([100., -50.])
+            .zoom(2.)
+            .rotation(0.5);
+
+        // The position ends up at the center of the screen.
+        assert_near(camera.world_to_screen([100., -50.]), [400., 300.].into());
+
+        let world = [123., 45.].into();
+        assert_near(camera.screen_to_world(camera.world_to_screen(world)), world);
+    }
+
+    #[test]
+    fn headless_test_camera2d_default_matches_canvas() {
+        let camera = Camera2d::new(800., 600.);
+        assert_near(camera.world_to_screen([10., 20.]), [10., 20.].into());
+    }
+
+    #[test]
+    fn headless_test_camera2d_bounds() {
+        let camera = Camera2d::new(100., 100.)
+            .bounds(Rect::new(0., 0., 1000., 1000.))
+            .position([0., 990.]);
+        assert_near(camera.center(), [50., 950.].into());
+
+        let visible = camera.visible_rect();
+        assert_near(visible.point(), [0., 900.].into());
+        assert_near([visible.w, visible.h].into(), [100., 100.].into());
+
+        // A view bigger than the bounds is centered on them.
+        let camera = camera.zoom(0.05);
+        assert_near(camera.center(), [500., 500.].into());
+    }
+
+    #[test]
+    fn headless_test_camera2d_degenerate() {
+        let mut camera = Camera2d::new(100., 100.).follow_speed(f32::INFINITY);
+        camera.follow([30., 40.], Duration::ZERO);
+        assert_near(camera.position, [30., 40.].into());
+
+        // A zero zoom would make the view impossible to invert.
+        let camera = camera.zoom(0.);
+        let world = camera.screen_to_world([10., 10.]);
+        assert!(world.x.is_finite() && world.y.is_finite());
+    }
+}
@@ -60,6 +60,8 @@ impl Canvas3d {
     /// `clear` will set the image initially to the given color, if a color is provided, or keep it as is, if it's `None`.
     ///
     /// The image must be created for Canvas3d usage, i.e. [`Image::new_canvas_image`()], or [`ScreenImage`], and must only have a sample count of 1.
+    /// To draw to an array or cube image, pass one of its layers, see [`Image::layer`];
+    /// [`Canvas3d::finish`] fails otherwise.
     #[inline]
     pub fn from_image(
         gfx: &impl Has<GraphicsContext>,
@@ -355,6 +357,7 @@ impl Canvas3d {
     #[inline]
     pub fn finish(mut self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult {
         let gfx = gfx.retrieve_mut();
+        self.target.check_2d()?;
         self.finalize(gfx)
     }
 
@@ -439,6 +442,13 @@ impl Canvas3d {
 
         canvas.finish();
 
+        // Keep the lower mip levels of mipmapped targets in sync with what was just drawn.
+        let target = self.resolve.as_ref().unwrap_or(&self.target);
+        if let Some(fcx) = &mut gfx.fcx {
+            gfx.mipmaps
+                .generate(&gfx.wgpu.device, &mut fcx.cmd, target.view.texture());
+        }
+
         Ok(())
     }
 }
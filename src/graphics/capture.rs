@@ -0,0 +1,265 @@
+use super::{
+    image::{encodable_pixels, encode_pixels},
+    GraphicsContext, Image, ImageEncodingFormat, ImageFormat, PixelReadback,
+};
+use crate::{GameError, GameResult};
+use std::{
+    io::Write,
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How many recorded frames may be on their way back from the GPU or waiting to be encoded.
+/// Frames that come due while that many are in flight are skipped, so that a recording that
+/// can't keep up drops frames instead of piling them up in memory.
+const MAX_PENDING_FRAMES: usize = 8;
+
+/// Keeps track of the screenshots and recordings requested of the presented frame;
+/// see [`GraphicsContext::capture_next_frame`](super::GraphicsContext::capture_next_frame)
+/// and [`GraphicsContext::start_recording`](super::GraphicsContext::start_recording).
+#[derive(Debug, Default)]
+pub(crate) struct FrameCapture {
+    screenshot: Option<PathBuf>,
+    recording: Option<Recording>,
+    pending: Vec<PendingFrame>,
+    encoder: Option<Encoder>,
+}
+
+#[derive(Debug)]
+struct Recording {
+    dir: PathBuf,
+    interval: Duration,
+    next: Instant,
+    frame: u32,
+    skipped: u32,
+}
+
+/// A recorded frame whose pixels are still on their way back from the GPU.
+#[derive(Debug)]
+struct PendingFrame {
+    readback: PixelReadback,
+    format: ImageFormat,
+    path: PathBuf,
+}
+
+impl FrameCapture {
+    pub fn capture_next_frame(&mut self, path: PathBuf) {
+        self.screenshot = Some(path);
+    }
+
+    pub fn start_recording(&mut self, dir: PathBuf, fps: f32) -> GameResult {
+        if !fps.is_finite() || fps <= 0. {
+            return Err(GameError::RenderError(format!(
+                "the recording frame rate must be positive, got {fps}"
+            )));
+        }
+        std::fs::create_dir_all(&dir)?;
+
+        self.recording = Some(Recording {
+            dir,
+            interval: Duration::from_secs_f32(1. / fps),
+            next: Instant::now(),
+            frame: 0,
+            skipped: 0,
+        });
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recording = None;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Captures the frame that was just submitted, if asked to, and hands the recorded frames
+    /// that arrived from the GPU in the meantime to the encoder thread.
+    ///
+    /// Errors from encoding earlier frames are reported here, a few frames late.
+    pub fn end_frame(&mut self, gfx: &GraphicsContext, frame: &Image) -> GameResult {
+        let encoder = self.encoder.get_or_insert_with(Encoder::spawn);
+        let mut result = encoder.finished();
+
+        if let Some(path) = self.screenshot.take() {
+            encoder.encode(EncodeJob {
+                out: Box::new(gfx.fs.create(path)?),
+                format: frame.format(),
+                pixels: frame.to_pixels(gfx)?,
+                width: frame.width(),
+                height: frame.height(),
+            });
+        }
+
+        if let Some(recording) = &mut self.recording {
+            let now = Instant::now();
+            if now >= recording.next {
+                if self.pending.len() + encoder.queued < MAX_PENDING_FRAMES {
+                    self.pending.push(PendingFrame {
+                        readback: frame.to_pixels_async(gfx, None)?,
+                        format: frame.format(),
+                        path: recording
+                            .dir
+                            .join(format!("frame_{:06}.png", recording.frame)),
+                    });
+                    recording.frame += 1;
+                } else {
+                    if recording.skipped == 0 {
+                        warn!("Frames are recorded faster than they can be written, skipping some");
+                    }
+                    recording.skipped += 1;
+                }
+                recording.next += recording.interval;
+                // Rather than bursting to catch up after a hitch, skip the frames that were missed.
+                if recording.next < now {
+                    recording.next = now + recording.interval;
+                }
+            }
+        }
+
+        self.pending.retain_mut(|pending| {
+            let Some(pixels) = pending.readback.poll() else {
+                return true;
+            };
+            let queued = pixels.and_then(|pixels| {
+                encoder.encode(EncodeJob {
+                    out: Box::new(std::fs::File::create(&pending.path)?),
+                    format: pending.format,
+                    pixels,
+                    width: pending.readback.width(),
+                    height: pending.readback.height(),
+                });
+                Ok(())
+            });
+            if queued.is_err() && result.is_ok() {
+                result = queued;
+            }
+            false
+        });
+        result
+    }
+}
+
+/// A frame to be written out as a PNG.
+struct EncodeJob {
+    out: Box<dyn Write + Send>,
+    format: ImageFormat,
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl EncodeJob {
+    fn run(self) -> GameResult {
+        let (pixels, color) = encodable_pixels(self.format, self.pixels)?;
+        encode_pixels(
+            self.out,
+            ImageEncodingFormat::Png,
+            &pixels,
+            self.width,
+            self.height,
+            color,
+        )
+    }
+}
+
+/// A thread encoding captured frames, so that PNG compression doesn't stall rendering.
+#[derive(Debug)]
+struct Encoder {
+    jobs: Option<mpsc::Sender<EncodeJob>>,
+    results: mpsc::Receiver<GameResult>,
+    thread: Option<thread::JoinHandle<()>>,
+    // Jobs sent that haven't reported back yet.
+    queued: usize,
+}
+
+impl Encoder {
+    fn spawn() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<EncodeJob>();
+        let (result_sender, results) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            for job in job_receiver {
+                let _ = result_sender.send(job.run());
+            }
+        });
+
+        Encoder {
+            jobs: Some(jobs),
+            results,
+            thread: Some(thread),
+            queued: 0,
+        }
+    }
+
+    fn encode(&mut self, job: EncodeJob) {
+        if let Some(jobs) = &self.jobs {
+            if jobs.send(job).is_ok() {
+                self.queued += 1;
+            }
+        }
+    }
+
+    /// Returns the first error of the jobs finished since the last call.
+    fn finished(&mut self) -> GameResult {
+        let mut result = Ok(());
+        while let Ok(finished) = self.results.try_recv() {
+            self.queued -= 1;
+            if finished.is_err() && result.is_ok() {
+                result = finished;
+            }
+        }
+        result
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        // Let the thread write out what's still queued, e.g. a screenshot of the last frame.
+        drop(self.jobs.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_recording_rate() {
+        let dir = std::env::temp_dir().join("ggez_headless_test_recording_rate");
+        let mut capture = FrameCapture::default();
+        for fps in [0., -30., f32::NAN, f32::INFINITY] {
+            assert!(capture.start_recording(dir.clone(), fps).is_err());
+            assert!(!capture.is_recording());
+        }
+
+        capture.start_recording(dir.clone(), 30.).unwrap();
+        assert!(capture.is_recording());
+        assert!(dir.is_dir());
+        capture.stop_recording();
+        assert!(!capture.is_recording());
+    }
+
+    #[test]
+    fn headless_test_capture_encoder() {
+        let path = std::env::temp_dir().join("ggez_headless_test_capture_encoder.png");
+        let mut encoder = Encoder::spawn();
+        encoder.encode(EncodeJob {
+            out: Box::new(std::fs::File::create(&path).unwrap()),
+            format: ImageFormat::Rgba8UnormSrgb,
+            pixels: vec![255; 2 * 2 * 4],
+            width: 2,
+            height: 2,
+        });
+        assert_eq!(encoder.queued, 1);
+
+        // Dropping the encoder waits for the queued frames to be written.
+        drop(encoder);
+        let png = std::fs::read(&path).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
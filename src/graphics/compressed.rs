@@ -0,0 +1,504 @@
+//! Loading of GPU compressed textures (BCn, ETC2/EAC and ASTC) from KTX2 and DDS containers.
+//!
+//! The payload is uploaded as-is if the device supports its format, and otherwise decoded on
+//! the CPU into BGRA8, keeping any mip chain the file comes with.
+//!
+//! Only available with the `compressed-textures` feature.
+
+use std::io::Read;
+
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use wgpu::{AstcBlock, AstcChannel, TextureFormat};
+
+use super::image::mip_levels;
+use crate::{GameError, GameResult};
+
+const KTX2_MAGIC: &[u8] = b"\xABKTX 20\xBB\r\n\x1A\n";
+const DDS_MAGIC: &[u8] = b"DDS ";
+
+/// Pixel data of a texture and its mip levels, as stored in a container.
+#[derive(Debug)]
+pub(crate) struct TextureData {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// Returns whether `bytes` start like a KTX2 or DDS file.
+pub(crate) fn is_container(bytes: &[u8]) -> bool {
+    bytes.starts_with(KTX2_MAGIC) || bytes.starts_with(DDS_MAGIC)
+}
+
+/// Parses a KTX2 or DDS file.
+pub(crate) fn parse(bytes: &[u8]) -> GameResult<TextureData> {
+    let data = if bytes.starts_with(KTX2_MAGIC) {
+        parse_ktx2(bytes)?
+    } else {
+        parse_dds(bytes)?
+    };
+
+    if data.width == 0 || data.height == 0 {
+        return Err(load_error("the texture has no pixels"));
+    }
+    // wgpu needs the base level to be made of whole blocks; only lower levels may be padded.
+    let (block_width, block_height) = data.format.block_dimensions();
+    if !data.width.is_multiple_of(block_width) || !data.height.is_multiple_of(block_height) {
+        return Err(load_error(format!(
+            "its size of {}x{} isn't a multiple of the {block_width}x{block_height} blocks of {:?}",
+            data.width, data.height, data.format
+        )));
+    }
+
+    check_level_count(data.levels.len() as u32, data.width, data.height)?;
+    for (level, pixels) in data.levels.iter().enumerate() {
+        if pixels.len() < level_size(data.format, data.width, data.height, level as u32) {
+            return Err(load_error(format!("mip level {level} is truncated")));
+        }
+    }
+
+    Ok(data)
+}
+
+/// Rejects more mip levels than a full chain down to 1x1 has, which the GPU doesn't allow.
+fn check_level_count(levels: u32, width: u32, height: u32) -> GameResult {
+    let max = mip_levels(width, height);
+    if levels > max {
+        return Err(load_error(format!(
+            "it has {levels} mip levels, but a {width}x{height} texture has at most {max}"
+        )));
+    }
+    Ok(())
+}
+
+fn load_error(message: impl std::fmt::Display) -> GameError {
+    GameError::ResourceLoadError(format!("failed to load compressed texture: {message}"))
+}
+
+fn parse_ktx2(bytes: &[u8]) -> GameResult<TextureData> {
+    let reader = ktx2::Reader::new(bytes).map_err(load_error)?;
+    let header = reader.header();
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+        return Err(load_error("only 2D textures are supported"));
+    }
+
+    let format = header
+        .format
+        .and_then(ktx2_format)
+        .ok_or_else(|| load_error(format!("unsupported format {:?}", header.format)))?;
+    check_level_count(
+        header.level_count,
+        header.pixel_width,
+        header.pixel_height.max(1),
+    )?;
+
+    let levels = reader
+        .levels()
+        .map(|level| match header.supercompression_scheme {
+            None => Ok(level.data.to_vec()),
+            Some(ktx2::SupercompressionScheme::Zstandard) => {
+                let mut pixels = Vec::with_capacity(level.uncompressed_byte_length as usize);
+                let _ = ruzstd::decoding::StreamingDecoder::new(level.data)
+                    .map_err(load_error)?
+                    .read_to_end(&mut pixels)
+                    .map_err(load_error)?;
+                Ok(pixels)
+            }
+            Some(ktx2::SupercompressionScheme::ZLIB) => {
+                let mut pixels = Vec::with_capacity(level.uncompressed_byte_length as usize);
+                let _ = flate2::read::ZlibDecoder::new(level.data)
+                    .read_to_end(&mut pixels)
+                    .map_err(load_error)?;
+                Ok(pixels)
+            }
+            Some(scheme) => Err(load_error(format!(
+                "unsupported supercompression {scheme:?}"
+            ))),
+        })
+        .collect::<GameResult<_>>()?;
+
+    Ok(TextureData {
+        format,
+        width: header.pixel_width,
+        height: header.pixel_height.max(1),
+        levels,
+    })
+}
+
+fn ktx2_format(format: ktx2::Format) -> Option<TextureFormat> {
+    use ktx2::Format as K;
+
+    let astc = |block, channel| TextureFormat::Astc { block, channel };
+    let unorm = AstcChannel::Unorm;
+    let srgb = AstcChannel::UnormSrgb;
+    let hdr = AstcChannel::Hdr;
+
+    Some(match format {
+        K::R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        K::R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        K::B8G8R8A8_UNORM => TextureFormat::Bgra8Unorm,
+        K::B8G8R8A8_SRGB => TextureFormat::Bgra8UnormSrgb,
+        K::BC1_RGB_UNORM_BLOCK | K::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1RgbaUnorm,
+        K::BC1_RGB_SRGB_BLOCK | K::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1RgbaUnormSrgb,
+        K::BC2_UNORM_BLOCK => TextureFormat::Bc2RgbaUnorm,
+        K::BC2_SRGB_BLOCK => TextureFormat::Bc2RgbaUnormSrgb,
+        K::BC3_UNORM_BLOCK => TextureFormat::Bc3RgbaUnorm,
+        K::BC3_SRGB_BLOCK => TextureFormat::Bc3RgbaUnormSrgb,
+        K::BC4_UNORM_BLOCK => TextureFormat::Bc4RUnorm,
+        K::BC4_SNORM_BLOCK => TextureFormat::Bc4RSnorm,
+        K::BC5_UNORM_BLOCK => TextureFormat::Bc5RgUnorm,
+        K::BC5_SNORM_BLOCK => TextureFormat::Bc5RgSnorm,
+        K::BC6H_UFLOAT_BLOCK => TextureFormat::Bc6hRgbUfloat,
+        K::BC6H_SFLOAT_BLOCK => TextureFormat::Bc6hRgbFloat,
+        K::BC7_UNORM_BLOCK => TextureFormat::Bc7RgbaUnorm,
+        K::BC7_SRGB_BLOCK => TextureFormat::Bc7RgbaUnormSrgb,
+        K::ETC2_R8G8B8_UNORM_BLOCK => TextureFormat::Etc2Rgb8Unorm,
+        K::ETC2_R8G8B8_SRGB_BLOCK => TextureFormat::Etc2Rgb8UnormSrgb,
+        K::ETC2_R8G8B8A1_UNORM_BLOCK => TextureFormat::Etc2Rgb8A1Unorm,
+        K::ETC2_R8G8B8A1_SRGB_BLOCK => TextureFormat::Etc2Rgb8A1UnormSrgb,
+        K::ETC2_R8G8B8A8_UNORM_BLOCK => TextureFormat::Etc2Rgba8Unorm,
+        K::ETC2_R8G8B8A8_SRGB_BLOCK => TextureFormat::Etc2Rgba8UnormSrgb,
+        K::EAC_R11_UNORM_BLOCK => TextureFormat::EacR11Unorm,
+        K::EAC_R11_SNORM_BLOCK => TextureFormat::EacR11Snorm,
+        K::EAC_R11G11_UNORM_BLOCK => TextureFormat::EacRg11Unorm,
+        K::EAC_R11G11_SNORM_BLOCK => TextureFormat::EacRg11Snorm,
+        K::ASTC_4x4_UNORM_BLOCK => astc(AstcBlock::B4x4, unorm),
+        K::ASTC_4x4_SRGB_BLOCK => astc(AstcBlock::B4x4, srgb),
+        K::ASTC_4x4_SFLOAT_BLOCK => astc(AstcBlock::B4x4, hdr),
+        K::ASTC_5x4_UNORM_BLOCK => astc(AstcBlock::B5x4, unorm),
+        K::ASTC_5x4_SRGB_BLOCK => astc(AstcBlock::B5x4, srgb),
+        K::ASTC_5x4_SFLOAT_BLOCK => astc(AstcBlock::B5x4, hdr),
+        K::ASTC_5x5_UNORM_BLOCK => astc(AstcBlock::B5x5, unorm),
+        K::ASTC_5x5_SRGB_BLOCK => astc(AstcBlock::B5x5, srgb),
+        K::ASTC_5x5_SFLOAT_BLOCK => astc(AstcBlock::B5x5, hdr),
+        K::ASTC_6x5_UNORM_BLOCK => astc(AstcBlock::B6x5, unorm),
+        K::ASTC_6x5_SRGB_BLOCK => astc(AstcBlock::B6x5, srgb),
+        K::ASTC_6x5_SFLOAT_BLOCK => astc(AstcBlock::B6x5, hdr),
+        K::ASTC_6x6_UNORM_BLOCK => astc(AstcBlock::B6x6, unorm),
+        K::ASTC_6x6_SRGB_BLOCK => astc(AstcBlock::B6x6, srgb),
+        K::ASTC_6x6_SFLOAT_BLOCK => astc(AstcBlock::B6x6, hdr),
+        K::ASTC_8x5_UNORM_BLOCK => astc(AstcBlock::B8x5, unorm),
+        K::ASTC_8x5_SRGB_BLOCK => astc(AstcBlock::B8x5, srgb),
+        K::ASTC_8x5_SFLOAT_BLOCK => astc(AstcBlock::B8x5, hdr),
+        K::ASTC_8x6_UNORM_BLOCK => astc(AstcBlock::B8x6, unorm),
+        K::ASTC_8x6_SRGB_BLOCK => astc(AstcBlock::B8x6, srgb),
+        K::ASTC_8x6_SFLOAT_BLOCK => astc(AstcBlock::B8x6, hdr),
+        K::ASTC_8x8_UNORM_BLOCK => astc(AstcBlock::B8x8, unorm),
+        K::ASTC_8x8_SRGB_BLOCK => astc(AstcBlock::B8x8, srgb),
+        K::ASTC_8x8_SFLOAT_BLOCK => astc(AstcBlock::B8x8, hdr),
+        K::ASTC_10x5_UNORM_BLOCK => astc(AstcBlock::B10x5, unorm),
+        K::ASTC_10x5_SRGB_BLOCK => astc(AstcBlock::B10x5, srgb),
+        K::ASTC_10x5_SFLOAT_BLOCK => astc(AstcBlock::B10x5, hdr),
+        K::ASTC_10x6_UNORM_BLOCK => astc(AstcBlock::B10x6, unorm),
+        K::ASTC_10x6_SRGB_BLOCK => astc(AstcBlock::B10x6, srgb),
+        K::ASTC_10x6_SFLOAT_BLOCK => astc(AstcBlock::B10x6, hdr),
+        K::ASTC_10x8_UNORM_BLOCK => astc(AstcBlock::B10x8, unorm),
+        K::ASTC_10x8_SRGB_BLOCK => astc(AstcBlock::B10x8, srgb),
+        K::ASTC_10x8_SFLOAT_BLOCK => astc(AstcBlock::B10x8, hdr),
+        K::ASTC_10x10_UNORM_BLOCK => astc(AstcBlock::B10x10, unorm),
+        K::ASTC_10x10_SRGB_BLOCK => astc(AstcBlock::B10x10, srgb),
+        K::ASTC_10x10_SFLOAT_BLOCK => astc(AstcBlock::B10x10, hdr),
+        K::ASTC_12x10_UNORM_BLOCK => astc(AstcBlock::B12x10, unorm),
+        K::ASTC_12x10_SRGB_BLOCK => astc(AstcBlock::B12x10, srgb),
+        K::ASTC_12x10_SFLOAT_BLOCK => astc(AstcBlock::B12x10, hdr),
+        K::ASTC_12x12_UNORM_BLOCK => astc(AstcBlock::B12x12, unorm),
+        K::ASTC_12x12_SRGB_BLOCK => astc(AstcBlock::B12x12, srgb),
+        K::ASTC_12x12_SFLOAT_BLOCK => astc(AstcBlock::B12x12, hdr),
+        _ => return None,
+    })
+}
+
+fn parse_dds(bytes: &[u8]) -> GameResult<TextureData> {
+    let dds = Dds::read(bytes).map_err(load_error)?;
+    if dds.get_depth() > 1 || dds.get_num_array_layers() > 1 {
+        return Err(load_error("only 2D textures are supported"));
+    }
+
+    // Without a DX10 header, `get_dxgi_format` guesses a format from the legacy pixel format,
+    // which e.g. turns DXT1 into sRGB BC1, so only trust it with the header.
+    let format = match &dds.header10 {
+        Some(header10) => dxgi_format(header10.dxgi_format),
+        None => dds.get_d3d_format().and_then(d3d_format),
+    }
+    .ok_or_else(|| load_error("unsupported format"))?;
+    let (width, height) = (dds.get_width(), dds.get_height());
+
+    let level_count = dds.get_num_mipmap_levels().max(1);
+    check_level_count(level_count, width, height)?;
+
+    // The levels of a layer are stored back to back.
+    let mut data = dds.get_data(0).map_err(load_error)?;
+    let mut levels = Vec::new();
+    for level in 0..level_count {
+        let size = level_size(format, width, height, level).min(data.len());
+        let (pixels, rest) = data.split_at(size);
+        levels.push(pixels.to_vec());
+        data = rest;
+    }
+
+    Ok(TextureData {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+fn dxgi_format(format: DxgiFormat) -> Option<TextureFormat> {
+    Some(match format {
+        DxgiFormat::R8G8B8A8_UNorm => TextureFormat::Rgba8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => TextureFormat::Rgba8UnormSrgb,
+        DxgiFormat::B8G8R8A8_UNorm => TextureFormat::Bgra8Unorm,
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => TextureFormat::Bgra8UnormSrgb,
+        DxgiFormat::BC1_Typeless | DxgiFormat::BC1_UNorm => TextureFormat::Bc1RgbaUnorm,
+        DxgiFormat::BC1_UNorm_sRGB => TextureFormat::Bc1RgbaUnormSrgb,
+        DxgiFormat::BC2_Typeless | DxgiFormat::BC2_UNorm => TextureFormat::Bc2RgbaUnorm,
+        DxgiFormat::BC2_UNorm_sRGB => TextureFormat::Bc2RgbaUnormSrgb,
+        DxgiFormat::BC3_Typeless | DxgiFormat::BC3_UNorm => TextureFormat::Bc3RgbaUnorm,
+        DxgiFormat::BC3_UNorm_sRGB => TextureFormat::Bc3RgbaUnormSrgb,
+        DxgiFormat::BC4_Typeless | DxgiFormat::BC4_UNorm => TextureFormat::Bc4RUnorm,
+        DxgiFormat::BC4_SNorm => TextureFormat::Bc4RSnorm,
+        DxgiFormat::BC5_Typeless | DxgiFormat::BC5_UNorm => TextureFormat::Bc5RgUnorm,
+        DxgiFormat::BC5_SNorm => TextureFormat::Bc5RgSnorm,
+        DxgiFormat::BC6H_Typeless | DxgiFormat::BC6H_UF16 => TextureFormat::Bc6hRgbUfloat,
+        DxgiFormat::BC6H_SF16 => TextureFormat::Bc6hRgbFloat,
+        DxgiFormat::BC7_Typeless | DxgiFormat::BC7_UNorm => TextureFormat::Bc7RgbaUnorm,
+        DxgiFormat::BC7_UNorm_sRGB => TextureFormat::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+fn d3d_format(format: D3DFormat) -> Option<TextureFormat> {
+    Some(match format {
+        D3DFormat::A8B8G8R8 => TextureFormat::Rgba8Unorm,
+        D3DFormat::A8R8G8B8 => TextureFormat::Bgra8Unorm,
+        D3DFormat::DXT1 => TextureFormat::Bc1RgbaUnorm,
+        D3DFormat::DXT2 | D3DFormat::DXT3 => TextureFormat::Bc2RgbaUnorm,
+        D3DFormat::DXT4 | D3DFormat::DXT5 => TextureFormat::Bc3RgbaUnorm,
+        _ => return None,
+    })
+}
+
+/// Size in bytes of the given mip level, padded to whole blocks.
+pub(crate) fn level_size(format: TextureFormat, width: u32, height: u32, level: u32) -> usize {
+    let (blocks_x, blocks_y) = level_blocks(format, width, height, level);
+    // Unwrap since it only fails with depth formats.
+    (blocks_x * blocks_y * format.block_copy_size(None).unwrap()) as usize
+}
+
+/// Size in blocks of the given mip level.
+pub(crate) fn level_blocks(
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    level: u32,
+) -> (u32, u32) {
+    let (block_width, block_height) = format.block_dimensions();
+    let width = (width >> level).max(1);
+    let height = (height >> level).max(1);
+    (width.div_ceil(block_width), height.div_ceil(block_height))
+}
+
+impl TextureData {
+    /// Decodes all levels into BGRA8, for devices that don't support the compressed format.
+    pub fn decode(self) -> GameResult<TextureData> {
+        if !self.format.is_compressed() {
+            return Ok(self);
+        }
+
+        let levels = self
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(level, pixels)| {
+                let width = (self.width >> level).max(1) as usize;
+                let height = (self.height >> level).max(1) as usize;
+                decode_level(self.format, pixels, width, height)
+            })
+            .collect::<GameResult<_>>()?;
+
+        Ok(TextureData {
+            format: if self.format.is_srgb() {
+                TextureFormat::Bgra8UnormSrgb
+            } else {
+                TextureFormat::Bgra8Unorm
+            },
+            levels,
+            ..self
+        })
+    }
+}
+
+fn decode_level(
+    format: TextureFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+) -> GameResult<Vec<u8>> {
+    use texture2ddecoder as t2d;
+    use TextureFormat as F;
+
+    let mut pixels = vec![0u32; width * height];
+    let image = &mut pixels[..];
+    let result = match format {
+        F::Bc1RgbaUnorm | F::Bc1RgbaUnormSrgb => t2d::decode_bc1(data, width, height, image),
+        F::Bc2RgbaUnorm | F::Bc2RgbaUnormSrgb => decode_bc2(data, width, height, image),
+        F::Bc3RgbaUnorm | F::Bc3RgbaUnormSrgb => t2d::decode_bc3(data, width, height, image),
+        F::Bc4RUnorm => t2d::decode_bc4(data, width, height, image),
+        F::Bc5RgUnorm => t2d::decode_bc5(data, width, height, image),
+        F::Bc6hRgbUfloat => t2d::decode_bc6_unsigned(data, width, height, image),
+        F::Bc6hRgbFloat => t2d::decode_bc6_signed(data, width, height, image),
+        F::Bc7RgbaUnorm | F::Bc7RgbaUnormSrgb => t2d::decode_bc7(data, width, height, image),
+        F::Etc2Rgb8Unorm | F::Etc2Rgb8UnormSrgb => t2d::decode_etc2_rgb(data, width, height, image),
+        F::Etc2Rgb8A1Unorm | F::Etc2Rgb8A1UnormSrgb => {
+            t2d::decode_etc2_rgba1(data, width, height, image)
+        }
+        F::Etc2Rgba8Unorm | F::Etc2Rgba8UnormSrgb => {
+            t2d::decode_etc2_rgba8(data, width, height, image)
+        }
+        F::EacR11Unorm => t2d::decode_eacr(data, width, height, image),
+        F::EacR11Snorm => t2d::decode_eacr_signed(data, width, height, image),
+        F::EacRg11Unorm => t2d::decode_eacrg(data, width, height, image),
+        F::EacRg11Snorm => t2d::decode_eacrg_signed(data, width, height, image),
+        F::Astc {
+            channel: AstcChannel::Unorm | AstcChannel::UnormSrgb,
+            ..
+        } => {
+            let (block_width, block_height) = format.block_dimensions();
+            t2d::decode_astc(
+                data,
+                width,
+                height,
+                block_width as usize,
+                block_height as usize,
+                image,
+            )
+        }
+        _ => {
+            return Err(load_error(format!(
+                "{format:?} isn't supported by this device and can't be decoded on the CPU"
+            )))
+        }
+    };
+    result.map_err(load_error)?;
+
+    // The decoder packs pixels as 0xAARRGGBB.
+    Ok(pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_le_bytes())
+        .collect())
+}
+
+/// BC2 is BC1 color blocks with explicit 4 bit alpha in front of each one.
+fn decode_bc2(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    image: &mut [u32],
+) -> Result<(), &'static str> {
+    let blocks = data.chunks_exact(16);
+    let colors: Vec<u8> = blocks
+        .clone()
+        .flat_map(|block| &block[8..])
+        .copied()
+        .collect();
+    texture2ddecoder::decode_bc1(&colors, width, height, image)?;
+
+    let blocks_x = width.div_ceil(4);
+    for (index, block) in blocks.enumerate() {
+        let (bx, by) = (index % blocks_x * 4, index / blocks_x * 4);
+        for texel in 0..16 {
+            let (x, y) = (bx + texel % 4, by + texel / 4);
+            if x >= width || y >= height {
+                continue;
+            }
+            let alpha = (block[texel / 2] >> (texel % 2 * 4)) & 0xF;
+            let pixel = &mut image[y * width + x];
+            *pixel = (*pixel & 0x00FF_FFFF) | (u32::from(alpha * 17) << 24);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_compressed_level_sizes() {
+        let bc1 = TextureFormat::Bc1RgbaUnorm;
+        assert_eq!(level_size(bc1, 256, 128, 0), 64 * 32 * 8);
+        assert_eq!(level_size(bc1, 256, 128, 7), 8);
+        assert_eq!(level_size(bc1, 6, 6, 0), 4 * 8);
+
+        let astc = TextureFormat::Astc {
+            block: AstcBlock::B6x5,
+            channel: AstcChannel::Unorm,
+        };
+        assert_eq!(level_blocks(astc, 13, 11, 0), (3, 3));
+    }
+
+    #[test]
+    fn headless_test_compressed_dds_decode() {
+        let mut dds = Dds::new_d3d(ddsfile::NewD3dParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: D3DFormat::DXT1,
+            mipmap_levels: Some(2),
+            caps2: None,
+        })
+        .unwrap();
+        // Every block is solid red: both endpoints 0xF800 and all indices 0.
+        for block in dds.data.chunks_exact_mut(8) {
+            block.copy_from_slice(&[0x00, 0xF8, 0x00, 0xF8, 0, 0, 0, 0]);
+        }
+        let mut bytes = Vec::new();
+        dds.write(&mut bytes).unwrap();
+
+        assert!(is_container(&bytes));
+        let data = parse(&bytes).unwrap();
+        assert_eq!(data.format, TextureFormat::Bc1RgbaUnorm);
+        assert_eq!((data.width, data.height), (8, 8));
+        assert_eq!(data.levels.len(), 2);
+        assert_eq!(data.levels[0].len(), 4 * 8);
+
+        let decoded = data.decode().unwrap();
+        assert_eq!(decoded.format, TextureFormat::Bgra8Unorm);
+        assert_eq!(decoded.levels[1].len(), 4 * 4 * 4);
+        assert!(decoded.levels[0]
+            .chunks_exact(4)
+            .all(|bgra| bgra == [0, 0, 255, 255]));
+
+        // The base level has to be made of whole blocks.
+        let dds = Dds::new_d3d(ddsfile::NewD3dParams {
+            height: 6,
+            width: 8,
+            depth: None,
+            format: D3DFormat::DXT1,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        let mut bytes = Vec::new();
+        dds.write(&mut bytes).unwrap();
+        assert!(parse(&bytes).is_err());
+
+        // An 8x8 texture has at most 4 mip levels, whatever the header says.
+        for count in [5, 40] {
+            let mut dds = Dds::new_d3d(ddsfile::NewD3dParams {
+                height: 8,
+                width: 8,
+                depth: None,
+                format: D3DFormat::DXT1,
+                mipmap_levels: Some(2),
+                caps2: None,
+            })
+            .unwrap();
+            dds.header.mip_map_count = Some(count);
+            let mut bytes = Vec::new();
+            dds.write(&mut bytes).unwrap();
+            assert!(parse(&bytes).is_err(), "{count} levels");
+        }
+    }
+}
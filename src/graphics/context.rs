@@ -1,7 +1,9 @@
 use super::{
+    capture::FrameCapture,
     gpu::{
         bind_group::{BindGroupCache, BindGroupEntryKey},
         growing::GrowingBufferArena,
+        mipmap::MipmapGenerator,
         pipeline::PipelineCache,
         text::TextRenderer,
     },
@@ -26,8 +28,8 @@ use winit::dpi::{self, PhysicalPosition};
 pub(crate) struct FrameContext {
     pub cmd: wgpu::CommandEncoder,
     pub present: Image,
-    pub frame: wgpu::SurfaceTexture,
-    pub frame_view: wgpu::TextureView,
+    // None when rendering headless, as there is no surface to present to.
+    pub frame: Option<(wgpu::SurfaceTexture, wgpu::TextureView)>,
 }
 
 /// WGPU graphics context objects.
@@ -44,8 +46,9 @@ pub struct WgpuContext {
 pub struct GraphicsContext {
     pub(crate) wgpu: Arc<WgpuContext>,
 
-    pub(crate) surface: wgpu::Surface<'static>,
-    pub(crate) window: Arc<winit::window::Window>,
+    // Both are None for a headless context, see `GraphicsContext::new_headless`.
+    pub(crate) surface: Option<wgpu::Surface<'static>>,
+    pub(crate) window: Option<Arc<winit::window::Window>>,
     pub(crate) surface_config: wgpu::SurfaceConfiguration,
 
     pub(crate) bind_group_cache: BindGroupCache,
@@ -62,6 +65,8 @@ pub struct GraphicsContext {
     pub(crate) text: TextRenderer,
     pub(crate) fonts: HashMap<String, FontId>,
     pub(crate) uniform_arena: GrowingBufferArena,
+    // Number of frames submitted so far.
+    pub(crate) frames: usize,
 
     pub(crate) draw_shader: wgpu::ShaderModule,
 
@@ -76,11 +81,13 @@ pub struct GraphicsContext {
     pub(crate) instance_unordered_shader: wgpu::ShaderModule,
     pub(crate) text_shader: wgpu::ShaderModule,
     pub(crate) copy_shader: wgpu::ShaderModule,
+    pub(crate) mipmaps: MipmapGenerator,
     pub(crate) rect_mesh: Mesh,
     pub(crate) white_image: Image,
     pub(crate) instance_bind_layout: wgpu::BindGroupLayout,
 
     pub(crate) fs: Filesystem,
+    capture: FrameCapture,
 
     bind_group: Option<([BindGroupEntryKey; 2], wgpu::BindGroup)>,
 }
@@ -92,6 +99,40 @@ impl GraphicsContext {
         event_loop: &winit::event_loop::EventLoop<()>,
         conf: &Conf,
         filesystem: &Filesystem,
+    ) -> GameResult<Self> {
+        Self::with_backends(conf, |instance| {
+            Self::new_from_instance(game_id, instance, event_loop, conf, filesystem)
+        })
+    }
+
+    /// Create a new graphics context that renders without a window or surface.
+    ///
+    /// The frame is an offscreen image of `width` x `height` physical pixels, so
+    /// [`Canvas::from_frame`](crate::graphics::Canvas::from_frame) and
+    /// [`Image::to_pixels`] work just as they do with a window. If no hardware adapter
+    /// is available, a software (fallback) adapter is used instead.
+    ///
+    /// This is mainly useful for rendering tests on machines without a display.
+    pub fn new_headless(
+        width: u32,
+        height: u32,
+        conf: &Conf,
+        filesystem: &Filesystem,
+    ) -> GameResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(GameError::WindowError(format!(
+                "headless width and height need to be at least 1; actual values: {width}, {height}"
+            )));
+        }
+
+        Self::with_backends(conf, |instance| {
+            Self::new_headless_from_instance(instance, width, height, conf, filesystem)
+        })
+    }
+
+    fn with_backends(
+        conf: &Conf,
+        mut create: impl FnMut(wgpu::Instance) -> GameResult<Self>,
     ) -> GameResult<Self> {
         let new_instance = |backends| {
             wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -101,13 +142,7 @@ impl GraphicsContext {
         };
 
         if conf.backend == Backend::All {
-            match Self::new_from_instance(
-                game_id,
-                new_instance(wgpu::Backends::PRIMARY),
-                event_loop,
-                conf,
-                filesystem,
-            ) {
+            match create(new_instance(wgpu::Backends::PRIMARY)) {
                 Ok(o) => Ok(o),
                 Err(GameError::GraphicsInitializationError) => {
                     println!(
@@ -117,13 +152,7 @@ impl GraphicsContext {
                         "Failed to initialize graphics, trying secondary backends.. Please mention this if you encounter any bugs!"
                     );
 
-                    Self::new_from_instance(
-                        game_id,
-                        new_instance(wgpu::Backends::SECONDARY),
-                        event_loop,
-                        conf,
-                        filesystem,
-                    )
+                    create(new_instance(wgpu::Backends::SECONDARY))
                 }
                 Err(e) => Err(e),
             }
@@ -138,7 +167,7 @@ impl GraphicsContext {
                 Backend::BrowserWebGpu => wgpu::Backends::BROWSER_WEBGPU,
             });
 
-            Self::new_from_instance(game_id, instance, event_loop, conf, filesystem)
+            create(instance)
         }
     }
 
@@ -220,7 +249,7 @@ impl GraphicsContext {
         {
             use winit::platform::windows::WindowAttributesExtWindows;
             window_builder = window_builder
-                .with_drag_and_drop(false)
+                .with_drag_and_drop(conf.window_setup.drag_and_drop)
                 .with_clip_children(false);
         }
 
@@ -246,6 +275,83 @@ impl GraphicsContext {
         }))
         .or(Err(GameError::GraphicsInitializationError))?;
 
+        let capabilities = surface.get_capabilities(&adapter);
+
+        let size = window.inner_size();
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: capabilities.formats[0],
+            width: size.width,
+            height: size.height,
+            present_mode: if conf.window_setup.vsync {
+                wgpu::PresentMode::AutoVsync
+            } else {
+                wgpu::PresentMode::AutoNoVsync
+            },
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+
+        Self::from_adapter(
+            instance,
+            &adapter,
+            Some((window, surface)),
+            surface_config,
+            conf,
+            filesystem,
+        )
+    }
+
+    fn new_headless_from_instance(
+        instance: wgpu::Instance,
+        width: u32,
+        height: u32,
+        conf: &Conf,
+        filesystem: &Filesystem,
+    ) -> GameResult<Self> {
+        let request = |force_fallback_adapter| {
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter,
+                compatible_surface: None,
+            }))
+        };
+
+        // CI machines usually don't have a GPU, so fall back to a software adapter if we must.
+        let adapter = request(false)
+            .or_else(|_| request(true))
+            .or(Err(GameError::GraphicsInitializationError))?;
+
+        // There's no surface to configure, but we keep the configuration around
+        // to track the size and format of the offscreen frame.
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: ImageFormat::Bgra8UnormSrgb,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+
+        let conf = Conf {
+            window_mode: conf.window_mode.dimensions(width as f32, height as f32),
+            ..conf.clone()
+        };
+
+        Self::from_adapter(instance, &adapter, None, surface_config, &conf, filesystem)
+    }
+
+    fn from_adapter(
+        instance: wgpu::Instance,
+        adapter: &wgpu::Adapter,
+        window: Option<(Arc<winit::window::Window>, wgpu::Surface<'static>)>,
+        surface_config: wgpu::SurfaceConfiguration,
+        conf: &Conf,
+        filesystem: &Filesystem,
+    ) -> GameResult<Self> {
         // One instance is 96 bytes, and we allow 1 million of them, for a total of 96MB (default being 128MB).
         const MAX_INSTANCES: u64 = 1_000_000;
         const INSTANCE_BUFFER_SIZE: u64 = 96 * MAX_INSTANCES;
@@ -265,6 +371,13 @@ impl GraphicsContext {
                     max_texture_dimension_2d: 8192,
                     ..wgpu::Limits::downlevel_webgl2_defaults()
                 },
+                // Compressed textures are uploaded as-is where the adapter supports them,
+                // see `Image::from_bytes`.
+                required_features: adapter.features()
+                    & (wgpu::Features::TEXTURE_COMPRESSION_BC
+                        | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+                        | wgpu::Features::TEXTURE_COMPRESSION_ASTC
+                        | wgpu::Features::TEXTURE_COMPRESSION_ASTC_HDR),
                 ..wgpu::DeviceDescriptor::default()
             }))?;
 
@@ -274,26 +387,14 @@ impl GraphicsContext {
             queue,
         });
 
-        let capabilities = surface.get_capabilities(&adapter);
-
-        let size = window.inner_size();
-        let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: capabilities.formats[0],
-            width: size.width,
-            height: size.height,
-            present_mode: if conf.window_setup.vsync {
-                wgpu::PresentMode::AutoVsync
-            } else {
-                wgpu::PresentMode::AutoNoVsync
-            },
-            desired_maximum_frame_latency: 2,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![],
+        let (window, surface) = match window {
+            Some((window, surface)) => {
+                surface.configure(&wgpu.device, &surface_config);
+                (Some(window), Some(surface))
+            }
+            None => (None, None),
         };
 
-        surface.configure(&wgpu.device, &surface_config);
-
         let mut bind_group_cache = BindGroupCache::new();
         let pipeline_cache = PipelineCache::new();
         let sampler_cache = SamplerCache::new();
@@ -337,6 +438,14 @@ impl GraphicsContext {
         let instance_unordered_shader = load_shader(include_str!("shader/instance_unordered.wgsl"));
         let text_shader = load_shader(include_str!("shader/text.wgsl"));
         let copy_shader = load_shader(include_str!("shader/copy.wgsl"));
+        let mipmaps = MipmapGenerator::new(
+            &wgpu.device,
+            copy_shader.clone(),
+            BindGroupLayoutBuilder::new()
+                .image(wgpu::ShaderStages::FRAGMENT)
+                .sampler(wgpu::ShaderStages::FRAGMENT)
+                .create(&wgpu.device, &mut bind_group_cache),
+        );
 
         let rect_mesh = Mesh::from_data_wgpu(
             &wgpu,
@@ -404,6 +513,7 @@ impl GraphicsContext {
             text,
             fonts: HashMap::new(),
             uniform_arena,
+            frames: 0,
             draw_shader,
 
             #[cfg(feature = "3d")]
@@ -417,11 +527,13 @@ impl GraphicsContext {
             instance_unordered_shader,
             text_shader,
             copy_shader,
+            mipmaps,
             rect_mesh,
             white_image,
             instance_bind_layout,
 
             fs: filesystem.clone(),
+            capture: FrameCapture::default(),
 
             bind_group: None,
         };
@@ -476,9 +588,26 @@ impl GraphicsContext {
     }
 
     /// Returns the size of the window’s underlying drawable in physical pixels as (width, height).
+    ///
+    /// For a headless context this is the size of the offscreen frame.
     pub fn drawable_size(&self) -> (f32, f32) {
-        let size = self.window.inner_size();
-        (size.width as f32, size.height as f32)
+        match &self.window {
+            Some(window) => {
+                let size = window.inner_size();
+                (size.width as f32, size.height as f32)
+            }
+            None => (
+                self.surface_config.width as f32,
+                self.surface_config.height as f32,
+            ),
+        }
+    }
+
+    /// Returns whether this context renders without a window,
+    /// i.e. it was created with [`GraphicsContext::new_headless`].
+    #[inline]
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
     }
 
     /// Sets the window size (in physical pixels) / resolution to the specified width and height.
@@ -491,42 +620,87 @@ impl GraphicsContext {
 
     /// Sets the window title.
     pub fn set_window_title(&self, title: &str) {
-        self.window.set_title(title);
+        if let Some(window) = &self.window {
+            window.set_title(title);
+        }
     }
 
     /// Returns the position of the system window, including the outer frame.
     pub fn window_position(&self) -> GameResult<PhysicalPosition<i32>> {
         self.window
+            .as_ref()
+            .ok_or_else(|| GameError::WindowError(String::from("headless context has no window")))?
             .outer_position()
             .map_err(|e| GameError::WindowError(e.to_string()))
     }
 
     /// Sets the window position.
     pub fn set_window_position(&self, position: impl Into<winit::dpi::Position>) -> GameResult {
-        self.window.set_outer_position(position);
+        if let Some(window) = &self.window {
+            window.set_outer_position(position);
+        }
         Ok(())
     }
 
+    /// Sets whether the window accepts IME (input method editor) input, which is needed to
+    /// type e.g. Chinese, Japanese or Korean text. It's disabled by default.
+    ///
+    /// While enabled, composition and committed text are reported through
+    /// [`EventHandler::ime_preedit_event`](crate::event::EventHandler::ime_preedit_event) and
+    /// [`EventHandler::ime_commit_event`](crate::event::EventHandler::ime_commit_event),
+    /// and key presses consumed by the IME don't produce text input events.
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        if let Some(window) = &self.window {
+            window.set_ime_allowed(allowed);
+        }
+    }
+
+    /// Sets the area of the text being edited, such as the text cursor, so the IME can place its
+    /// candidate window next to it instead of covering it. The position is relative to the
+    /// top-left corner of the window.
+    pub fn set_ime_cursor_area(
+        &self,
+        position: impl Into<winit::dpi::Position>,
+        size: impl Into<winit::dpi::Size>,
+    ) {
+        if let Some(window) = &self.window {
+            window.set_ime_cursor_area(position, size);
+        }
+    }
+
     /// Returns the size of the window in pixels as (width, height),
     /// including borders, titlebar, etc.
     /// Returns zeros if the window doesn't exist.
     pub fn size(&self) -> (f32, f32) {
-        let size = self.window.outer_size();
-        (size.width as f32, size.height as f32)
+        match &self.window {
+            Some(window) => {
+                let size = window.outer_size();
+                (size.width as f32, size.height as f32)
+            }
+            None => (0., 0.),
+        }
     }
 
     /// Returns an iterator providing all resolutions supported by the current monitor.
-    pub fn supported_resolutions(&self) -> impl Iterator<Item = winit::dpi::PhysicalSize<u32>> {
+    pub fn supported_resolutions(
+        &self,
+    ) -> impl Iterator<Item = winit::dpi::PhysicalSize<u32>> + '_ {
         self.window
-            .current_monitor()
-            .into_iter()
+            .iter()
+            .flat_map(|window| window.current_monitor())
             .flat_map(|monitor| monitor.video_modes().map(|vm| vm.size()))
     }
 
     /// Returns a reference to the Winit window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the context is headless, see [`GraphicsContext::is_headless`].
     #[inline]
     pub fn window(&self) -> &winit::window::Window {
-        &self.window
+        self.window
+            .as_ref()
+            .expect("headless GraphicsContext has no window")
     }
 
     /// Sets the window icon. `None` for path removes the icon.
@@ -540,7 +714,9 @@ impl GraphicsContext {
             Some(p) => Some(load_icon(p.as_ref(), filesystem)?),
             None => None,
         };
-        self.window.set_window_icon(icon);
+        if let Some(window) = &self.window {
+            window.set_window_icon(icon);
+        }
         Ok(())
     }
 
@@ -572,6 +748,10 @@ impl GraphicsContext {
             mode.fullscreen_type = old_fullscreen;
         }
         self.window_mode = mode;
+        // There's no window to send us a resize event, so resize the frame right away.
+        if self.is_headless() && result.is_ok() {
+            self.on_resize();
+        }
         result
     }
 
@@ -604,34 +784,45 @@ impl GraphicsContext {
             )));
         }
 
-        let frame = loop {
-            match self.surface.get_current_texture() {
-                wgpu::CurrentSurfaceTexture::Success(frame) => break frame,
-                wgpu::CurrentSurfaceTexture::Suboptimal(_)
-                | wgpu::CurrentSurfaceTexture::Outdated => self.reconfigure_surface(),
-                wgpu::CurrentSurfaceTexture::Timeout | wgpu::CurrentSurfaceTexture::Occluded => {
-                    // TODO: Add proper way to skip frame
-                    continue;
-                }
-                wgpu::CurrentSurfaceTexture::Lost => {
-                    self.surface = self
-                        .wgpu
-                        .instance
-                        .create_surface(self.window.clone())
-                        .map_err(|_| GameError::GraphicsInitializationError)?;
-                    self.reconfigure_surface();
-                }
+        let frame = match &self.window {
+            Some(window) => {
+                let window = window.clone();
+                let frame = loop {
+                    let surface = self.surface.as_ref().unwrap(/* invariant */);
+                    match surface.get_current_texture() {
+                        wgpu::CurrentSurfaceTexture::Success(frame) => break frame,
+                        wgpu::CurrentSurfaceTexture::Suboptimal(_)
+                        | wgpu::CurrentSurfaceTexture::Outdated => self.reconfigure_surface(),
+                        wgpu::CurrentSurfaceTexture::Timeout
+                        | wgpu::CurrentSurfaceTexture::Occluded => {
+                            // TODO: Add proper way to skip frame
+                            continue;
+                        }
+                        wgpu::CurrentSurfaceTexture::Lost => {
+                            self.surface = Some(
+                                self.wgpu
+                                    .instance
+                                    .create_surface(window.clone())
+                                    .map_err(|_| GameError::GraphicsInitializationError)?,
+                            );
+                            self.reconfigure_surface();
+                        }
 
-                wgpu::CurrentSurfaceTexture::Validation => {
-                    return Err(GameError::RenderError(String::from("validation error")));
-                }
+                        wgpu::CurrentSurfaceTexture::Validation => {
+                            return Err(GameError::RenderError(String::from("validation error")));
+                        }
+                    }
+                };
+
+                let frame_view = frame
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+
+                Some((frame, frame_view))
             }
+            None => None,
         };
 
-        let frame_view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
         self.fcx = Some(FrameContext {
             cmd: self
                 .wgpu
@@ -639,7 +830,6 @@ impl GraphicsContext {
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor::default()),
             present: self.frame().clone(),
             frame,
-            frame_view,
         });
 
         self.uniform_arena.free();
@@ -654,10 +844,17 @@ impl GraphicsContext {
     /// The only situation you need to call this in is when you are rolling your own event loop.
     pub fn end_frame(&mut self) -> GameResult {
         if let Some(mut fcx) = self.fcx.take() {
+            self.frames += 1;
+            let Some((frame, frame_view)) = fcx.frame else {
+                // Headless, so there's nothing to present; the frame image holds the result.
+                let _ = self.wgpu.queue.submit([fcx.cmd.finish()]);
+                return self.capture_frame(&fcx.present);
+            };
+
             let mut present_pass = fcx.cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &fcx.frame_view,
+                    view: &frame_view,
                     depth_slice: None,
                     resolve_target: None,
                     ops: wgpu::Operations {
@@ -675,7 +872,7 @@ impl GraphicsContext {
                 .sampler_cache
                 .get(&self.wgpu.device, Sampler::default());
 
-            let (bind, layout) = self.bind_group(fcx.present.view, sampler);
+            let (bind, layout) = self.bind_group(fcx.present.view.clone(), sampler);
 
             let layout = self
                 .pipeline_cache
@@ -706,9 +903,9 @@ impl GraphicsContext {
             std::mem::drop(present_pass);
 
             let _ = self.wgpu.queue.submit([fcx.cmd.finish()]);
-            fcx.frame.present();
+            frame.present();
 
-            Ok(())
+            self.capture_frame(&fcx.present)
         } else {
             Err(GameError::RenderError(String::from(
                 "cannot end a frame as there was never one in progress; call begin_frame first",
@@ -716,14 +913,58 @@ impl GraphicsContext {
         }
     }
 
+    fn capture_frame(&mut self, present: &Image) -> GameResult {
+        let mut capture = std::mem::take(&mut self.capture);
+        let result = capture.end_frame(self, present);
+        self.capture = capture;
+        result
+    }
+
+    /// Saves the next frame as a PNG at `path`, once it has been presented at the end of the frame.
+    ///
+    /// This captures exactly what ends up on screen, after MSAA resolve, and is written to the
+    /// user directory just like [`Image::encode`] would.
+    pub fn capture_next_frame(&mut self, path: impl AsRef<Path>) {
+        self.capture.capture_next_frame(path.as_ref().to_path_buf());
+    }
+
+    /// Starts recording the presented frames as a numbered sequence of PNGs (`frame_000000.png`,
+    /// `frame_000001.png` and so on) into the `dir` directory inside
+    /// [`Filesystem::user_data_dir`], `fps` times per second, for trailers and bug reports.
+    ///
+    /// The frames are read back without stalling the GPU and encoded on a separate thread a frame
+    /// or two later. Frames the game didn't render in time for are skipped rather than duplicated,
+    /// and so are frames that come due while the disk can't keep up.
+    pub fn start_recording(&mut self, dir: impl AsRef<Path>, fps: f32) -> GameResult {
+        let dir = dir.as_ref();
+        let dir = self
+            .fs
+            .user_data_dir()
+            .join(dir.strip_prefix("/").unwrap_or(dir));
+        self.capture.start_recording(dir, fps)
+    }
+
+    /// Stops recording frames; see [`GraphicsContext::start_recording`].
+    ///
+    /// Frames still on their way back from the GPU are written out during the next frames.
+    pub fn stop_recording(&mut self) {
+        self.capture.stop_recording();
+    }
+
+    /// Returns whether the presented frames are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.capture.is_recording()
+    }
+
     fn reconfigure_surface(&mut self) {
         let _ = self.wgpu.device.poll(wgpu::PollType::wait_indefinitely());
 
-        let size = self.window.inner_size();
-        self.surface_config.width = size.width.max(1);
-        self.surface_config.height = size.height.max(1);
-        self.surface
-            .configure(&self.wgpu.device, &self.surface_config);
+        let (width, height) = self.drawable_size();
+        self.surface_config.width = (width as u32).max(1);
+        self.surface_config.height = (height as u32).max(1);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.wgpu.device, &self.surface_config);
+        }
     }
 
     pub(crate) fn on_resize(&mut self) {
@@ -743,7 +984,12 @@ impl GraphicsContext {
     }
 
     pub(crate) fn set_window_mode(&mut self, mode: &WindowMode) -> GameResult {
-        let window = &mut self.window;
+        let Some(window) = &self.window else {
+            // A headless frame has no window to constrain, it's simply resized.
+            self.surface_config.width = (mode.width as u32).max(1);
+            self.surface_config.height = (mode.height as u32).max(1);
+            return Ok(());
+        };
 
         // TODO LATER: find out if single-dimension constraints are possible?
         let min_dimensions = if mode.min_width >= 1.0 && mode.min_height >= 1.0 {
@@ -810,8 +1056,9 @@ impl GraphicsContext {
         self.surface_config.width = size.width.max(1);
         self.surface_config.height = size.height.max(1);
 
-        self.surface
-            .configure(&self.wgpu.device, &self.surface_config);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.wgpu.device, &self.surface_config);
+        }
 
         Ok(())
     }
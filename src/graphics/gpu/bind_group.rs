@@ -44,13 +44,21 @@ impl BindGroupLayoutBuilder {
         self
     }
 
-    pub fn image(mut self, visibility: wgpu::ShaderStages) -> Self {
+    pub fn image(self, visibility: wgpu::ShaderStages) -> Self {
+        self.image_dimension(visibility, wgpu::TextureViewDimension::D2)
+    }
+
+    pub fn image_dimension(
+        mut self,
+        visibility: wgpu::ShaderStages,
+        view_dimension: wgpu::TextureViewDimension,
+    ) -> Self {
         self.entries.push(wgpu::BindGroupLayoutEntry {
             binding: self.entries.len() as _,
             visibility,
             ty: wgpu::BindingType::Texture {
                 sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                view_dimension: wgpu::TextureViewDimension::D2,
+                view_dimension,
                 multisampled: false,
             },
             count: None,
@@ -144,7 +152,16 @@ impl<'a> BindGroupBuilder<'a> {
         }
     }
 
-    pub fn image(mut self, view: &'a wgpu::TextureView, visibility: wgpu::ShaderStages) -> Self {
+    pub fn image(self, view: &'a wgpu::TextureView, visibility: wgpu::ShaderStages) -> Self {
+        self.image_dimension(view, visibility, wgpu::TextureViewDimension::D2)
+    }
+
+    pub fn image_dimension(
+        mut self,
+        view: &'a wgpu::TextureView,
+        visibility: wgpu::ShaderStages,
+        view_dimension: wgpu::TextureViewDimension,
+    ) -> Self {
         self.entries.push(wgpu::BindGroupEntry {
             binding: self.entries.len() as _,
             resource: wgpu::BindingResource::TextureView(view),
@@ -153,7 +170,7 @@ impl<'a> BindGroupBuilder<'a> {
         self.key.push(BindGroupEntryKey::Image(view.clone()));
 
         BindGroupBuilder {
-            layout: self.layout.image(visibility),
+            layout: self.layout.image_dimension(visibility, view_dimension),
             entries: self.entries,
             key: self.key,
         }
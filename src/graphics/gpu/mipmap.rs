@@ -0,0 +1,172 @@
+use std::{collections::HashMap, sync::Mutex};
+
+/// Fills in the mip chain of a texture by rendering each level from the one above it,
+/// using the full screen triangle of the copy shader and linear filtering.
+///
+/// Pipelines are created lazily per format; they live behind a lock so that images can
+/// generate their mips with a shared reference to the graphics context.
+#[derive(Debug)]
+pub struct MipmapGenerator {
+    shader: wgpu::ShaderModule,
+    bind_layout: wgpu::BindGroupLayout,
+    layout: wgpu::PipelineLayout,
+    sampler: wgpu::Sampler,
+    pipelines: Mutex<HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>>,
+}
+
+impl MipmapGenerator {
+    pub fn new(
+        device: &wgpu::Device,
+        copy_shader: wgpu::ShaderModule,
+        bind_layout: wgpu::BindGroupLayout,
+    ) -> Self {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[Some(&bind_layout)],
+            immediate_size: 0,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: None,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        MipmapGenerator {
+            shader: copy_shader,
+            bind_layout,
+            layout,
+            sampler,
+            pipelines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether mips of this format can be generated, i.e. it can be both rendered to and
+    /// filtered.
+    pub fn supports(device: &wgpu::Device, format: wgpu::TextureFormat) -> bool {
+        let features = format.guaranteed_format_features(device.features());
+        features
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+            && features
+                .flags
+                .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE)
+    }
+
+    /// Records the passes regenerating levels `1..` of every layer of `texture` from its level 0.
+    ///
+    /// The texture must have been created with `RENDER_ATTACHMENT` and `TEXTURE_BINDING` usage.
+    pub fn generate(
+        &self,
+        device: &wgpu::Device,
+        cmd: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        // Layers of 3D textures aren't separate images; they'd need 3D downsampling.
+        if texture.mip_level_count() < 2 || texture.dimension() != wgpu::TextureDimension::D2 {
+            return;
+        }
+
+        let format = texture.format();
+        let pipeline = self
+            .pipelines
+            .lock()
+            .unwrap()
+            .entry(format)
+            .or_insert_with(|| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: None,
+                    layout: Some(&self.layout),
+                    vertex: wgpu::VertexState {
+                        module: &self.shader,
+                        entry_point: Some("vs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[],
+                    },
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    fragment: Some(wgpu::FragmentState {
+                        module: &self.shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format,
+                            blend: None,
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    multiview_mask: None,
+                    cache: None,
+                })
+            })
+            .clone();
+
+        for layer in 0..texture.depth_or_array_layers() {
+            self.generate_layer(device, cmd, &pipeline, texture, layer);
+        }
+    }
+
+    fn generate_layer(
+        &self,
+        device: &wgpu::Device,
+        cmd: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        texture: &wgpu::Texture,
+        layer: u32,
+    ) {
+        let level_view = |level: u32| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                ..Default::default()
+            })
+        };
+
+        let mut source = level_view(0);
+        for level in 1..texture.mip_level_count() {
+            let target = level_view(level);
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &self.bind_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+
+            let mut pass = cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+                multiview_mask: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+            std::mem::drop(pass);
+
+            source = target;
+        }
+    }
+}
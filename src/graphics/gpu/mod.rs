@@ -1,4 +1,5 @@
 pub mod bind_group;
 pub mod growing;
+pub mod mipmap;
 pub mod pipeline;
 pub mod text;
@@ -0,0 +1,208 @@
+//! Hot-reloading of shaders, images and fonts while the game is running.
+//!
+//! A [`Watched`] value is loaded from one or more files, and loaded again by
+//! [`Watched::update`] whenever one of them changes on disk, so that iterating on a WGSL
+//! shader or a sprite doesn't require restarting the game. Changes are picked up from the
+//! physical directories mounted in the [`Filesystem`](crate::filesystem::Filesystem),
+//! see [`Filesystem::watch`](crate::filesystem::Filesystem::watch).
+//!
+//! Only available with the `hot-reload` feature, which is meant for development builds.
+//!
+//! ```rust,no_run
+//! # use ggez::{graphics::{self, hot_reload::Watched}, Context, GameResult};
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! let mut shader = Watched::shader(ctx, None, Some("/dimmer.wgsl"))?;
+//! let mut image = Watched::image(ctx, "/player.png")?;
+//!
+//! // In `EventHandler::update`; a broken shader is reported and the old one kept.
+//! if let Err(e) = shader.update(ctx) {
+//!     println!("{e}");
+//! }
+//! image.update(ctx)?;
+//!
+//! // In `EventHandler::draw`.
+//! let mut canvas = graphics::Canvas::from_frame(ctx, graphics::Color::BLACK);
+//! canvas.set_shader(&shader);
+//! canvas.draw(&*image, graphics::DrawParam::new());
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{FontData, Image, Shader, ShaderBuilder};
+use crate::{Context, GameResult};
+use std::{
+    fmt,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// Loads the value of a [`Watched`].
+type Loader<T> = Box<dyn FnMut(&mut Context) -> GameResult<T>>;
+
+/// A value loaded from files, which is reloaded when they change on disk.
+///
+/// Dereferences to the currently loaded value.
+pub struct Watched<T> {
+    value: T,
+    paths: Vec<PathBuf>,
+    changes: Vec<u64>,
+    load: Loader<T>,
+}
+
+impl<T> Watched<T> {
+    /// Loads a value with `load`, and remembers to load it again whenever one of the files at
+    /// `paths` changes. Starts watching the filesystem if it isn't already.
+    pub fn new<P: AsRef<Path>>(
+        ctx: &mut Context,
+        paths: impl IntoIterator<Item = P>,
+        mut load: impl FnMut(&mut Context) -> GameResult<T> + 'static,
+    ) -> GameResult<Self> {
+        ctx.fs.watch()?;
+
+        let paths = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .collect::<Vec<_>>();
+        let changes = paths.iter().map(|path| ctx.fs.change_count(path)).collect();
+
+        Ok(Watched {
+            value: load(ctx)?,
+            paths,
+            changes,
+            load: Box::new(load),
+        })
+    }
+
+    /// Reloads the value if one of its files changed since it was last loaded, returning
+    /// whether it did. Call this once per frame, e.g. in `EventHandler::update`.
+    ///
+    /// If reloading fails, for example because a shader doesn't compile, the error is logged
+    /// and returned, and the previously loaded value is kept. It's tried again on the next
+    /// change.
+    pub fn update(&mut self, ctx: &mut Context) -> GameResult<bool> {
+        let changes = self
+            .paths
+            .iter()
+            .map(|path| ctx.fs.change_count(path))
+            .collect::<Vec<_>>();
+        if changes == self.changes {
+            return Ok(false);
+        }
+        self.changes = changes;
+
+        match (self.load)(ctx) {
+            Ok(value) => {
+                info!("Reloaded {}", self.describe_paths());
+                self.value = value;
+                Ok(true)
+            }
+            Err(e) => {
+                warn!("Failed to reload {}: {e}", self.describe_paths());
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the currently loaded value.
+    #[inline]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the files this value is loaded from.
+    #[inline]
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    fn describe_paths(&self) -> String {
+        self.paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Watched<Shader> {
+    /// Loads a shader from WGSL files, like [`ShaderBuilder::vertex_path`] and
+    /// [`ShaderBuilder::fragment_path`]; passing the same path for both uses it for both stages.
+    ///
+    /// When the new code fails to compile, [`Watched::update`] returns the
+    /// [`GameError::ShaderCompileError`](crate::GameError::ShaderCompileError) pointing at the mistake and keeps the old shader.
+    pub fn shader(
+        ctx: &mut Context,
+        vertex_path: Option<&str>,
+        fragment_path: Option<&str>,
+    ) -> GameResult<Self> {
+        let vertex_path = vertex_path.map(String::from);
+        let fragment_path = fragment_path.map(String::from);
+        let paths = vertex_path
+            .iter()
+            .chain(
+                fragment_path
+                    .iter()
+                    .filter(|&path| Some(path) != vertex_path.as_ref()),
+            )
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Watched::new(ctx, paths, move |ctx| {
+            let mut builder = ShaderBuilder::new();
+            if let Some(path) = &vertex_path {
+                builder = builder.vertex_path(path);
+            }
+            if let Some(path) = &fragment_path {
+                builder = builder.fragment_path(path);
+            }
+
+            builder.build(ctx)
+        })
+    }
+}
+
+impl Watched<Image> {
+    /// Loads an image, like [`Image::from_path`].
+    pub fn image(ctx: &mut Context, path: impl AsRef<Path>) -> GameResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        Watched::new(ctx, [path.clone()], move |ctx| Image::from_path(ctx, &path))
+    }
+}
+
+impl Watched<FontData> {
+    /// Loads a font and adds it to the graphics context as `name`, like
+    /// [`GraphicsContext::add_font`](super::GraphicsContext::add_font). Reloading replaces the
+    /// font under the same name, so text drawn from then on uses the new one.
+    pub fn font(ctx: &mut Context, name: &str, path: impl AsRef<Path>) -> GameResult<Self> {
+        let name = name.to_string();
+        let path = path.as_ref().to_path_buf();
+        Watched::new(ctx, [path.clone()], move |ctx| {
+            let font = FontData::from_path(ctx, &path)?;
+            ctx.gfx.add_font(
+                &name,
+                FontData {
+                    font: font.font.clone(),
+                },
+            );
+            Ok(font)
+        })
+    }
+}
+
+impl<T> Deref for Watched<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Watched<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watched")
+            .field("value", &self.value)
+            .field("paths", &self.paths)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,8 +1,14 @@
+#[cfg(feature = "compressed-textures")]
+use super::compressed::{self, TextureData};
 use super::{
-    context::GraphicsContext, gpu::bind_group::BindGroupBuilder, Canvas, Color, Draw, DrawParam,
-    Drawable, Rect, WgpuContext,
+    context::GraphicsContext,
+    gpu::{bind_group::BindGroupBuilder, mipmap::MipmapGenerator},
+    Canvas, Color, Draw, DrawParam, Drawable, Rect, WgpuContext,
+};
+use crate::{
+    context::{Has, HasMut},
+    Context, GameError, GameResult,
 };
-use crate::{context::Has, Context, GameError, GameResult};
 use image::ImageEncoder;
 use std::{
     collections::BTreeMap,
@@ -18,6 +24,27 @@ pub type ImageFormat = wgpu::TextureFormat;
 /// Describes the format of an encoded image.
 pub type ImageEncodingFormat = ::image::ImageFormat;
 
+/// Describes how the layers of an [`Image`] are viewed by shaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageDimension {
+    /// A single 2D image; `texture_2d` in WGSL.
+    D2,
+    /// An array of equally sized 2D layers; `texture_2d_array` in WGSL.
+    D2Array,
+    /// The six square faces of a cube; `texture_cube` in WGSL.
+    Cube,
+}
+
+impl From<ImageDimension> for wgpu::TextureViewDimension {
+    fn from(dimension: ImageDimension) -> Self {
+        match dimension {
+            ImageDimension::D2 => wgpu::TextureViewDimension::D2,
+            ImageDimension::D2Array => wgpu::TextureViewDimension::D2Array,
+            ImageDimension::Cube => wgpu::TextureViewDimension::Cube,
+        }
+    }
+}
+
 /// Handle to an image stored in GPU memory.
 #[derive(Debug, Clone)]
 pub struct Image {
@@ -26,6 +53,10 @@ pub struct Image {
     pub(crate) width: u32,
     pub(crate) height: u32,
     pub(crate) samples: u32,
+    pub(crate) mip_levels: u32,
+    pub(crate) layers: u32,
+    pub(crate) base_layer: u32,
+    pub(crate) dimension: ImageDimension,
     pub(crate) cache: Arc<RwLock<BTreeMap<wgpu::Sampler, wgpu::BindGroup>>>,
 }
 
@@ -45,9 +76,11 @@ impl Image {
             width,
             height,
             samples,
+            1,
             wgpu::TextureUsages::RENDER_ATTACHMENT
                 | wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::COPY_SRC,
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
         )
     }
 
@@ -62,6 +95,30 @@ impl Image {
         Self::new_canvas_image_raw(gfx, gfx.surface_format(), width, height, samples)
     }
 
+    /// Creates a new image with a full mip chain for use with a [Canvas](crate::graphics::Canvas).
+    ///
+    /// The lower mip levels are regenerated from the drawn image every time a canvas drawing to it
+    /// is finished, so that it can be drawn scaled down without shimmering.
+    pub fn new_canvas_image_mipmapped(
+        gfx: &impl Has<GraphicsContext>,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let gfx = gfx.retrieve();
+        Self::new(
+            &gfx.wgpu,
+            gfx.surface_format(),
+            width,
+            height,
+            1,
+            mip_levels(width, height),
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+        )
+    }
+
     /// Creates a new depth image specifically for use with a [Canvas](crate::graphics::Canvas).
     pub fn new_depth_canvas_image(
         gfx: &impl Has<GraphicsContext>,
@@ -111,24 +168,70 @@ impl Image {
         Self::from_pixels_wgpu(&gfx.wgpu, pixels, format, width, height)
     }
 
-    pub(crate) fn from_pixels_wgpu(
-        wgpu: &WgpuContext,
+    /// Creates a new image initialized with given pixel data, and generates a full mip chain
+    /// for it so that it can be drawn scaled down without shimmering.
+    ///
+    /// Fails if the format can't be both rendered to and filtered, which the mip generation needs.
+    pub fn from_pixels_mipmapped(
+        gfx: &impl Has<GraphicsContext>,
         pixels: &[u8],
         format: ImageFormat,
         width: u32,
         height: u32,
-    ) -> Self {
-        let image = Self::new(
-            wgpu,
+    ) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        if !MipmapGenerator::supports(&gfx.wgpu.device, format) {
+            return Err(GameError::RenderError(format!(
+                "cannot generate mipmaps for images of format {format:?}"
+            )));
+        }
+
+        let image = Self::upload(
+            &gfx.wgpu,
+            pixels,
             format,
             width,
             height,
-            1,
-            wgpu::TextureUsages::COPY_DST
-                | wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::COPY_SRC,
+            mip_levels(width, height),
         );
 
+        let mut cmd = gfx
+            .wgpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        gfx.mipmaps
+            .generate(&gfx.wgpu.device, &mut cmd, image.view.texture());
+        let _ = gfx.wgpu.queue.submit([cmd.finish()]);
+
+        Ok(image)
+    }
+
+    pub(crate) fn from_pixels_wgpu(
+        wgpu: &WgpuContext,
+        pixels: &[u8],
+        format: ImageFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        Self::upload(wgpu, pixels, format, width, height, 1)
+    }
+
+    fn upload(
+        wgpu: &WgpuContext,
+        pixels: &[u8],
+        format: ImageFormat,
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+    ) -> Self {
+        let mut usage = wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC;
+        if mip_levels > 1 {
+            usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+        let image = Self::new(wgpu, format, width, height, 1, mip_levels, usage);
+
         wgpu.queue.write_texture(
             image.view.texture().as_image_copy(),
             pixels,
@@ -156,20 +259,286 @@ impl Image {
         Self::from_bytes(gfx, &gfx.fs.read(path)?)
     }
 
+    /// Like [`Image::from_path`], but also generates a full mip chain for the image so that
+    /// it can be drawn scaled down without shimmering.
+    pub fn from_path_mipmapped(
+        gfx: &impl Has<GraphicsContext>,
+        path: impl AsRef<Path>,
+    ) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+
+        Self::from_bytes_mipmapped(gfx, &gfx.fs.read(path)?)
+    }
+
     /// Creates a new image initialized with pixel data from a given encoded image (e.g. PNG or JPEG)
+    ///
+    /// With the `compressed-textures` feature, KTX2 and DDS files with BCn, ETC2/EAC or ASTC
+    /// payloads are kept compressed on the GPU, along with their mip levels, if the device supports
+    /// the format. Otherwise they're decoded on the CPU into an uncompressed image.
     pub fn from_bytes(gfx: &impl Has<GraphicsContext>, encoded: &[u8]) -> Result<Image, GameError> {
-        let decoded = image::load_from_memory(encoded)
-            .map_err(|_| GameError::ResourceLoadError(String::from("failed to load image")))?;
-        let rgba8 = decoded.to_rgba8();
-        let (width, height) = (rgba8.width(), rgba8.height());
+        #[cfg(feature = "compressed-textures")]
+        if compressed::is_container(encoded) {
+            return Self::from_texture_data(gfx.retrieve(), compressed::parse(encoded)?);
+        }
 
+        let rgba8 = decode_rgba8(encoded)?;
         Ok(Self::from_pixels(
             gfx,
             rgba8.as_ref(),
             ImageFormat::Rgba8UnormSrgb,
+            rgba8.width(),
+            rgba8.height(),
+        ))
+    }
+
+    /// Like [`Image::from_bytes`], but also generates a full mip chain for the image so that
+    /// it can be drawn scaled down without shimmering.
+    ///
+    /// KTX2 and DDS files are loaded with the mip levels they come with instead.
+    pub fn from_bytes_mipmapped(
+        gfx: &impl Has<GraphicsContext>,
+        encoded: &[u8],
+    ) -> GameResult<Self> {
+        #[cfg(feature = "compressed-textures")]
+        if compressed::is_container(encoded) {
+            return Self::from_bytes(gfx, encoded);
+        }
+
+        let rgba8 = decode_rgba8(encoded)?;
+        Self::from_pixels_mipmapped(
+            gfx,
+            rgba8.as_ref(),
+            ImageFormat::Rgba8UnormSrgb,
+            rgba8.width(),
+            rgba8.height(),
+        )
+    }
+
+    /// Creates a 2D array image from equally sized layers of pixel data, e.g. for terrain
+    /// splatting. Bind it with [`ShaderParamsBuilder::images`](crate::graphics::ShaderParamsBuilder::images)
+    /// as a `texture_2d_array<f32>`.
+    ///
+    /// Fails if there are no layers or a layer doesn't hold `width * height` pixels.
+    pub fn array_from_pixels(
+        gfx: &impl Has<GraphicsContext>,
+        layers: &[&[u8]],
+        format: ImageFormat,
+        width: u32,
+        height: u32,
+    ) -> GameResult<Self> {
+        Self::from_layers(
+            &gfx.retrieve().wgpu,
+            layers,
+            format,
             width,
             height,
-        ))
+            ImageDimension::D2Array,
+        )
+    }
+
+    /// Creates a 2D array image from encoded images (e.g. PNG or JPEG) loaded from the given
+    /// paths, one layer per path. All of them must have the same size.
+    pub fn array_from_paths(
+        gfx: &impl Has<GraphicsContext>,
+        paths: &[impl AsRef<Path>],
+    ) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        let (layers, width, height) = Self::decode_layers(gfx, paths)?;
+        let layers = layers.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        Self::from_layers(
+            &gfx.wgpu,
+            &layers,
+            ImageFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            ImageDimension::D2Array,
+        )
+    }
+
+    /// Creates a cubemap image, e.g. for skyboxes, from the pixel data of its six square faces
+    /// in the order +X, -X, +Y, -Y, +Z, -Z. Bind it with
+    /// [`ShaderParamsBuilder::images`](crate::graphics::ShaderParamsBuilder::images) as a
+    /// `texture_cube<f32>`.
+    pub fn cube_from_pixels(
+        gfx: &impl Has<GraphicsContext>,
+        faces: &[&[u8]; 6],
+        format: ImageFormat,
+        size: u32,
+    ) -> GameResult<Self> {
+        Self::from_layers(
+            &gfx.retrieve().wgpu,
+            faces,
+            format,
+            size,
+            size,
+            ImageDimension::Cube,
+        )
+    }
+
+    /// Creates a cubemap image from six encoded images loaded from the given paths, in the order
+    /// +X, -X, +Y, -Y, +Z, -Z. All of them must be square and of the same size.
+    pub fn cube_from_paths(
+        gfx: &impl Has<GraphicsContext>,
+        faces: &[impl AsRef<Path>; 6],
+    ) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        let (faces, width, height) = Self::decode_layers(gfx, faces)?;
+        if width != height {
+            return Err(GameError::RenderError(format!(
+                "cubemap faces must be square, got {width}x{height}"
+            )));
+        }
+        let faces = faces.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        Self::from_layers(
+            &gfx.wgpu,
+            &faces,
+            ImageFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            ImageDimension::Cube,
+        )
+    }
+
+    /// Loads and decodes images to RGBA8, checking that they're all the same size.
+    fn decode_layers(
+        gfx: &GraphicsContext,
+        paths: &[impl AsRef<Path>],
+    ) -> GameResult<(Vec<Vec<u8>>, u32, u32)> {
+        let mut size = None;
+        let mut layers = Vec::with_capacity(paths.len());
+        for path in paths {
+            let path = path.as_ref();
+            let decoded = image::load_from_memory(&gfx.fs.read(path)?).map_err(|_| {
+                GameError::ResourceLoadError(format!("failed to load image {}", path.display()))
+            })?;
+            let rgba8 = decoded.to_rgba8();
+            let layer_size = (rgba8.width(), rgba8.height());
+            if *size.get_or_insert(layer_size) != layer_size {
+                return Err(GameError::ResourceLoadError(format!(
+                    "image {} is {}x{}, unlike the previous layers",
+                    path.display(),
+                    layer_size.0,
+                    layer_size.1
+                )));
+            }
+            layers.push(rgba8.into_raw());
+        }
+
+        let (width, height) = size.ok_or_else(|| {
+            GameError::ResourceLoadError(String::from("no images given for the layers"))
+        })?;
+        Ok((layers, width, height))
+    }
+
+    fn from_layers(
+        wgpu: &WgpuContext,
+        layers: &[&[u8]],
+        format: ImageFormat,
+        width: u32,
+        height: u32,
+        dimension: ImageDimension,
+    ) -> GameResult<Self> {
+        check_layers(layers, format, width, height, dimension)?;
+
+        let image = Self::create(
+            wgpu,
+            &wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: layers.len() as u32,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::COPY_DST
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            },
+            dimension,
+        );
+
+        for (layer, pixels) in layers.iter().enumerate() {
+            wgpu.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: image.view.texture(),
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                pixels,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(format.block_copy_size(None).unwrap() * width),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        Ok(image)
+    }
+
+    #[cfg(feature = "compressed-textures")]
+    fn from_texture_data(gfx: &GraphicsContext, data: TextureData) -> GameResult<Self> {
+        let supported = gfx
+            .wgpu
+            .device
+            .features()
+            .contains(data.format.required_features());
+        let data = if supported { data } else { data.decode()? };
+
+        let image = Self::new(
+            &gfx.wgpu,
+            data.format,
+            data.width,
+            data.height,
+            1,
+            data.levels.len() as u32,
+            wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        );
+
+        let (block_width, block_height) = data.format.block_dimensions();
+        for (level, pixels) in data.levels.iter().enumerate() {
+            let level = level as u32;
+            let (blocks_x, blocks_y) =
+                compressed::level_blocks(data.format, data.width, data.height, level);
+            gfx.wgpu.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: image.view.texture(),
+                    mip_level: level,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                pixels,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(data.format.block_copy_size(None).unwrap() * blocks_x),
+                    rows_per_image: None,
+                },
+                // Compressed levels are uploaded at their size padded to whole blocks.
+                wgpu::Extent3d {
+                    width: blocks_x * block_width,
+                    height: blocks_y * block_height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        Ok(image)
     }
 
     fn new(
@@ -178,47 +547,106 @@ impl Image {
         width: u32,
         height: u32,
         samples: u32,
+        mip_levels: u32,
         usage: wgpu::TextureUsages,
     ) -> Self {
         assert!(width > 0);
         assert!(height > 0);
         assert!(samples > 0);
 
-        let texture = wgpu.device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
-            size: wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
+        Self::create(
+            wgpu,
+            &wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: mip_levels,
+                sample_count: samples,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
             },
-            mip_level_count: 1,
-            sample_count: samples,
-            dimension: wgpu::TextureDimension::D2,
-            format,
-            usage,
-            view_formats: &[],
-        });
+            ImageDimension::D2,
+        )
+    }
+
+    fn create(
+        wgpu: &WgpuContext,
+        desc: &wgpu::TextureDescriptor<'_>,
+        dimension: ImageDimension,
+    ) -> Self {
+        let texture = wgpu.device.create_texture(desc);
+        let layers = desc.size.depth_or_array_layers;
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor {
             label: None,
-            format: Some(format),
-            dimension: Some(wgpu::TextureViewDimension::D2),
+            format: Some(desc.format),
+            dimension: Some(dimension.into()),
             aspect: wgpu::TextureAspect::All,
             base_mip_level: 0,
-            mip_level_count: Some(1),
+            mip_level_count: Some(desc.mip_level_count),
             base_array_layer: 0,
-            array_layer_count: Some(1),
+            array_layer_count: Some(layers),
             usage: None,
         });
 
         Image {
             view,
-            format,
-            width,
-            height,
-            samples,
+            format: desc.format,
+            width: desc.size.width,
+            height: desc.size.height,
+            samples: desc.sample_count,
+            mip_levels: desc.mip_level_count,
+            layers,
+            base_layer: 0,
+            dimension,
+            cache: Arc::new(RwLock::new(BTreeMap::default())),
+        }
+    }
+
+    /// Returns a 2D image viewing a single layer (or cubemap face) of this image, sharing its
+    /// GPU memory. Array and cube images can't be drawn directly, but their layers can.
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn layer(&self, index: u32) -> Image {
+        assert!(index < self.layers, "layer index out of bounds");
+
+        let view = self
+            .view
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor {
+                format: Some(self.format),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                mip_level_count: Some(self.mip_levels),
+                base_array_layer: self.base_layer + index,
+                array_layer_count: Some(1),
+                ..Default::default()
+            });
+
+        Image {
+            view,
+            layers: 1,
+            base_layer: self.base_layer + index,
+            dimension: ImageDimension::D2,
             cache: Arc::new(RwLock::new(BTreeMap::default())),
+            ..self.clone()
+        }
+    }
+
+    /// Fails for array and cube images, which can only be drawn, drawn to, or read back one
+    /// layer at a time.
+    pub(crate) fn check_2d(&self) -> GameResult {
+        if self.dimension == ImageDimension::D2 {
+            return Ok(());
         }
+        Err(GameError::RenderError(format!(
+            "cannot use a {:?} image as a whole here; use `Image::layer` for one of its layers",
+            self.dimension
+        )))
     }
 
     /// Returns the underlying [`wgpu::TextureView`] for this [`Image`].
@@ -229,29 +657,202 @@ impl Image {
         &self.view
     }
 
+    /// Overwrites a sub-rectangle of this image with new pixel data, e.g. to stream video frames
+    /// into a dynamic texture without recreating it. `pixels` must be tightly packed rows in the
+    /// image's format, and the rectangle is in pixel coordinates, rounded towards zero.
+    ///
+    /// The upload lands before anything drawn in the current frame is rendered. The lower mip
+    /// levels of mipmapped images are regenerated afterwards, except for images that can't be
+    /// rendered to, like compressed ones loaded along with their mips, which keep them as they
+    /// were.
+    pub fn write_pixels(
+        &self,
+        gfx: &impl Has<GraphicsContext>,
+        rect: Rect,
+        pixels: &[u8],
+    ) -> GameResult {
+        let gfx = gfx.retrieve();
+        if self.samples > 1 {
+            return Err(GameError::RenderError(String::from(
+                "cannot write the pixels of a multisampled image",
+            )));
+        }
+
+        let (origin, size) = texel_region(rect, self.width, self.height, self.format)?;
+        let (block_width, block_height) = self.format.block_dimensions();
+        let bytes_per_row = self.format.block_copy_size(None).unwrap() * size.width / block_width; // Unwrap since texel_region rejects formats without one.
+        let expected = bytes_per_row as usize * (size.height / block_height) as usize;
+        if pixels.len() != expected {
+            return Err(GameError::RenderError(format!(
+                "expected {expected} bytes of pixel data for the rectangle, got {}",
+                pixels.len()
+            )));
+        }
+
+        gfx.wgpu.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: self.view.texture(),
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    z: self.base_layer,
+                    ..origin
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            pixels,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: None,
+            },
+            size,
+        );
+
+        if self.regenerates_mips(&gfx.wgpu.device) {
+            let mut cmd = gfx
+                .wgpu
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            gfx.mipmaps
+                .generate(&gfx.wgpu.device, &mut cmd, self.view.texture());
+            let _ = gfx.wgpu.queue.submit([cmd.finish()]);
+        }
+
+        Ok(())
+    }
+
+    /// Copies the `src_rect` region of `other` into this image at `dst`, on the GPU. Both are
+    /// in pixel coordinates, rounded towards zero.
+    ///
+    /// The images must have the same format (up to sRGB-ness) and not be multisampled, and an
+    /// image can't be copied into itself, not even between separate regions. During a frame the
+    /// copy is ordered with the canvases drawn before and after it; outside of one it's submitted
+    /// right away. Mip levels are regenerated like with [`Image::write_pixels`].
+    pub fn copy_from(
+        &self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+        other: &Image,
+        src_rect: Rect,
+        dst: impl Into<mint::Point2<f32>>,
+    ) -> GameResult {
+        let gfx = gfx.retrieve_mut();
+        if self.samples > 1 || other.samples > 1 {
+            return Err(GameError::RenderError(String::from(
+                "cannot copy between multisampled images; resolve them with a canvas",
+            )));
+        }
+        if self.format.remove_srgb_suffix() != other.format.remove_srgb_suffix() {
+            return Err(GameError::RenderError(format!(
+                "cannot copy an image of format {:?} into one of format {:?}",
+                other.format, self.format
+            )));
+        }
+        // The GPU can't copy within one layer of a texture, overlapping or not, though it can
+        // between layers of the same texture.
+        if self.view.texture() == other.view.texture() && self.base_layer == other.base_layer {
+            return Err(GameError::RenderError(String::from(
+                "cannot copy an image into itself; copy through another image",
+            )));
+        }
+
+        let (src_origin, size) = texel_region(src_rect, other.width, other.height, other.format)?;
+        let dst = dst.into();
+        let dst_rect = Rect::new(dst.x, dst.y, src_rect.w, src_rect.h);
+        let (dst_origin, dst_size) = texel_region(dst_rect, self.width, self.height, self.format)?;
+        if dst_size != size {
+            return Err(GameError::RenderError(String::from(
+                "compressed images can only be copied between regions covering whole blocks",
+            )));
+        }
+
+        let mut own_cmd = None;
+        let cmd = match &mut gfx.fcx {
+            Some(fcx) => &mut fcx.cmd,
+            None => own_cmd.insert(
+                gfx.wgpu
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor::default()),
+            ),
+        };
+
+        cmd.copy_texture_to_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: other.view.texture(),
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    z: other.base_layer,
+                    ..src_origin
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyTextureInfo {
+                texture: self.view.texture(),
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    z: self.base_layer,
+                    ..dst_origin
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            size,
+        );
+        if self.regenerates_mips(&gfx.wgpu.device) {
+            gfx.mipmaps
+                .generate(&gfx.wgpu.device, cmd, self.view.texture());
+        }
+
+        if let Some(cmd) = own_cmd {
+            let _ = gfx.wgpu.queue.submit([cmd.finish()]);
+        }
+
+        Ok(())
+    }
+
     /// Reads the pixels of this `ImageView` and returns as `Vec<u8>`.
-    /// The format matches the GPU image format.
+    /// The format matches the GPU image format. Fails for array and cube images; read one of
+    /// their layers instead, see [`Image::layer`].
     ///
-    /// **This is a very expensive operation - call sparingly.**
+    /// **This is a very expensive operation - call sparingly.** See [`Image::to_pixels_async`]
+    /// for a version that doesn't stall the frame.
     pub fn to_pixels(&self, gfx: &impl Has<GraphicsContext>) -> GameResult<Vec<u8>> {
+        self.to_pixels_async(gfx, None)?.wait()
+    }
+
+    /// Starts reading the pixels of this image, or of the `rect` region of it (in pixel
+    /// coordinates, rounded towards zero), back from the GPU without waiting for them.
+    ///
+    /// The returned [`PixelReadback`] resolves on a later frame, once the GPU got to it; poll it
+    /// every frame, or await it in a [`Coroutine`](crate::Coroutine). It sees everything drawn
+    /// by canvases finished before this call, except in the current frame.
+    pub fn to_pixels_async(
+        &self,
+        gfx: &impl Has<GraphicsContext>,
+        rect: impl Into<Option<Rect>>,
+    ) -> GameResult<PixelReadback> {
         let gfx = gfx.retrieve();
+        self.check_2d()?;
         if self.samples > 1 {
             return Err(GameError::RenderError(String::from(
                 "cannot read the pixels of a multisampled image; resolve this image with a canvas",
             )));
         }
 
-        let block_size = u64::from(self.format.block_copy_size(None).unwrap()); // Unwrap since it only fails with depth formats.
+        let rect = rect
+            .into()
+            .unwrap_or_else(|| Rect::new(0., 0., self.width as f32, self.height as f32));
+        let (origin, size) = texel_region(rect, self.width, self.height, self.format)?;
 
-        let bytes_per_pixel = block_size;
-        let unpadded_bytes_per_row = self.width as usize * bytes_per_pixel as usize;
+        let (block_width, block_height) = self.format.block_dimensions();
+        let block_size = self.format.block_copy_size(None).unwrap(); // Unwrap since texel_region rejects formats without one.
+        let unpadded_bytes_per_row = (block_size * size.width / block_width) as usize;
         let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
         let padded_bytes_per_row_padding = (align - unpadded_bytes_per_row % align) % align;
         let padded_bytes_per_row = unpadded_bytes_per_row + padded_bytes_per_row_padding;
+        let rows = (size.height / block_height) as usize;
 
         let buffer = gfx.wgpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
-            size: (padded_bytes_per_row * self.height as usize) as u64,
+            size: (padded_bytes_per_row * rows) as u64,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
@@ -262,7 +863,15 @@ impl Image {
                 .device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
             encoder.copy_texture_to_buffer(
-                self.view.texture().as_image_copy(),
+                wgpu::TexelCopyTextureInfo {
+                    texture: self.view.texture(),
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        z: self.base_layer,
+                        ..origin
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
                 wgpu::TexelCopyBufferInfo {
                     buffer: &buffer,
                     layout: wgpu::TexelCopyBufferLayout {
@@ -271,37 +880,31 @@ impl Image {
                         rows_per_image: None,
                     },
                 },
-                wgpu::Extent3d {
-                    width: self.width,
-                    height: self.height,
-                    depth_or_array_layers: 1,
-                },
+                size,
             );
             encoder.finish()
         };
 
         let _ = gfx.wgpu.queue.submit([cmd]);
 
-        // wait...
+        // The buffer can only be mapped once the copy has been submitted.
         let (tx, rx) = std::sync::mpsc::sync_channel(1);
         buffer
             .slice(..)
-            .map_async(wgpu::MapMode::Read, move |result| tx.send(result).unwrap()); // Unwrap is fine as this should never fail
-        let _ = gfx.wgpu.device.poll(wgpu::PollType::wait_indefinitely());
-        let map_result = rx
-            .recv()
-            .expect("All senders dropped, this should not be possible.");
-        map_result?;
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
 
-        let mut out = Vec::new();
-        for chunk in buffer
-            .slice(..)
-            .get_mapped_range()
-            .chunks(padded_bytes_per_row)
-        {
-            out.extend_from_slice(&chunk[..unpadded_bytes_per_row]);
-        }
-        Ok(out)
+        Ok(PixelReadback {
+            device: gfx.wgpu.device.clone(),
+            buffer,
+            rx,
+            width: size.width,
+            height: size.height,
+            padded_bytes_per_row,
+            unpadded_bytes_per_row,
+            finished: false,
+        })
     }
 
     /// Encodes the `ImageView` to the given file format and return the encoded bytes.
@@ -313,34 +916,18 @@ impl Image {
         format: ImageEncodingFormat,
         path: impl AsRef<std::path::Path>,
     ) -> GameResult {
-        let color = match self.format {
-            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => {
-                ::image::ExtendedColorType::Rgba8
-            }
-            ImageFormat::R8Unorm => ::image::ExtendedColorType::L8,
-            ImageFormat::R16Unorm => ::image::ExtendedColorType::L16,
-            format => {
-                return Err(GameError::RenderError(format!(
-                    "cannot ImageView::encode for the {format:#?} GPU image format"
-                )))
-            }
-        };
-
-        let pixels = self.to_pixels(ctx)?;
+        let (pixels, color) = self.to_encodable_pixels(ctx)?;
         let f = ctx.fs.create(path)?;
-        let writer = &mut std::io::BufWriter::new(f);
+        encode_pixels(f, format, &pixels, self.width, self.height, color)
+    }
 
-        match format {
-            ImageEncodingFormat::Png => ::image::codecs::png::PngEncoder::new(writer)
-                .write_image(&pixels, self.width, self.height, color)
-                .map_err(Into::into),
-            ImageEncodingFormat::Bmp => ::image::codecs::bmp::BmpEncoder::new(writer)
-                .encode(&pixels, self.width, self.height, color)
-                .map_err(Into::into),
-            _ => Err(GameError::RenderError(String::from(
-                "cannot ImageView::encode for formats other than Png and Bmp",
-            ))),
-        }
+    /// Reads the pixels of this image in a layout the `image` crate can encode,
+    /// swizzling BGRA images (such as the frame) into RGBA order.
+    pub(crate) fn to_encodable_pixels(
+        &self,
+        gfx: &impl Has<GraphicsContext>,
+    ) -> GameResult<(Vec<u8>, ::image::ExtendedColorType)> {
+        encodable_pixels(self.format, self.to_pixels(gfx)?)
     }
 
     /// Returns the image format of this image.
@@ -355,6 +942,55 @@ impl Image {
         self.samples
     }
 
+    /// Returns the number of mip levels this image has; 1 unless it was created mipmapped.
+    #[inline]
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    /// Returns the number of layers this image has; 6 for cubemaps, 1 for plain 2D images.
+    #[inline]
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    /// Returns how the layers of this image are viewed by shaders.
+    #[inline]
+    pub fn dimension(&self) -> ImageDimension {
+        self.dimension
+    }
+
+    /// Whether this image has lower mip levels that can be regenerated from the top one, which
+    /// needs a format and usage that allow rendering to it.
+    fn regenerates_mips(&self, device: &wgpu::Device) -> bool {
+        self.mip_levels > 1
+            && self
+                .view
+                .texture()
+                .usage()
+                .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+            && MipmapGenerator::supports(device, self.format)
+    }
+
+    /// Returns a view of the top mip level, for rendering to.
+    pub(crate) fn target_view(&self) -> wgpu::TextureView {
+        if self.mip_levels == 1 {
+            return self.view.clone();
+        }
+
+        self.view
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor {
+                format: Some(self.format),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_mip_level: 0,
+                mip_level_count: Some(1),
+                base_array_layer: self.base_layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            })
+    }
+
     /// Returns the width (in pixels) of the image.
     #[inline]
     pub fn width(&self) -> u32 {
@@ -404,7 +1040,13 @@ impl Image {
 }
 
 impl Drawable for Image {
+    /// Array and cube images aren't drawn, and log an error instead; draw one of their layers,
+    /// see [`Image::layer`].
     fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        if let Err(e) = self.check_2d() {
+            error!("{e}");
+            return;
+        }
         canvas.push_draw(
             Draw::Mesh {
                 mesh: canvas.default_resources().mesh.clone(),
@@ -425,6 +1067,271 @@ impl Drawable for Image {
     }
 }
 
+/// Checks that there are layers for the dimension, each holding `width * height` pixels.
+fn check_layers(
+    layers: &[&[u8]],
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    dimension: ImageDimension,
+) -> GameResult {
+    if width == 0 || height == 0 {
+        return Err(GameError::RenderError(String::from(
+            "image layers must not be empty",
+        )));
+    }
+    match dimension {
+        ImageDimension::D2 if layers.len() != 1 => {
+            return Err(GameError::RenderError(String::from(
+                "2D images have exactly one layer",
+            )))
+        }
+        ImageDimension::D2Array if layers.is_empty() => {
+            return Err(GameError::RenderError(String::from(
+                "array images need at least one layer",
+            )))
+        }
+        ImageDimension::Cube if layers.len() != 6 || width != height => {
+            return Err(GameError::RenderError(String::from(
+                "cubemaps need six square faces",
+            )))
+        }
+        _ => {}
+    }
+
+    let block_size = format.block_copy_size(None).ok_or_else(|| {
+        GameError::RenderError(format!("cannot upload pixels of format {format:?}"))
+    })?;
+    let expected = block_size as usize * width as usize * height as usize;
+    if let Some(index) = layers.iter().position(|layer| layer.len() != expected) {
+        return Err(GameError::RenderError(format!(
+            "layer {index} has {} bytes, expected {expected}",
+            layers[index].len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Brings pixels read from an image of the given format into a layout the `image` crate can
+/// encode, swizzling BGRA pixels into RGBA order.
+pub(crate) fn encodable_pixels(
+    format: ImageFormat,
+    mut pixels: Vec<u8>,
+) -> GameResult<(Vec<u8>, ::image::ExtendedColorType)> {
+    let (color, swizzle) = match format {
+        ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => {
+            (::image::ExtendedColorType::Rgba8, false)
+        }
+        ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+            (::image::ExtendedColorType::Rgba8, true)
+        }
+        ImageFormat::R8Unorm => (::image::ExtendedColorType::L8, false),
+        ImageFormat::R16Unorm => (::image::ExtendedColorType::L16, false),
+        format => {
+            return Err(GameError::RenderError(format!(
+                "cannot ImageView::encode for the {format:#?} GPU image format"
+            )))
+        }
+    };
+
+    if swizzle {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    Ok((pixels, color))
+}
+
+/// Encodes pixels from [`encodable_pixels`] as PNG or BMP into `writer`.
+pub(crate) fn encode_pixels(
+    writer: impl std::io::Write,
+    format: ImageEncodingFormat,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    color: ::image::ExtendedColorType,
+) -> GameResult {
+    let writer = &mut std::io::BufWriter::new(writer);
+
+    match format {
+        ImageEncodingFormat::Png => ::image::codecs::png::PngEncoder::new(writer)
+            .write_image(pixels, width, height, color)
+            .map_err(Into::into),
+        ImageEncodingFormat::Bmp => ::image::codecs::bmp::BmpEncoder::new(writer)
+            .encode(pixels, width, height, color)
+            .map_err(Into::into),
+        _ => Err(GameError::RenderError(String::from(
+            "cannot ImageView::encode for formats other than Png and Bmp",
+        ))),
+    }
+}
+
+/// Converts a rectangle in pixel coordinates to a copy region of a `width` by `height` image,
+/// checking that it's in bounds and aligned to the blocks of compressed formats. Regions ending
+/// in a partial block at the image's edge are padded to whole blocks, as wgpu expects.
+fn texel_region(
+    rect: Rect,
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+) -> GameResult<(wgpu::Origin3d, wgpu::Extent3d)> {
+    if format.block_copy_size(None).is_none() {
+        return Err(GameError::RenderError(format!(
+            "cannot copy pixels of format {format:?}"
+        )));
+    }
+    if rect.x < 0. || rect.y < 0. {
+        return Err(GameError::RenderError(String::from(
+            "the rectangle cannot start outside the image",
+        )));
+    }
+
+    let (x, y, w, h) = (rect.x as u32, rect.y as u32, rect.w as u32, rect.h as u32);
+    if w == 0 || h == 0 {
+        return Err(GameError::RenderError(String::from(
+            "the rectangle size must be larger than zero",
+        )));
+    }
+    if x.saturating_add(w) > width || y.saturating_add(h) > height {
+        return Err(GameError::RenderError(format!(
+            "the rectangle ({x}, {y}, {w}, {h}) doesn't fit in the {width}x{height} image"
+        )));
+    }
+
+    // Compressed formats can only be copied in whole blocks, except at the image's edges.
+    let (block_width, block_height) = format.block_dimensions();
+    let aligned = |start: u32, len: u32, block: u32, size: u32| {
+        start.is_multiple_of(block) && (len.is_multiple_of(block) || start + len == size)
+    };
+    if !aligned(x, w, block_width, width) || !aligned(y, h, block_height, height) {
+        return Err(GameError::RenderError(format!(
+            "the rectangle must be aligned to the {block_width}x{block_height} blocks of {format:?}"
+        )));
+    }
+
+    Ok((
+        wgpu::Origin3d { x, y, z: 0 },
+        wgpu::Extent3d {
+            width: w.next_multiple_of(block_width),
+            height: h.next_multiple_of(block_height),
+            depth_or_array_layers: 1,
+        },
+    ))
+}
+
+/// Decodes an encoded image (e.g. PNG or JPEG) into RGBA8 pixels.
+fn decode_rgba8(encoded: &[u8]) -> GameResult<image::RgbaImage> {
+    image::load_from_memory(encoded)
+        .map(|decoded| decoded.to_rgba8())
+        .map_err(|_| GameError::ResourceLoadError(String::from("failed to load image")))
+}
+
+/// Number of levels in a full mip chain, down to 1x1.
+pub(crate) fn mip_levels(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Pending read of an image's pixels back from the GPU; see [`Image::to_pixels_async`].
+///
+/// Also a [`Future`], so it can be awaited in a [`Coroutine`](crate::Coroutine).
+#[derive(Debug)]
+pub struct PixelReadback {
+    device: wgpu::Device,
+    buffer: wgpu::Buffer,
+    rx: std::sync::mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: usize,
+    unpadded_bytes_per_row: usize,
+    finished: bool,
+}
+
+impl PixelReadback {
+    /// Returns the width (in pixels) of the region being read.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height (in pixels) of the region being read.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Checks on the readback without blocking, returning the pixels once they've arrived.
+    ///
+    /// The format matches the GPU image format. Returns `None` while the GPU isn't done yet,
+    /// and again after the pixels have been returned once.
+    pub fn poll(&mut self) -> Option<GameResult<Vec<u8>>> {
+        if self.finished {
+            return None;
+        }
+
+        let _ = self.device.poll(wgpu::PollType::Poll);
+        let map_result = match self.rx.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return None,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                self.finished = true;
+                return Some(Err(GameError::RenderError(String::from(
+                    "the pixel readback was dropped by the GPU",
+                ))));
+            }
+        };
+        self.finished = true;
+
+        Some(map_result.map_err(Into::into).map(|()| self.read_mapped()))
+    }
+
+    /// Blocks until the pixels have arrived and returns them.
+    pub fn wait(mut self) -> GameResult<Vec<u8>> {
+        let _ = self.device.poll(wgpu::PollType::wait_indefinitely());
+        let map_result = self
+            .rx
+            .recv()
+            .expect("All senders dropped, this should not be possible.");
+        map_result?;
+        self.finished = true;
+
+        Ok(self.read_mapped())
+    }
+
+    fn read_mapped(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.unpadded_bytes_per_row * self.height as usize);
+        for chunk in self
+            .buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks(self.padded_bytes_per_row)
+        {
+            out.extend_from_slice(&chunk[..self.unpadded_bytes_per_row]);
+        }
+        self.buffer.unmap();
+        out
+    }
+}
+
+impl std::future::Future for PixelReadback {
+    type Output = GameResult<Vec<u8>>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        match PixelReadback::poll(self.get_mut()) {
+            Some(result) => std::task::Poll::Ready(result),
+            None => {
+                // Nothing wakes us up when the GPU is done, so ask to be polled again.
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            }
+        }
+    }
+}
+
 /// An image which is sized relative to the screen.
 /// This is primarily for canvas images.
 #[derive(Debug, Clone)]
@@ -494,9 +1401,9 @@ impl ScreenImage {
 
     fn size(gfx: &impl Has<GraphicsContext>, (width, height): (f32, f32)) -> (u32, u32) {
         let gfx = gfx.retrieve();
-        let size = gfx.window.inner_size();
-        let width = (size.width as f32 * width) as u32;
-        let height = (size.height as f32 * height) as u32;
+        let size = gfx.drawable_size();
+        let width = (size.0 * width) as u32;
+        let height = (size.1 * height) as u32;
         (width.max(1), height.max(1))
     }
 
@@ -510,3 +1417,85 @@ impl ScreenImage {
         Image::new_canvas_image_raw(gfx, format, width, height, samples)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_mip_levels() {
+        assert_eq!(mip_levels(1, 1), 1);
+        assert_eq!(mip_levels(2, 1), 2);
+        assert_eq!(mip_levels(256, 100), 9);
+        assert_eq!(mip_levels(257, 100), 9);
+        assert_eq!(mip_levels(100, 512), 10);
+    }
+
+    #[test]
+    fn headless_test_texel_region() {
+        let format = ImageFormat::Rgba8UnormSrgb;
+        let (origin, size) = texel_region(Rect::new(2.7, 3., 4., 5.), 16, 16, format).unwrap();
+        assert_eq!((origin.x, origin.y), (2, 3));
+        assert_eq!((size.width, size.height), (4, 5));
+
+        assert!(texel_region(Rect::new(0., 0., 16., 16.), 16, 16, format).is_ok());
+        assert!(texel_region(Rect::new(8., 0., 9., 1.), 16, 16, format).is_err());
+        assert!(texel_region(Rect::new(-1., 0., 1., 1.), 16, 16, format).is_err());
+        assert!(texel_region(Rect::new(0., 0., 0., 1.), 16, 16, format).is_err());
+
+        let bc1 = ImageFormat::Bc1RgbaUnormSrgb;
+        assert!(texel_region(Rect::new(4., 8., 4., 4.), 16, 16, bc1).is_ok());
+        assert!(texel_region(Rect::new(2., 0., 4., 4.), 16, 16, bc1).is_err());
+        // Partial blocks are fine at the edges of images that aren't a multiple of the block size,
+        // and get padded to whole blocks.
+        let (_, size) = texel_region(Rect::new(8., 8., 6., 6.), 14, 14, bc1).unwrap();
+        assert_eq!((size.width, size.height), (8, 8));
+    }
+
+    #[test]
+    fn headless_test_check_layers() {
+        let format = ImageFormat::Rgba8UnormSrgb;
+        let layer = &[0u8; 2 * 2 * 4][..];
+        let short = &[0u8; 2 * 2 * 4 - 1][..];
+
+        assert!(check_layers(&[layer; 3], format, 2, 2, ImageDimension::D2Array).is_ok());
+        assert!(check_layers(&[], format, 2, 2, ImageDimension::D2Array).is_err());
+        assert!(check_layers(&[layer, short], format, 2, 2, ImageDimension::D2Array).is_err());
+
+        assert!(check_layers(&[layer; 6], format, 2, 2, ImageDimension::Cube).is_ok());
+        assert!(check_layers(&[layer; 5], format, 2, 2, ImageDimension::Cube).is_err());
+        let wide = &[0u8; 4 * 2 * 4][..];
+        assert!(check_layers(&[wide; 6], format, 4, 2, ImageDimension::Cube).is_err());
+    }
+
+    #[test]
+    fn headless_test_write_prebuilt_mips() {
+        let mut ctx =
+            match crate::ContextBuilder::new("ggez_image_test", "ggez").build_headless(1, 1) {
+                Ok(ctx) => ctx,
+                // No adapter at all, not even a software one, or no audio device to open.
+                Err(GameError::GraphicsInitializationError | GameError::AudioError(_)) => return,
+                Err(e) => panic!("{e}"),
+            };
+
+        // Like a compressed texture loaded with its mips, which can't be rendered to.
+        let format = ImageFormat::Rgba8UnormSrgb;
+        let usage = wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::TEXTURE_BINDING;
+        let prebuilt = Image::new(&ctx.gfx.wgpu, format, 4, 4, 1, 3, usage);
+        let pixels = [255u8; 4 * 4 * 4];
+        let rect = Rect::new(0., 0., 4., 4.);
+        prebuilt.write_pixels(&ctx, rect, &pixels).unwrap();
+
+        let mipmapped = Image::from_pixels_mipmapped(&ctx, &pixels, format, 4, 4).unwrap();
+        mipmapped.write_pixels(&ctx, rect, &pixels).unwrap();
+        prebuilt
+            .copy_from(&mut ctx, &mipmapped, rect, [0., 0.])
+            .unwrap();
+        mipmapped
+            .copy_from(&mut ctx, &prebuilt, rect, [0., 0.])
+            .unwrap();
+        assert_eq!(prebuilt.to_pixels(&ctx).unwrap(), pixels);
+    }
+}
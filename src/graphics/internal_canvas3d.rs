@@ -60,11 +60,12 @@ impl<'a> InternalCanvas3d<'a> {
             return Err(GameError::RenderError(String::from("non-MSAA rendering requires an image with exactly 1 sample, for this image use Canvas::from_msaa instead")));
         }
 
+        let view = image.target_view();
         Self::new(gfx, 1, image.format(), |cmd| {
             cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &image.view,
+                    view: &view,
                     depth_slice: None,
                     resolve_target: None,
                     ops: wgpu::Operations {
@@ -115,13 +116,14 @@ impl<'a> InternalCanvas3d<'a> {
             )));
         }
 
+        let resolve_view = resolve_image.target_view();
         Self::new(gfx, msaa_image.samples(), msaa_image.format(), |cmd| {
             cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                     view: &msaa_image.view,
                     depth_slice: None,
-                    resolve_target: Some(&resolve_image.view),
+                    resolve_target: Some(&resolve_view),
                     ops: wgpu::Operations {
                         load: match clear.into() {
                             None => wgpu::LoadOp::Load,
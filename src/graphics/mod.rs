@@ -32,26 +32,40 @@ pub(crate) mod instance3d;
 #[cfg(feature = "3d")]
 pub(crate) mod internal_canvas3d;
 
+pub(crate) mod animation;
+pub(crate) mod atlas;
+pub(crate) mod camera2d;
 pub(crate) mod canvas;
+pub(crate) mod capture;
+#[cfg(feature = "compressed-textures")]
+pub(crate) mod compressed;
 /// Module for the graphics context dealing with wgpu
 pub mod context;
 pub(crate) mod draw;
 pub(crate) mod gpu;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
 pub(crate) mod image;
 pub(crate) mod instance;
 pub(crate) mod internal_canvas;
 pub(crate) mod mesh;
 #[cfg(feature = "3d")]
 pub(crate) mod mesh3d;
+pub(crate) mod packer;
+pub mod particles;
+pub mod postprocess;
 pub(crate) mod sampler;
 pub(crate) mod shader;
+pub(crate) mod snapshot;
 pub(crate) mod text;
+#[cfg(feature = "tilemap")]
+pub mod tilemap;
 mod types;
 
 pub use lyon::tessellation::{FillOptions, FillRule, LineCap, LineJoin, StrokeOptions};
 pub use {
-    self::image::*, canvas::*, context::*, draw::*, instance::*, mesh::*, sampler::*, shader::*,
-    text::*, types::*,
+    self::image::*, animation::*, atlas::*, camera2d::*, canvas::*, context::*, draw::*,
+    instance::*, mesh::*, packer::*, sampler::*, shader::*, snapshot::*, text::*, types::*,
 };
 
 #[cfg(feature = "3d")]
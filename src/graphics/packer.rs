@@ -0,0 +1,476 @@
+use super::{context::GraphicsContext, Canvas, DrawParam, Drawable, Image, ImageFormat, Rect};
+use crate::{context::Has, GameError, GameResult};
+
+/// Packs many small images into as few large atlas images ("pages") as possible.
+///
+/// Every change of image between two draws of a [`Canvas`] costs a new bind group and breaks
+/// batching, so drawing lots of distinct small images is a lot faster once they share a page.
+/// Images that end up on the same page can also be drawn with a single
+/// [`InstanceArray`](super::InstanceArray).
+///
+/// ```rust,no_run
+/// # use ggez::{graphics::{AtlasPacker, Canvas, Image}, Context, GameResult};
+/// # fn t(ctx: &mut Context, canvas: &mut Canvas, icons: &[Image]) -> GameResult {
+/// let mut packer = AtlasPacker::new().padding(1).extrude(1);
+/// for icon in icons {
+///     packer.add_image(ctx, icon)?;
+/// }
+/// let packed = packer.build(ctx)?;
+///
+/// // Drawn just like the original images, but without switching images in between.
+/// for (i, icon) in packed.iter().enumerate() {
+///     canvas.draw(icon, [i as f32 * 32., 0.]);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AtlasPacker {
+    max_size: u32,
+    padding: u32,
+    extrude: u32,
+    sources: Vec<PackSource>,
+}
+
+#[derive(Debug, Clone)]
+struct PackSource {
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    /// The format of the page to pack into, as images of different formats can't share one.
+    format: ImageFormat,
+}
+
+impl Default for AtlasPacker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AtlasPacker {
+    /// Creates an empty packer, with pages of up to 4096x4096 pixels and no padding or extrusion.
+    pub fn new() -> Self {
+        AtlasPacker {
+            max_size: 4096,
+            padding: 0,
+            extrude: 0,
+            sources: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum width and height of a page. It's also limited by the maximum
+    /// texture size supported by the GPU.
+    #[must_use]
+    pub fn max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the number of transparent pixels between the packed images.
+    #[must_use]
+    pub fn padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets how many times the outermost pixels of each image are repeated around it.
+    ///
+    /// This keeps neighbouring images from bleeding in when the images are drawn with linear
+    /// filtering or at non-integer positions.
+    #[must_use]
+    pub fn extrude(mut self, extrude: u32) -> Self {
+        self.extrude = extrude;
+        self
+    }
+
+    /// Adds the pixels of an image to be packed, returning its index in the result of
+    /// [`AtlasPacker::build`].
+    ///
+    /// Only 8-bit RGBA and BGRA images are supported. sRGB and linear images are packed onto
+    /// separate pages, keeping their format. This reads the image back from the GPU, so prefer
+    /// [`AtlasPacker::add_pixels`] if the pixels are at hand already.
+    pub fn add_image(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        image: &Image,
+    ) -> GameResult<usize> {
+        let (pixels, color) = image.to_encodable_pixels(gfx)?;
+        if color != ::image::ExtendedColorType::Rgba8 {
+            return Err(GameError::RenderError(format!(
+                "cannot pack images of the {:#?} GPU image format",
+                image.format()
+            )));
+        }
+        let format = match image.format() {
+            ImageFormat::Rgba8Unorm | ImageFormat::Bgra8Unorm => ImageFormat::Rgba8Unorm,
+            _ => ImageFormat::Rgba8UnormSrgb,
+        };
+        self.add_source(pixels, image.width(), image.height(), format)
+    }
+
+    /// Adds an image as sRGB RGBA pixels, 4 bytes per pixel and row by row, to be packed,
+    /// returning its index in the result of [`AtlasPacker::build`].
+    pub fn add_pixels(
+        &mut self,
+        pixels: impl Into<Vec<u8>>,
+        width: u32,
+        height: u32,
+    ) -> GameResult<usize> {
+        self.add_source(pixels.into(), width, height, ImageFormat::Rgba8UnormSrgb)
+    }
+
+    fn add_source(
+        &mut self,
+        pixels: Vec<u8>,
+        width: u32,
+        height: u32,
+        format: ImageFormat,
+    ) -> GameResult<usize> {
+        if width == 0 || height == 0 || pixels.len() != width as usize * height as usize * 4 {
+            return Err(GameError::RenderError(format!(
+                "expected {width}x{height} RGBA pixels, got {} bytes",
+                pixels.len()
+            )));
+        }
+        self.sources.push(PackSource {
+            pixels,
+            width,
+            height,
+            format,
+        });
+        Ok(self.sources.len() - 1)
+    }
+
+    /// Packs all added images into pages and uploads them to the GPU.
+    ///
+    /// Returns one [`PackedImage`] for each added image, in the order they were added.
+    pub fn build(self, gfx: &impl Has<GraphicsContext>) -> GameResult<Vec<PackedImage>> {
+        let gfx = gfx.retrieve();
+        let max_size = self
+            .max_size
+            .min(gfx.wgpu.device.limits().max_texture_dimension_2d);
+        let border = 2 * self.extrude;
+
+        let sizes: Vec<_> = self
+            .sources
+            .iter()
+            .map(|source| (source.width + border, source.height + border))
+            .collect();
+
+        // Each format gets pages of its own, numbered after those of the formats before it.
+        let mut placements = vec![
+            Placement {
+                page: 0,
+                x: 0,
+                y: 0
+            };
+            sizes.len()
+        ];
+        let mut page_formats: Vec<ImageFormat> = Vec::new();
+        let mut formats = Vec::new();
+        for source in &self.sources {
+            if !formats.contains(&source.format) {
+                formats.push(source.format);
+            }
+        }
+        for format in formats {
+            let indices: Vec<_> = (0..sizes.len())
+                .filter(|&i| self.sources[i].format == format)
+                .collect();
+            let format_sizes: Vec<_> = indices.iter().map(|&i| sizes[i]).collect();
+            let packed = pack(&format_sizes, max_size, self.padding).map_err(|i| {
+                let source = &self.sources[indices[i]];
+                GameError::RenderError(format!(
+                    "cannot pack a {}x{} image into an atlas of at most {max_size}x{max_size} pixels",
+                    source.width, source.height
+                ))
+            })?;
+
+            let first_page = page_formats.len();
+            let page_count = packed.iter().map(|p| p.page + 1).max().unwrap_or(0);
+            page_formats.resize(first_page + page_count, format);
+            for (&i, placement) in indices.iter().zip(packed) {
+                placements[i] = Placement {
+                    page: first_page + placement.page,
+                    ..placement
+                };
+            }
+        }
+
+        let mut pages: Vec<(Vec<u8>, u32, u32)> = (0..page_formats.len())
+            .map(|page| {
+                let (width, height) = placements
+                    .iter()
+                    .zip(&sizes)
+                    .filter(|(p, _)| p.page == page)
+                    .fold((1, 1), |(width, height), (p, (w, h))| {
+                        (width.max(p.x + w), height.max(p.y + h))
+                    });
+                (vec![0; width as usize * height as usize * 4], width, height)
+            })
+            .collect();
+
+        for (p, source) in placements.iter().zip(&self.sources) {
+            let (page, page_width, _) = &mut pages[p.page];
+            blit_extruded(
+                page,
+                *page_width,
+                &source.pixels,
+                source.width,
+                source.height,
+                p.x,
+                p.y,
+                self.extrude,
+            );
+        }
+
+        let images: Vec<_> = pages
+            .iter()
+            .zip(&page_formats)
+            .map(|((pixels, width, height), format)| {
+                Image::from_pixels(gfx, pixels, *format, *width, *height)
+            })
+            .collect();
+
+        Ok(placements
+            .iter()
+            .zip(&self.sources)
+            .map(|(p, source)| PackedImage {
+                image: images[p.page].clone(),
+                rect: Rect::new(
+                    (p.x + self.extrude) as f32,
+                    (p.y + self.extrude) as f32,
+                    source.width as f32,
+                    source.height as f32,
+                ),
+            })
+            .collect())
+    }
+}
+
+/// An image packed into a page by an [`AtlasPacker`].
+///
+/// It can be drawn like an [`Image`], with [`DrawParam::src`] relative to the packed image.
+/// To draw it with an [`InstanceArray`](super::InstanceArray), create the array with
+/// [`PackedImage::image`] and push [`PackedImage::param`].
+#[derive(Debug, Clone)]
+pub struct PackedImage {
+    image: Image,
+    rect: Rect,
+}
+
+impl PackedImage {
+    /// Returns the page this image was packed into.
+    #[inline]
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// Returns the pixel rect of this image within its page.
+    #[inline]
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Returns the rect of this image within its page in UV coordinates.
+    pub fn uv_rect(&self) -> Rect {
+        Rect::new(
+            self.rect.x / self.image.width() as f32,
+            self.rect.y / self.image.height() as f32,
+            self.rect.w / self.image.width() as f32,
+            self.rect.h / self.image.height() as f32,
+        )
+    }
+
+    /// Returns a default [`DrawParam`] with its `src` set to this image, for drawing its page.
+    pub fn param(&self) -> DrawParam {
+        DrawParam::new().src(self.uv_rect())
+    }
+}
+
+impl Drawable for PackedImage {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        let mut param = param.into();
+        let uv = self.uv_rect();
+        param.src = Rect::new(
+            uv.x + param.src.x * uv.w,
+            uv.y + param.src.y * uv.h,
+            param.src.w * uv.w,
+            param.src.h * uv.h,
+        );
+        self.image.draw(canvas, param);
+    }
+
+    fn dimensions(&self, _gfx: &impl Has<GraphicsContext>) -> Rect {
+        Rect::new(0., 0., self.rect.w, self.rect.h)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Placement {
+    page: usize,
+    x: u32,
+    y: u32,
+}
+
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    next_x: u32,
+}
+
+/// Places rects of the given sizes onto as few pages of `max_size` as possible using shelf
+/// packing, tallest first, leaving `padding` between them.
+///
+/// Fails with the index of a rect that doesn't fit onto a page at all.
+fn pack(sizes: &[(u32, u32)], max_size: u32, padding: u32) -> Result<Vec<Placement>, usize> {
+    let mut order: Vec<_> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse((sizes[i].1, sizes[i].0)));
+
+    let mut placements = vec![
+        Placement {
+            page: 0,
+            x: 0,
+            y: 0
+        };
+        sizes.len()
+    ];
+    let mut pages: Vec<Vec<Shelf>> = Vec::new();
+
+    for i in order {
+        let (w, h) = sizes[i];
+        if w > max_size || h > max_size {
+            return Err(i);
+        }
+
+        let mut placed = None;
+        'pages: for (page, shelves) in pages.iter_mut().enumerate() {
+            for shelf in shelves.iter_mut() {
+                if h <= shelf.height && shelf.next_x + w <= max_size {
+                    placed = Some(Placement {
+                        page,
+                        x: shelf.next_x,
+                        y: shelf.y,
+                    });
+                    shelf.next_x += w + padding;
+                    break 'pages;
+                }
+            }
+
+            let y = shelves.last().map_or(0, |s| s.y + s.height + padding);
+            if y + h <= max_size {
+                shelves.push(Shelf {
+                    y,
+                    height: h,
+                    next_x: w + padding,
+                });
+                placed = Some(Placement { page, x: 0, y });
+                break;
+            }
+        }
+
+        placements[i] = placed.unwrap_or_else(|| {
+            pages.push(vec![Shelf {
+                y: 0,
+                height: h,
+                next_x: w + padding,
+            }]);
+            Placement {
+                page: pages.len() - 1,
+                x: 0,
+                y: 0,
+            }
+        });
+    }
+
+    Ok(placements)
+}
+
+/// Copies `src` to (`x`, `y`) of `page`, repeating its outermost pixels `extrude` times around it.
+#[allow(clippy::too_many_arguments)]
+fn blit_extruded(
+    page: &mut [u8],
+    page_width: u32,
+    src: &[u8],
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    extrude: u32,
+) {
+    for dy in 0..height + 2 * extrude {
+        let sy = dy.saturating_sub(extrude).min(height - 1);
+        for dx in 0..width + 2 * extrude {
+            let sx = dx.saturating_sub(extrude).min(width - 1);
+            let from = (sy * width + sx) as usize * 4;
+            let to = ((y + dy) * page_width + x + dx) as usize * 4;
+            page[to..to + 4].copy_from_slice(&src[from..from + 4]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_pack_shelves_and_pages() {
+        let sizes = [(4, 2), (4, 4), (4, 4), (8, 8)];
+        let placements = pack(&sizes, 10, 1).unwrap();
+
+        // Tallest first: the 8x8 fills most of the first shelf, so one 4x4 spills into a new page.
+        assert_eq!(
+            placements[3],
+            Placement {
+                page: 0,
+                x: 0,
+                y: 0
+            }
+        );
+        assert_eq!(
+            placements[1],
+            Placement {
+                page: 1,
+                x: 0,
+                y: 0
+            }
+        );
+        assert_eq!(
+            placements[2],
+            Placement {
+                page: 1,
+                x: 5,
+                y: 0
+            }
+        );
+        assert_eq!(
+            placements[0],
+            Placement {
+                page: 1,
+                x: 0,
+                y: 5
+            }
+        );
+
+        assert_eq!(pack(&[(11, 1)], 10, 0), Err(0));
+    }
+
+    #[test]
+    fn headless_test_blit_extruded() {
+        // 2x1 image: red, green
+        let src = [255, 0, 0, 255, 0, 255, 0, 255];
+        let mut page = vec![0; 4 * 3 * 4];
+        blit_extruded(&mut page, 4, &src, 2, 1, 0, 0, 1);
+
+        let red = [255, 0, 0, 255];
+        let green = [0, 255, 0, 255];
+        for row in 0..3 {
+            let row = &page[row * 16..(row + 1) * 16];
+            assert_eq!(&row[0..4], &red);
+            assert_eq!(&row[4..8], &red);
+            assert_eq!(&row[8..12], &green);
+            assert_eq!(&row[12..16], &green);
+        }
+    }
+}
@@ -0,0 +1,630 @@
+//! Particle effects: [`Emitter`]s spawn and simulate particles on the CPU, and a
+//! [`ParticleSystem`] draws the particles of all its emitters in one go with an
+//! [`InstanceArray`].
+//!
+//! ```rust,no_run
+//! # use ggez::{graphics::{particles::*, Canvas, Color, Image}, Context, GameResult};
+//! # fn t(ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+//! let spark = Image::from_path(ctx, "/spark.png")?;
+//! let mut sparks = ParticleSystem::new(ctx, spark);
+//! sparks.add_emitter(
+//!     Emitter::new()
+//!         .with_position([400., 300.])
+//!         .rate(200.)
+//!         .shape(EmitterShape::Circle { radius: 8. })
+//!         .lifetime(0.5..=1.0)
+//!         .speed(50.0..=150.0)
+//!         .direction(-std::f32::consts::FRAC_PI_2, 0.6)
+//!         .gravity([0., 200.])
+//!         .color(Curve::linear(Color::YELLOW, Color::new(1., 0., 0., 0.)))
+//!         .size(Curve::constant(1.).key(1., 0.2)),
+//! );
+//!
+//! // in `update`
+//! sparks.update(ctx.time.delta());
+//!
+//! // in `draw`
+//! canvas.draw(&sparks, [0., 0.]);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{
+    context::GraphicsContext, Canvas, Color, DrawParam, Drawable, Image, InstanceArray, Rect,
+};
+use crate::{context::Has, glam::Vec2, rng::Rng};
+use std::{ops::RangeInclusive, time::Duration};
+
+/// A value that can be interpolated by a [`Curve`].
+pub trait CurveValue: Copy {
+    /// Interpolates linearly between `self` at `t == 0.0` and `other` at `t == 1.0`.
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl CurveValue for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl CurveValue for Color {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Color::new(
+            self.r.lerp(other.r, t),
+            self.g.lerp(other.g, t),
+            self.b.lerp(other.b, t),
+            self.a.lerp(other.a, t),
+        )
+    }
+}
+
+impl CurveValue for mint::Vector2<f32> {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec2::from(self).lerp(other.into(), t).into()
+    }
+}
+
+/// A value changing over the life of a particle, made of keys between which it's
+/// interpolated linearly.
+///
+/// Keys are placed at a time from `0.0`, when a particle is spawned, to `1.0`, when it dies.
+/// Before the first and after the last key the value stays the same.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve<T> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: CurveValue> Curve<T> {
+    /// Creates a curve that's always `value`.
+    pub fn constant(value: T) -> Self {
+        Curve {
+            keys: vec![(0., value)],
+        }
+    }
+
+    /// Creates a curve going from `from` at spawn to `to` at death.
+    pub fn linear(from: T, to: T) -> Self {
+        Curve {
+            keys: vec![(0., from), (1., to)],
+        }
+    }
+
+    /// Adds a key, replacing any other key at the same time.
+    #[must_use]
+    pub fn key(mut self, time: f32, value: T) -> Self {
+        let time = time.clamp(0., 1.);
+        match self.keys.binary_search_by(|(t, _)| t.total_cmp(&time)) {
+            Ok(i) => self.keys[i].1 = value,
+            Err(i) => self.keys.insert(i, (time, value)),
+        }
+        self
+    }
+
+    /// Returns the keys of the curve, sorted by time.
+    #[inline]
+    pub fn keys(&self) -> &[(f32, T)] {
+        &self.keys
+    }
+
+    /// Returns the value at `time`, from `0.0` to `1.0`.
+    pub fn sample(&self, time: f32) -> T {
+        let next = self.keys.partition_point(|(t, _)| *t <= time);
+        match (
+            next.checked_sub(1).map(|i| self.keys[i]),
+            self.keys.get(next),
+        ) {
+            (Some((t0, v0)), Some(&(t1, v1))) => v0.lerp(v1, (time - t0) / (t1 - t0)),
+            (Some((_, v)), None) | (None, Some(&(_, v))) => v,
+            (None, None) => unreachable!("curves have at least one key"),
+        }
+    }
+}
+
+impl<T: CurveValue> From<T> for Curve<T> {
+    fn from(value: T) -> Self {
+        Curve::constant(value)
+    }
+}
+
+/// The area particles are spawned in, centered on the emitter's position.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EmitterShape {
+    /// All particles are spawned at the emitter's position.
+    #[default]
+    Point,
+    /// Anywhere in a circle.
+    Circle {
+        /// The radius of the circle.
+        radius: f32,
+    },
+    /// Anywhere in a ring between two circles.
+    Ring {
+        /// The radius of the inner circle.
+        inner_radius: f32,
+        /// The radius of the outer circle.
+        outer_radius: f32,
+    },
+    /// Anywhere in an axis-aligned rectangle.
+    Rectangle {
+        /// The width of the rectangle.
+        width: f32,
+        /// The height of the rectangle.
+        height: f32,
+    },
+}
+
+/// A single particle of an [`Emitter`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    /// The position of the particle.
+    pub position: mint::Point2<f32>,
+    /// The velocity of the particle in units per second, before the emitter's
+    /// velocity curve is applied.
+    pub velocity: mint::Vector2<f32>,
+    /// The rotation of the particle in radians.
+    pub rotation: f32,
+    /// How fast the particle rotates, in radians per second.
+    pub spin: f32,
+    /// How long the particle has been alive, in seconds.
+    pub age: f32,
+    /// How long the particle lives in total, in seconds.
+    pub lifetime: f32,
+}
+
+impl Particle {
+    /// Returns how far the particle is through its life, from `0.0` to `1.0`.
+    #[inline]
+    pub fn progress(&self) -> f32 {
+        (self.age / self.lifetime).min(1.)
+    }
+}
+
+/// Spawns particles and simulates them over time.
+///
+/// An emitter spawns [`Emitter::rate`] particles per second while it's emitting, plus any
+/// number at once with [`Emitter::burst`]. Every particle gets a random position in the
+/// emitter's [`EmitterShape`], and a random lifetime, speed, direction and spin from the
+/// configured ranges. Color, size and speed then change over the life of a particle
+/// according to [`Curve`]s.
+///
+/// Random values come from a generator seeded with [`Emitter::seed`], so emitters with the
+/// same seed and settings behave exactly the same for the same sequence of updates.
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    position: mint::Point2<f32>,
+    emitting: bool,
+    rate: f32,
+    max_particles: usize,
+    shape: EmitterShape,
+    lifetime: (f32, f32),
+    speed: (f32, f32),
+    direction: f32,
+    spread: f32,
+    spin: (f32, f32),
+    gravity: mint::Vector2<f32>,
+    damping: f32,
+    color: Curve<Color>,
+    size: Curve<f32>,
+    velocity: Curve<f32>,
+    rng: Rng,
+    spawn_debt: f32,
+    particles: Vec<Particle>,
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Emitter {
+    /// Creates an emitter at the origin, spawning 10 particles per second that live for one
+    /// second, in all directions at 100 units per second, white and of size 1.
+    ///
+    /// The emitter is randomly seeded; see [`Emitter::seed`] to make it deterministic.
+    pub fn new() -> Self {
+        Emitter {
+            position: [0., 0.].into(),
+            emitting: true,
+            rate: 10.,
+            max_particles: 1000,
+            shape: EmitterShape::Point,
+            lifetime: (1., 1.),
+            speed: (100., 100.),
+            direction: 0.,
+            spread: std::f32::consts::TAU,
+            spin: (0., 0.),
+            gravity: [0., 0.].into(),
+            damping: 0.,
+            color: Curve::constant(Color::WHITE),
+            size: Curve::constant(1.),
+            velocity: Curve::constant(1.),
+            rng: Rng::from_entropy(),
+            spawn_debt: 0.,
+            particles: Vec::new(),
+        }
+    }
+
+    /// Sets the seed of the random number generator, which makes the emitter deterministic.
+    #[must_use]
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Sets where particles are spawned.
+    #[must_use]
+    pub fn with_position(mut self, position: impl Into<mint::Point2<f32>>) -> Self {
+        self.position = position.into();
+        self
+    }
+
+    /// Sets how many particles are spawned per second while emitting.
+    #[must_use]
+    pub fn rate(mut self, rate: f32) -> Self {
+        self.rate = rate.max(0.);
+        self
+    }
+
+    /// Sets the maximum number of particles alive at once. No new particles are spawned
+    /// while it's reached.
+    #[must_use]
+    pub fn max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    /// Sets the area particles are spawned in.
+    #[must_use]
+    pub fn shape(mut self, shape: EmitterShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Sets the range of the lifetime of particles, in seconds.
+    #[must_use]
+    pub fn lifetime(mut self, lifetime: RangeInclusive<f32>) -> Self {
+        self.lifetime = (*lifetime.start(), *lifetime.end());
+        self
+    }
+
+    /// Sets the range of the initial speed of particles, in units per second.
+    #[must_use]
+    pub fn speed(mut self, speed: RangeInclusive<f32>) -> Self {
+        self.speed = (*speed.start(), *speed.end());
+        self
+    }
+
+    /// Sets the direction particles move in initially, as an angle in radians with `0.0`
+    /// pointing right, and the angle of the cone around it they are spread across.
+    #[must_use]
+    pub fn direction(mut self, direction: f32, spread: f32) -> Self {
+        self.direction = direction;
+        self.spread = spread;
+        self
+    }
+
+    /// Sets the range of how fast particles rotate, in radians per second.
+    #[must_use]
+    pub fn spin(mut self, spin: RangeInclusive<f32>) -> Self {
+        self.spin = (*spin.start(), *spin.end());
+        self
+    }
+
+    /// Sets the acceleration applied to all particles, in units per second squared.
+    #[must_use]
+    pub fn gravity(mut self, gravity: impl Into<mint::Vector2<f32>>) -> Self {
+        self.gravity = gravity.into();
+        self
+    }
+
+    /// Sets how quickly particles slow down; their velocity is multiplied by
+    /// `exp(-damping)` every second.
+    #[must_use]
+    pub fn damping(mut self, damping: f32) -> Self {
+        self.damping = damping.max(0.);
+        self
+    }
+
+    /// Sets the color of particles over their life.
+    #[must_use]
+    pub fn color(mut self, color: impl Into<Curve<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Sets the size of particles over their life, as a scale of the image they're drawn with.
+    #[must_use]
+    pub fn size(mut self, size: impl Into<Curve<f32>>) -> Self {
+        self.size = size.into();
+        self
+    }
+
+    /// Sets the factor the velocity of particles is multiplied with over their life.
+    #[must_use]
+    pub fn velocity(mut self, velocity: impl Into<Curve<f32>>) -> Self {
+        self.velocity = velocity.into();
+        self
+    }
+
+    /// Moves the emitter. Particles spawned already aren't moved along.
+    pub fn set_position(&mut self, position: impl Into<mint::Point2<f32>>) {
+        self.position = position.into();
+    }
+
+    /// Returns where particles are spawned.
+    #[inline]
+    pub fn position(&self) -> mint::Point2<f32> {
+        self.position
+    }
+
+    /// Starts or stops spawning particles at [`Emitter::rate`]. Alive particles
+    /// keep being simulated either way.
+    pub fn set_emitting(&mut self, emitting: bool) {
+        self.emitting = emitting;
+        self.spawn_debt = 0.;
+    }
+
+    /// Returns whether the emitter spawns particles at [`Emitter::rate`].
+    #[inline]
+    pub fn is_emitting(&self) -> bool {
+        self.emitting
+    }
+
+    /// Returns whether the emitter stopped emitting and all its particles died.
+    pub fn is_finished(&self) -> bool {
+        !self.emitting && self.particles.is_empty()
+    }
+
+    /// Returns the alive particles.
+    #[inline]
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Kills all particles.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Spawns `count` particles at once, up to the maximum number of particles.
+    pub fn burst(&mut self, count: usize) {
+        let count = count.min(self.max_particles.saturating_sub(self.particles.len()));
+        self.particles.reserve(count);
+        for _ in 0..count {
+            let particle = self.spawn();
+            self.particles.push(particle);
+        }
+    }
+
+    /// Advances the simulation, spawning new particles, moving alive ones and
+    /// removing those that died.
+    pub fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        let gravity = Vec2::from(self.gravity) * dt;
+        let damping = (-self.damping * dt).exp();
+
+        let mut i = 0;
+        while i < self.particles.len() {
+            let particle = &mut self.particles[i];
+            particle.age += dt;
+            if particle.age >= particle.lifetime {
+                let _ = self.particles.swap_remove(i);
+                continue;
+            }
+
+            let velocity = (Vec2::from(particle.velocity) + gravity) * damping;
+            let factor = self.velocity.sample(particle.progress());
+            particle.velocity = velocity.into();
+            particle.position = (Vec2::from(particle.position) + velocity * factor * dt).into();
+            particle.rotation += particle.spin * dt;
+            i += 1;
+        }
+
+        if self.emitting {
+            self.spawn_debt += self.rate * dt;
+            let count = self.spawn_debt.floor();
+            self.spawn_debt -= count;
+            self.burst(count as usize);
+        }
+    }
+
+    /// Returns the parameters to draw `particle` with.
+    pub fn draw_param(&self, particle: &Particle) -> DrawParam {
+        let progress = particle.progress();
+        let size = self.size.sample(progress);
+        DrawParam::new()
+            .dest(particle.position)
+            .offset([0.5, 0.5])
+            .rotation(particle.rotation)
+            .scale([size, size])
+            .color(self.color.sample(progress))
+    }
+
+    fn spawn(&mut self) -> Particle {
+        let rng = &mut self.rng;
+        let mut random = |(min, max): (f32, f32)| min + (max - min) * rng.rand_float();
+
+        let offset = match self.shape {
+            EmitterShape::Point => Vec2::ZERO,
+            EmitterShape::Circle { radius } => {
+                // sqrt makes the distribution uniform across the area
+                let angle = random((0., std::f32::consts::TAU));
+                Vec2::from_angle(angle) * radius * random((0., 1.)).sqrt()
+            }
+            EmitterShape::Ring {
+                inner_radius,
+                outer_radius,
+            } => {
+                let angle = random((0., std::f32::consts::TAU));
+                let radius = random((inner_radius * inner_radius, outer_radius * outer_radius));
+                Vec2::from_angle(angle) * radius.sqrt()
+            }
+            EmitterShape::Rectangle { width, height } => Vec2::new(
+                random((-width / 2., width / 2.)),
+                random((-height / 2., height / 2.)),
+            ),
+        };
+
+        let angle = self.direction + random((-self.spread / 2., self.spread / 2.));
+        let speed = random(self.speed);
+        Particle {
+            position: (Vec2::from(self.position) + offset).into(),
+            velocity: (Vec2::from_angle(angle) * speed).into(),
+            rotation: 0.,
+            spin: random(self.spin),
+            age: 0.,
+            lifetime: random(self.lifetime).max(f32::EPSILON),
+        }
+    }
+}
+
+/// Draws the particles of a number of [`Emitter`]s with a single image, in one draw call.
+///
+/// Call [`ParticleSystem::update`] once per frame; the particles are drawn as of
+/// the last update.
+#[derive(Debug)]
+pub struct ParticleSystem {
+    emitters: Vec<Emitter>,
+    instances: InstanceArray,
+}
+
+impl ParticleSystem {
+    /// Creates a particle system without emitters, drawing particles with `image`.
+    ///
+    /// If `image` is `None`, particles are drawn as 1x1 white squares, so
+    /// [`Emitter::size`] is in pixels.
+    pub fn new(gfx: &impl Has<GraphicsContext>, image: impl Into<Option<Image>>) -> Self {
+        ParticleSystem {
+            emitters: Vec::new(),
+            instances: InstanceArray::new(gfx, image),
+        }
+    }
+
+    /// Adds an emitter, returning its index.
+    pub fn add_emitter(&mut self, emitter: Emitter) -> usize {
+        self.emitters.push(emitter);
+        self.emitters.len() - 1
+    }
+
+    /// Removes and returns the emitter at `index`, shifting all emitters after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_emitter(&mut self, index: usize) -> Emitter {
+        self.emitters.remove(index)
+    }
+
+    /// Returns all emitters.
+    #[inline]
+    pub fn emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+
+    /// Returns all emitters, e.g. to move them or trigger bursts.
+    #[inline]
+    pub fn emitters_mut(&mut self) -> &mut [Emitter] {
+        &mut self.emitters
+    }
+
+    /// Returns the total number of alive particles.
+    pub fn particle_count(&self) -> usize {
+        self.emitters.iter().map(|e| e.particles().len()).sum()
+    }
+
+    /// Returns whether all emitters are finished.
+    pub fn is_finished(&self) -> bool {
+        self.emitters.iter().all(Emitter::is_finished)
+    }
+
+    /// Removes all emitters that are finished.
+    pub fn remove_finished(&mut self) {
+        self.emitters.retain(|emitter| !emitter.is_finished());
+    }
+
+    /// Updates all emitters and prepares their particles for drawing.
+    pub fn update(&mut self, dt: Duration) {
+        for emitter in &mut self.emitters {
+            emitter.update(dt);
+        }
+        self.instances.set(self.emitters.iter().flat_map(|emitter| {
+            emitter
+                .particles()
+                .iter()
+                .map(|particle| emitter.draw_param(particle))
+        }));
+    }
+}
+
+impl Drawable for ParticleSystem {
+    fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
+        self.instances.draw(canvas, param);
+    }
+
+    fn dimensions(&self, gfx: &impl Has<GraphicsContext>) -> Rect {
+        self.instances.dimensions(gfx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emitter(seed: u64) -> Emitter {
+        Emitter::new()
+            .seed(seed)
+            .rate(100.)
+            .shape(EmitterShape::Circle { radius: 10. })
+            .lifetime(0.5..=1.)
+            .speed(10.0..=20.)
+            .gravity([0., 9.8])
+    }
+
+    #[test]
+    fn headless_test_particles_deterministic() {
+        let (mut a, mut b) = (emitter(42), emitter(42));
+        for _ in 0..30 {
+            a.update(Duration::from_millis(16));
+            b.update(Duration::from_millis(16));
+        }
+        assert!(!a.particles().is_empty());
+        assert_eq!(a.particles(), b.particles());
+
+        let mut c = emitter(43);
+        for _ in 0..30 {
+            c.update(Duration::from_millis(16));
+        }
+        assert_ne!(a.particles(), c.particles());
+    }
+
+    #[test]
+    fn headless_test_particles_lifetime_and_limits() {
+        let mut emitter = emitter(1).rate(0.).max_particles(5);
+        emitter.burst(10);
+        assert_eq!(emitter.particles().len(), 5);
+        assert!(emitter
+            .particles()
+            .iter()
+            .all(|p| Vec2::from(p.position).length() <= 10.));
+
+        emitter.set_emitting(false);
+        emitter.update(Duration::from_millis(400));
+        assert_eq!(emitter.particles().len(), 5);
+        emitter.update(Duration::from_millis(700));
+        assert!(emitter.is_finished());
+    }
+
+    #[test]
+    fn headless_test_particles_curve() {
+        let curve = Curve::linear(0., 10.).key(0.5, 2.);
+        assert_eq!(curve.sample(-1.), 0.);
+        assert_eq!(curve.sample(0.25), 1.);
+        assert_eq!(curve.sample(0.75), 6.);
+        assert_eq!(curve.sample(2.), 10.);
+        assert_eq!(Curve::constant(3.).sample(0.5), 3.);
+
+        let color = Curve::linear(Color::BLACK, Color::WHITE).sample(0.5);
+        assert_eq!(color, Color::new(0.5, 0.5, 0.5, 1.));
+    }
+}
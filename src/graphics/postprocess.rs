@@ -0,0 +1,564 @@
+//! Full screen post-processing: a [`PostProcess`] renders the scene into an offscreen
+//! image and then runs it through an ordered chain of [`PostEffect`]s, ping-ponging
+//! between two images, with the last pass drawing onto the frame.
+//!
+//! ```rust,no_run
+//! # use ggez::{graphics::{postprocess::*, Color}, Context, GameResult};
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! // once, e.g. in `MainState::new`
+//! let mut post = PostProcess::new(ctx)
+//!     .effect(PostEffect::bloom(ctx, Bloom::default())?)
+//!     .effect(PostEffect::vignette(ctx, Vignette::default())?);
+//!
+//! // in `draw`
+//! let mut canvas = post.begin(ctx, Color::BLACK);
+//! // ...draw the scene as usual...
+//! post.finish(ctx, canvas)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Custom effects are plain fragment shaders, written like any other ggez shader: the
+//! previous pass' result is the image being drawn (group 1) and the effect's own
+//! [`ShaderParams`] are bound to group 3.
+
+use std::{any::Any, borrow::Cow, fmt};
+
+use crevice::std140::AsStd140;
+use glam::Vec4;
+
+use crate::{
+    context::{Has, HasMut},
+    GameError, GameResult,
+};
+
+use super::{
+    BlendMode, Canvas, Color, DrawParam, GraphicsContext, LinearColor, Sampler, ScreenImage,
+    Shader, ShaderBuilder, ShaderParams, ShaderParamsBuilder,
+};
+
+/// Settings of the built-in gaussian blur, see [`PostEffect::blur`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blur {
+    /// Blur radius, in pixels.
+    pub radius: f32,
+}
+
+impl Default for Blur {
+    fn default() -> Self {
+        Blur { radius: 4. }
+    }
+}
+
+/// Settings of the built-in bloom, see [`PostEffect::bloom`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    /// Luminance above which pixels start to glow, from 0 to 1.
+    pub threshold: f32,
+    /// How strongly the glow is added on top of the image.
+    pub intensity: f32,
+    /// How far the glow spreads, in pixels.
+    pub radius: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom {
+            threshold: 0.7,
+            intensity: 1.,
+            radius: 8.,
+        }
+    }
+}
+
+/// Settings of the built-in color grading, see [`PostEffect::color_grading`].
+///
+/// The defaults leave the image unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorGrading {
+    /// Exposure adjustment, in stops.
+    pub exposure: f32,
+    /// Contrast multiplier around middle grey.
+    pub contrast: f32,
+    /// Saturation multiplier; 0 is greyscale.
+    pub saturation: f32,
+    /// Gamma correction applied after the other adjustments.
+    pub gamma: f32,
+    /// Color the result is multiplied with. Alpha is ignored.
+    pub tint: Color,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        ColorGrading {
+            exposure: 0.,
+            contrast: 1.,
+            saturation: 1.,
+            gamma: 1.,
+            tint: Color::WHITE,
+        }
+    }
+}
+
+/// Settings of the built-in CRT monitor look, see [`PostEffect::crt`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crt {
+    /// How much the picture bulges outwards; 0 is flat.
+    pub curvature: f32,
+    /// Darkness of the gaps between scanlines, from 0 to 1.
+    pub scanlines: f32,
+    /// Horizontal offset of the red and blue channels, in pixels.
+    pub aberration: f32,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Crt {
+            curvature: 0.1,
+            scanlines: 0.3,
+            aberration: 1.,
+        }
+    }
+}
+
+/// Settings of the built-in vignette, see [`PostEffect::vignette`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    /// Distance from the center at which the vignette reaches full strength;
+    /// 1 is the corners.
+    pub radius: f32,
+    /// Width of the fade towards `radius`.
+    pub softness: f32,
+    /// Strength of the vignette, from 0 to 1.
+    pub intensity: f32,
+    /// Color the edges fade to; its alpha scales the intensity.
+    pub color: Color,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Vignette {
+            radius: 1.,
+            softness: 0.6,
+            intensity: 0.8,
+            color: Color::BLACK,
+        }
+    }
+}
+
+#[derive(AsStd140)]
+struct BlurUniforms {
+    radius: f32,
+}
+
+impl From<&Blur> for BlurUniforms {
+    fn from(blur: &Blur) -> Self {
+        BlurUniforms {
+            radius: blur.radius,
+        }
+    }
+}
+
+#[derive(AsStd140)]
+struct BloomUniforms {
+    threshold: f32,
+    intensity: f32,
+    radius: f32,
+}
+
+impl From<&Bloom> for BloomUniforms {
+    fn from(bloom: &Bloom) -> Self {
+        BloomUniforms {
+            threshold: bloom.threshold,
+            intensity: bloom.intensity,
+            radius: bloom.radius,
+        }
+    }
+}
+
+#[derive(AsStd140)]
+struct ColorGradingUniforms {
+    exposure: f32,
+    contrast: f32,
+    saturation: f32,
+    gamma: f32,
+    tint: Vec4,
+}
+
+impl From<&ColorGrading> for ColorGradingUniforms {
+    fn from(grading: &ColorGrading) -> Self {
+        ColorGradingUniforms {
+            exposure: grading.exposure,
+            contrast: grading.contrast,
+            saturation: grading.saturation,
+            gamma: grading.gamma,
+            tint: Vec4::from_array(LinearColor::from(grading.tint).into()),
+        }
+    }
+}
+
+#[derive(AsStd140)]
+struct CrtUniforms {
+    curvature: f32,
+    scanlines: f32,
+    aberration: f32,
+}
+
+impl From<&Crt> for CrtUniforms {
+    fn from(crt: &Crt) -> Self {
+        CrtUniforms {
+            curvature: crt.curvature,
+            scanlines: crt.scanlines,
+            aberration: crt.aberration,
+        }
+    }
+}
+
+#[derive(AsStd140)]
+struct VignetteUniforms {
+    color: Vec4,
+    radius: f32,
+    softness: f32,
+    intensity: f32,
+}
+
+impl From<&Vignette> for VignetteUniforms {
+    fn from(vignette: &Vignette) -> Self {
+        VignetteUniforms {
+            color: Vec4::from_array(LinearColor::from(vignette.color).into()),
+            radius: vignette.radius,
+            softness: vignette.softness,
+            intensity: vignette.intensity,
+        }
+    }
+}
+
+const BLUR: &str = include_str!("shader/post_blur.wgsl");
+const BLUR_HORIZONTAL: &str = "const DIRECTION: vec2<f32> = vec2<f32>(1.0, 0.0);";
+const BLUR_VERTICAL: &str = "const DIRECTION: vec2<f32> = vec2<f32>(0.0, 1.0);";
+
+fn blur_source(vertical: bool) -> Cow<'static, str> {
+    if vertical {
+        Cow::Owned(BLUR.replace(BLUR_HORIZONTAL, BLUR_VERTICAL))
+    } else {
+        Cow::Borrowed(BLUR)
+    }
+}
+
+/// Type-erased [`ShaderParams`] of a single pass.
+trait PassParams {
+    fn apply(&self, canvas: &mut Canvas);
+    /// Returns whether `uniforms` were of the right type.
+    fn set_uniforms(&mut self, gfx: &mut GraphicsContext, uniforms: &dyn Any) -> bool;
+}
+
+impl<U: AsStd140 + 'static> PassParams for ShaderParams<U> {
+    fn apply(&self, canvas: &mut Canvas) {
+        canvas.set_shader_params(self);
+    }
+
+    fn set_uniforms(&mut self, gfx: &mut GraphicsContext, uniforms: &dyn Any) -> bool {
+        let Some(uniforms) = uniforms.downcast_ref::<U>() else {
+            return false;
+        };
+        ShaderParams::set_uniforms(self, gfx, uniforms);
+        true
+    }
+}
+
+/// Params of the built-in effects, updated through their public settings struct.
+struct SettingsParams<S, U: AsStd140> {
+    params: ShaderParams<U>,
+    convert: fn(&S) -> U,
+}
+
+impl<S: 'static, U: AsStd140 + 'static> PassParams for SettingsParams<S, U> {
+    fn apply(&self, canvas: &mut Canvas) {
+        canvas.set_shader_params(&self.params);
+    }
+
+    fn set_uniforms(&mut self, gfx: &mut GraphicsContext, uniforms: &dyn Any) -> bool {
+        let Some(settings) = uniforms.downcast_ref::<S>() else {
+            return false;
+        };
+        self.params.set_uniforms(gfx, &(self.convert)(settings));
+        true
+    }
+}
+
+struct Pass {
+    shader: Shader,
+    params: Option<Box<dyn PassParams>>,
+}
+
+impl fmt::Debug for Pass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pass")
+            .field("shader", &self.shader)
+            .field("params", &self.params.is_some())
+            .finish()
+    }
+}
+
+/// A post-processing effect, made of one or more full screen passes that are applied in order.
+///
+/// Use one of the built-in effects, or bring your own fragment shader with
+/// [`PostEffect::new`] and [`PostEffect::with_params`].
+#[derive(Debug)]
+pub struct PostEffect {
+    passes: Vec<Pass>,
+    enabled: bool,
+}
+
+impl PostEffect {
+    /// Creates a single pass effect from a fragment shader without parameters.
+    pub fn new(shader: Shader) -> Self {
+        PostEffect {
+            passes: vec![Pass {
+                shader,
+                params: None,
+            }],
+            enabled: true,
+        }
+    }
+
+    /// Creates a single pass effect from a fragment shader and its parameters.
+    pub fn with_params<U: AsStd140 + 'static>(shader: Shader, params: ShaderParams<U>) -> Self {
+        PostEffect {
+            passes: vec![Pass {
+                shader,
+                params: Some(Box::new(params)),
+            }],
+            enabled: true,
+        }
+    }
+
+    /// Appends the passes of another effect to this one, e.g. to build multi-pass effects.
+    #[must_use]
+    pub fn then(mut self, other: PostEffect) -> Self {
+        self.passes.extend(other.passes);
+        self
+    }
+
+    fn builtin<S: 'static, U: AsStd140 + for<'a> From<&'a S> + 'static>(
+        gfx: &mut impl HasMut<GraphicsContext>,
+        source: &str,
+        settings: &S,
+    ) -> GameResult<Self> {
+        let gfx = gfx.retrieve_mut();
+        let shader = ShaderBuilder::new().fragment_code(source).build(gfx)?;
+        let params = ShaderParamsBuilder::new(&U::from(settings)).build(gfx);
+        Ok(PostEffect {
+            passes: vec![Pass {
+                shader,
+                params: Some(Box::new(SettingsParams {
+                    params,
+                    convert: |settings| U::from(settings),
+                })),
+            }],
+            enabled: true,
+        })
+    }
+
+    /// Separable gaussian blur, applied as a horizontal and a vertical pass.
+    pub fn blur(gfx: &mut impl HasMut<GraphicsContext>, settings: Blur) -> GameResult<Self> {
+        let horizontal = Self::builtin::<_, BlurUniforms>(gfx, &blur_source(false), &settings)?;
+        let vertical = Self::builtin::<_, BlurUniforms>(gfx, &blur_source(true), &settings)?;
+        Ok(horizontal.then(vertical))
+    }
+
+    /// Makes bright parts of the image glow.
+    pub fn bloom(gfx: &mut impl HasMut<GraphicsContext>, settings: Bloom) -> GameResult<Self> {
+        Self::builtin::<_, BloomUniforms>(gfx, include_str!("shader/post_bloom.wgsl"), &settings)
+    }
+
+    /// Exposure, contrast, saturation, gamma and tint adjustments.
+    pub fn color_grading(
+        gfx: &mut impl HasMut<GraphicsContext>,
+        settings: ColorGrading,
+    ) -> GameResult<Self> {
+        Self::builtin::<_, ColorGradingUniforms>(
+            gfx,
+            include_str!("shader/post_color_grading.wgsl"),
+            &settings,
+        )
+    }
+
+    /// Screen curvature, scanlines and chromatic aberration of an old CRT monitor.
+    pub fn crt(gfx: &mut impl HasMut<GraphicsContext>, settings: Crt) -> GameResult<Self> {
+        Self::builtin::<_, CrtUniforms>(gfx, include_str!("shader/post_crt.wgsl"), &settings)
+    }
+
+    /// Darkens (or tints) the edges of the screen.
+    pub fn vignette(
+        gfx: &mut impl HasMut<GraphicsContext>,
+        settings: Vignette,
+    ) -> GameResult<Self> {
+        Self::builtin::<_, VignetteUniforms>(
+            gfx,
+            include_str!("shader/post_vignette.wgsl"),
+            &settings,
+        )
+    }
+
+    /// Updates the uniforms of every pass whose parameters are of type `U`.
+    ///
+    /// For the built-in effects, pass their settings struct, e.g. [`Bloom`].
+    /// Passes with parameters of a different type are left untouched, and it's an error
+    /// if no pass has parameters of type `U`.
+    pub fn set_uniforms<U: 'static>(
+        &mut self,
+        gfx: &mut impl HasMut<GraphicsContext>,
+        uniforms: &U,
+    ) -> GameResult {
+        let gfx = gfx.retrieve_mut();
+        let mut matched = false;
+        for params in self
+            .passes
+            .iter_mut()
+            .filter_map(|pass| pass.params.as_mut())
+        {
+            matched |= params.set_uniforms(gfx, uniforms);
+        }
+
+        if matched {
+            Ok(())
+        } else {
+            Err(GameError::RenderError(format!(
+                "no pass of this post effect takes uniforms of type {}",
+                std::any::type_name::<U>()
+            )))
+        }
+    }
+
+    /// Enables or disables the effect. Disabled effects are skipped entirely.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Returns whether the effect is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the number of full screen passes this effect takes.
+    pub fn passes(&self) -> usize {
+        self.passes.len()
+    }
+}
+
+/// An ordered chain of [`PostEffect`]s applied to everything drawn between
+/// [`PostProcess::begin`] and [`PostProcess::finish`].
+#[derive(Debug)]
+pub struct PostProcess {
+    images: [ScreenImage; 2],
+    effects: Vec<PostEffect>,
+}
+
+impl PostProcess {
+    /// Creates an empty chain, with offscreen images matching the frame's size and format.
+    pub fn new(gfx: &impl Has<GraphicsContext>) -> Self {
+        PostProcess {
+            images: [
+                ScreenImage::new_raw(gfx, None, 1., 1., 1),
+                ScreenImage::new_raw(gfx, None, 1., 1., 1),
+            ],
+            effects: Vec::new(),
+        }
+    }
+
+    /// Appends an effect to the end of the chain.
+    #[must_use]
+    pub fn effect(mut self, effect: PostEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    /// Appends an effect to the end of the chain.
+    pub fn push(&mut self, effect: PostEffect) {
+        self.effects.push(effect);
+    }
+
+    /// Returns the effects, in the order they're applied.
+    pub fn effects(&self) -> &[PostEffect] {
+        &self.effects
+    }
+
+    /// Returns the effects mutably, e.g. to update, reorder or remove them.
+    pub fn effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.effects
+    }
+
+    /// Returns a canvas for drawing the scene that the effects will be applied to.
+    pub fn begin(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        clear: impl Into<Option<Color>>,
+    ) -> Canvas {
+        Canvas::from_screen_image(gfx, &mut self.images[0], clear)
+    }
+
+    /// Finishes the scene canvas returned by [`PostProcess::begin`], then applies all
+    /// enabled effects and draws the result onto the frame.
+    ///
+    /// Without any enabled effects, the scene is copied to the frame as is.
+    pub fn finish(&mut self, gfx: &mut impl HasMut<GraphicsContext>, canvas: Canvas) -> GameResult {
+        let gfx = gfx.retrieve_mut();
+        canvas.finish(gfx)?;
+
+        let passes: Vec<&Pass> = self
+            .effects
+            .iter()
+            .filter(|effect| effect.enabled)
+            .flat_map(|effect| &effect.passes)
+            .collect();
+
+        for (index, (source, target)) in ping_pong(passes.len().max(1)).enumerate() {
+            let image = self.images[source].image(gfx);
+            let mut canvas = match target {
+                Some(target) => Canvas::from_screen_image(gfx, &mut self.images[target], None),
+                None => Canvas::from_frame(gfx, None),
+            };
+            if let Some(pass) = passes.get(index) {
+                canvas.set_shader(&pass.shader);
+                if let Some(params) = &pass.params {
+                    params.apply(&mut canvas);
+                }
+            }
+            canvas.set_sampler(Sampler::linear_clamp());
+            canvas.set_blend_mode(BlendMode::REPLACE);
+            canvas.draw(&image, DrawParam::default());
+            canvas.finish(gfx)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The offscreen image each of `passes` passes reads from, and the one it
+/// renders to; `None` being the frame.
+fn ping_pong(passes: usize) -> impl Iterator<Item = (usize, Option<usize>)> {
+    (0..passes).map(move |pass| (pass % 2, (pass + 1 < passes).then_some((pass + 1) % 2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_post_process_ping_pong() {
+        assert_eq!(ping_pong(1).collect::<Vec<_>>(), [(0, None)]);
+        assert_eq!(
+            ping_pong(4).collect::<Vec<_>>(),
+            [(0, Some(1)), (1, Some(0)), (0, Some(1)), (1, None)]
+        );
+    }
+
+    #[test]
+    fn headless_test_post_process_blur_directions() {
+        assert!(blur_source(false).contains(BLUR_HORIZONTAL));
+        let vertical = blur_source(true);
+        assert!(vertical.contains(BLUR_VERTICAL));
+        assert!(!vertical.contains(BLUR_HORIZONTAL));
+    }
+}
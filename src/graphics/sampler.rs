@@ -1,7 +1,14 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
 
 /// Sampler state that is used when sampling images on the GPU.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+///
+/// The mipmap settings only matter for images with more than one mip level, see e.g.
+/// [`Image::from_path`](crate::graphics::Image::from_path).
+#[derive(Debug, Clone, Copy)]
 pub struct Sampler {
     /// Clamping mode in the U (x) direction.
     pub clamp_u: ClampMode,
@@ -13,6 +20,18 @@ pub struct Sampler {
     pub mag: FilterMode,
     /// Minification (downscaling) filter.
     pub min: FilterMode,
+    /// Filter between mip levels; `Linear` together with linear `mag` and `min` gives
+    /// trilinear filtering.
+    pub mipmap: FilterMode,
+    /// Lowest (i.e. most detailed) mip level that may be sampled.
+    pub lod_min_clamp: f32,
+    /// Highest (i.e. least detailed) mip level that may be sampled.
+    pub lod_max_clamp: f32,
+    /// Maximum anisotropy, from 1 (off) to 16. Sharpens textures seen at steep angles or
+    /// scaled down unevenly.
+    ///
+    /// Only used if `mag`, `min` and `mipmap` are all `Linear`.
+    pub anisotropy: u16,
 }
 
 impl Sampler {
@@ -24,6 +43,10 @@ impl Sampler {
             clamp_w: ClampMode::Clamp,
             mag: FilterMode::Linear,
             min: FilterMode::Linear,
+            mipmap: FilterMode::Linear,
+            lod_min_clamp: 0.,
+            lod_max_clamp: 32.,
+            anisotropy: 1,
         }
     }
 
@@ -34,9 +57,66 @@ impl Sampler {
         Sampler {
             mag: FilterMode::Nearest,
             min: FilterMode::Nearest,
+            mipmap: FilterMode::Nearest,
             ..Self::linear_clamp()
         }
     }
+
+    /// Returns the sampler with the given maximum anisotropy, see [`Sampler::anisotropy`].
+    #[must_use]
+    pub fn with_anisotropy(mut self, anisotropy: u16) -> Self {
+        self.anisotropy = anisotropy;
+        self
+    }
+
+    /// Returns the sampler with mip level sampling restricted to `min..=max`.
+    #[must_use]
+    pub fn with_lod_clamp(mut self, min: f32, max: f32) -> Self {
+        self.lod_min_clamp = min;
+        self.lod_max_clamp = max;
+        self
+    }
+
+    // The LOD clamps are floats, so compare and hash them by their bits.
+    fn key(&self) -> impl Ord + Hash {
+        (
+            self.clamp_u,
+            self.clamp_v,
+            self.clamp_w,
+            self.mag,
+            self.min,
+            self.mipmap,
+            self.lod_min_clamp.to_bits(),
+            self.lod_max_clamp.to_bits(),
+            self.anisotropy,
+        )
+    }
+}
+
+impl PartialEq for Sampler {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Sampler {}
+
+impl PartialOrd for Sampler {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Sampler {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for Sampler {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
 }
 
 impl Default for Sampler {
@@ -54,11 +134,19 @@ impl From<Sampler> for wgpu::SamplerDescriptor<'_> {
             address_mode_w: sampler.clamp_w.into(),
             mag_filter: sampler.mag.into(),
             min_filter: sampler.min.into(),
-            mipmap_filter: wgpu::MipmapFilterMode::Linear,
-            lod_min_clamp: 0.0,
-            lod_max_clamp: 1.0,
+            mipmap_filter: sampler.mipmap.into(),
+            lod_min_clamp: sampler.lod_min_clamp,
+            lod_max_clamp: sampler.lod_max_clamp,
             compare: None,
-            anisotropy_clamp: 1,
+            // wgpu rejects anisotropic samplers that aren't fully linear.
+            anisotropy_clamp: if [sampler.mag, sampler.min, sampler.mipmap]
+                .iter()
+                .all(|&filter| filter == FilterMode::Linear)
+            {
+                sampler.anisotropy.clamp(1, 16)
+            } else {
+                1
+            },
             border_color: None,
         }
     }
@@ -112,6 +200,15 @@ impl From<FilterMode> for wgpu::FilterMode {
     }
 }
 
+impl From<FilterMode> for wgpu::MipmapFilterMode {
+    fn from(filter: FilterMode) -> Self {
+        match filter {
+            FilterMode::Nearest => wgpu::MipmapFilterMode::Nearest,
+            FilterMode::Linear => wgpu::MipmapFilterMode::Linear,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct SamplerCache {
     cache: HashMap<Sampler, wgpu::Sampler>,
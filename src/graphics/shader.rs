@@ -1,6 +1,9 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, path::PathBuf};
 
-use crate::{context::Has, Context, GameError, GameResult};
+use crate::{
+    context::{Has, HasMut},
+    GameError, GameResult, ShaderError,
+};
 
 use super::{
     context::GraphicsContext,
@@ -85,27 +88,47 @@ impl<'a> ShaderBuilder<'a> {
     }
 
     /// Create a Shader from the builder.
+    ///
+    /// The WGSL code is parsed and validated first, so mistakes in it are returned as a
+    /// [`GameError::ShaderCompileError`] pointing at where they are, rather than panicking.
     pub fn build(self, gfx: &impl Has<GraphicsContext>) -> GameResult<Shader> {
         let gfx = gfx.retrieve();
-        let load = |s: &str| {
-            Some(
-                gfx.wgpu
-                    .device
-                    .create_shader_module(wgpu::ShaderModuleDescriptor {
-                        label: None,
-                        source: wgpu::ShaderSource::Wgsl(s.into()),
-                    }),
-            )
+        let load = |s: &str, path: Option<&str>| -> GameResult<Option<wgpu::ShaderModule>> {
+            check_wgsl(s, path).map_err(GameError::ShaderCompileError)?;
+
+            // Validation by the device can still fail, e.g. on features it doesn't support.
+            let scope = gfx
+                .wgpu
+                .device
+                .push_error_scope(wgpu::ErrorFilter::Validation);
+            let module = gfx
+                .wgpu
+                .device
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: path,
+                    source: wgpu::ShaderSource::Wgsl(s.into()),
+                });
+            match pollster::block_on(scope.pop()) {
+                Some(e) => Err(GameError::ShaderCompileError(ShaderError {
+                    path: path.map(PathBuf::from),
+                    message: e.to_string(),
+                    location: None,
+                    snippet: None,
+                    report: String::new(),
+                })),
+                None => Ok(Some(module)),
+            }
         };
         let load_resource = |path: &str| -> GameResult<Option<wgpu::ShaderModule>> {
             let encoded = gfx.fs.read(path)?;
-            Ok(load(
+            load(
                 &String::from_utf8(encoded).map_err(GameError::ShaderEncodingError)?,
-            ))
+                Some(path),
+            )
         };
         let load_any = |source| -> GameResult<Option<wgpu::ShaderModule>> {
             Ok(match source {
-                ShaderSource::Code(source) => load(source),
+                ShaderSource::Code(source) => load(source, None)?,
                 ShaderSource::Path(source) => load_resource(source)?,
                 ShaderSource::None => None,
             })
@@ -125,6 +148,48 @@ impl<'a> ShaderBuilder<'a> {
     }
 }
 
+/// Parses and validates WGSL code with naga, describing the first error found.
+fn check_wgsl(source: &str, path: Option<&str>) -> Result<(), ShaderError> {
+    use wgpu::naga::{front::wgsl, valid};
+
+    let report_path = path.unwrap_or("wgsl");
+    let error = |message: String, location: Option<wgpu::naga::SourceLocation>, report| {
+        let location = location.map(|l| (l.line_number, l.line_position));
+        ShaderError {
+            path: path.map(PathBuf::from),
+            message,
+            location,
+            snippet: location.and_then(|(line, _)| {
+                source
+                    .lines()
+                    .nth(line as usize - 1)
+                    .map(|line| line.trim_end().to_string())
+            }),
+            report,
+        }
+    };
+
+    let module = wgsl::parse_str(source).map_err(|e| {
+        error(
+            e.message().to_string(),
+            e.location(source),
+            e.emit_to_string_with_path(source, report_path),
+        )
+    })?;
+
+    let _ = valid::Validator::new(valid::ValidationFlags::all(), valid::Capabilities::all())
+        .validate(&module)
+        .map_err(|e| {
+            error(
+                e.as_inner().to_string(),
+                e.location(source),
+                e.emit_to_string_with_path(source, report_path),
+            )
+        })?;
+
+    Ok(())
+}
+
 impl Default for ShaderBuilder<'_> {
     fn default() -> Self {
         Self::new()
@@ -257,24 +322,23 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
     }
 
     /// Produce a [`ShaderParams`] from the builder.
-    pub fn build(self, ctx: &mut Context) -> ShaderParams<Uniforms> {
-        let images = self.images.iter().map(|image| image.view.clone()).collect();
+    pub fn build(self, gfx: &mut impl HasMut<GraphicsContext>) -> ShaderParams<Uniforms> {
+        let gfx = gfx.retrieve_mut();
+        let images = self
+            .images
+            .iter()
+            .map(|image| (image.view.clone(), image.dimension.into()))
+            .collect();
         let samplers = self
             .samplers
             .iter()
-            .map(|&sampler| ctx.gfx.sampler_cache.get(&ctx.gfx.wgpu.device, sampler))
+            .map(|&sampler| gfx.sampler_cache.get(&gfx.wgpu.device, sampler))
             .collect();
 
         let mut params = ShaderParams {
             uniform_arena: GrowingBufferArena::new(
-                &ctx.gfx.wgpu.device,
-                u64::from(
-                    ctx.gfx
-                        .wgpu
-                        .device
-                        .limits()
-                        .min_uniform_buffer_offset_alignment,
-                ),
+                &gfx.wgpu.device,
+                u64::from(gfx.wgpu.device.limits().min_uniform_buffer_offset_alignment),
                 wgpu::BufferDescriptor {
                     label: None,
                     size: ShaderParams::<Uniforms>::UPDATES_PER_ARENA
@@ -289,10 +353,10 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
             images,
             samplers,
             images_vs_visible: self.images_vs_visible,
-            last_tick: 0,
+            last_frame: gfx.frames,
             _marker: PhantomData,
         };
-        params.set_uniforms(ctx, self.uniforms);
+        params.set_uniforms(gfx, self.uniforms);
         params
     }
 }
@@ -318,6 +382,9 @@ impl<'a, Uniforms: AsStd140> ShaderParamsBuilder<'a, Uniforms> {
 /// @group(3) @binding(3)
 /// var sampler1: sampler;
 /// ```
+///
+/// Array and cube images (see [`Image::array_from_pixels`] and [`Image::cube_from_pixels`]) are
+/// bound as `texture_2d_array<f32>` and `texture_cube<f32>` respectively.
 #[derive(Debug)]
 pub struct ShaderParams<Uniforms: AsStd140> {
     uniform_arena: GrowingBufferArena,
@@ -325,10 +392,10 @@ pub struct ShaderParams<Uniforms: AsStd140> {
     pub(crate) layout: Option<wgpu::BindGroupLayout>,
     pub(crate) bind_group: Option<wgpu::BindGroup>,
     pub(crate) buffer_offset: u32,
-    images: Vec<wgpu::TextureView>,
+    images: Vec<(wgpu::TextureView, wgpu::TextureViewDimension)>,
     samplers: Vec<wgpu::Sampler>,
     images_vs_visible: bool,
-    last_tick: usize,
+    last_frame: usize,
     _marker: PhantomData<Uniforms>,
 }
 
@@ -341,19 +408,19 @@ impl<Uniforms: AsStd140> ShaderParams<Uniforms> {
     /// Updates the uniform data.
     ///
     /// When called, [`Canvas::set_shader_params`] (or [`Canvas::set_text_shader_params`]) **needs to be called again** for the new uniforms to take effect.
-    pub fn set_uniforms(&mut self, ctx: &mut Context, uniforms: &Uniforms) {
-        if ctx.time.ticks() != self.last_tick {
+    pub fn set_uniforms(&mut self, gfx: &mut impl HasMut<GraphicsContext>, uniforms: &Uniforms) {
+        let gfx = gfx.retrieve_mut();
+        // Buffers written before the last frame was submitted are free to be reused.
+        if gfx.frames != self.last_frame {
             self.uniform_arena.free();
-            self.last_tick = ctx.time.ticks();
+            self.last_frame = gfx.frames;
         }
         let alloc = self
             .uniform_arena
-            .allocate(&ctx.gfx.wgpu.device, Uniforms::std140_size_static() as u64);
-        ctx.gfx.wgpu.queue.write_buffer(
-            &alloc.buffer,
-            alloc.offset,
-            uniforms.as_std140().as_bytes(),
-        );
+            .allocate(&gfx.wgpu.device, Uniforms::std140_size_static() as u64);
+        gfx.wgpu
+            .queue
+            .write_buffer(&alloc.buffer, alloc.offset, uniforms.as_std140().as_bytes());
 
         self.buffer_offset = alloc.offset as u32;
 
@@ -373,16 +440,15 @@ impl<Uniforms: AsStd140> ShaderParams<Uniforms> {
             wgpu::ShaderStages::FRAGMENT
         };
 
-        for view in &self.images {
-            builder = builder.image(view, vis);
+        for (view, dimension) in &self.images {
+            builder = builder.image_dimension(view, vis, *dimension);
         }
 
         for sampler in &self.samplers {
             builder = builder.sampler(sampler, vis);
         }
 
-        let (bind_group, layout) =
-            builder.create(&ctx.gfx.wgpu.device, &mut ctx.gfx.bind_group_cache);
+        let (bind_group, layout) = builder.create(&gfx.wgpu.device, &mut gfx.bind_group_cache);
         self.layout = Some(layout);
         self.bind_group = Some(bind_group);
     }
@@ -531,3 +597,31 @@ impl BlendMode {
         },
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_check_wgsl() {
+        let valid =
+            "@fragment\nfn fs_main() -> @location(0) vec4<f32> {\n    return vec4<f32>(1.0);\n}\n";
+        assert!(check_wgsl(valid, None).is_ok());
+
+        let typo =
+            "@fragment\nfn fs_main() -> @location(0) vec4<f32> {\n    return vec4<f32>(1.0)\n}\n";
+        let error = check_wgsl(typo, Some("/broken.wgsl")).unwrap_err();
+        assert_eq!(
+            error.path.as_deref(),
+            Some(std::path::Path::new("/broken.wgsl"))
+        );
+        assert_eq!(error.location.map(|(line, _)| line), Some(4));
+        assert_eq!(error.snippet.as_deref(), Some("}"));
+        assert!(error.report.contains("/broken.wgsl"));
+
+        let mistyped = "@fragment\nfn fs_main() -> @location(0) vec4<f32> {\n    let x: f32 = 1u;\n    return vec4<f32>(x);\n}\n";
+        let error = check_wgsl(mistyped, None).unwrap_err();
+        assert_eq!(error.location.map(|(line, _)| line), Some(3));
+        assert_eq!(error.snippet.as_deref(), Some("    let x: f32 = 1u;"));
+    }
+}
@@ -0,0 +1,55 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct Bloom {
+    threshold: f32,
+    intensity: f32,
+    radius: f32,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> bloom: Bloom;
+
+const LUMA: vec3<f32> = vec3<f32>(0.2126, 0.7152, 0.0722);
+const TAU: f32 = 6.28318530718;
+const RINGS: i32 = 4;
+const TAPS_PER_RING: i32 = 12;
+
+// The part of the color above the brightness threshold.
+fn bright(uv: vec2<f32>) -> vec3<f32> {
+    let color = textureSample(t, s, uv).rgb;
+    let luma = dot(color, LUMA);
+    return color * (max(luma - bloom.threshold, 0.0) / max(luma, 0.0001));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = bloom.radius / vec2<f32>(textureDimensions(t));
+
+    var glow = bright(in.uv);
+    var weights = 1.0;
+    for (var ring = 1; ring <= RINGS; ring++) {
+        let dist = f32(ring) / f32(RINGS);
+        let weight = 1.0 - dist * 0.75;
+        // Rotate every other ring by half a step so the taps don't line up.
+        let phase = f32(ring % 2) * 0.5;
+        for (var tap = 0; tap < TAPS_PER_RING; tap++) {
+            let angle = TAU * (f32(tap) + phase) / f32(TAPS_PER_RING);
+            let offset = vec2<f32>(cos(angle), sin(angle)) * dist;
+            glow += bright(in.uv + offset * texel) * weight;
+            weights += weight;
+        }
+    }
+
+    let base = textureSample(t, s, in.uv);
+    return in.color * vec4<f32>(base.rgb + glow / weights * bloom.intensity, base.a);
+}
@@ -0,0 +1,41 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct Blur {
+    radius: f32,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> blur: Blur;
+
+// Replaced by the vertical pass.
+const DIRECTION: vec2<f32> = vec2<f32>(1.0, 0.0);
+const MAX_TAPS: i32 = 32;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = DIRECTION / vec2<f32>(textureDimensions(t));
+    let taps = min(i32(ceil(blur.radius)), MAX_TAPS);
+    // Large radii spread the taps out instead of adding more of them.
+    let spacing = blur.radius / f32(max(taps, 1));
+    let sigma = max(blur.radius * 0.5, 0.0001);
+
+    var sum = vec4<f32>(0.0);
+    var weights = 0.0;
+    for (var i = -taps; i <= taps; i++) {
+        let offset = f32(i) * spacing;
+        let weight = exp(-(offset * offset) / (2.0 * sigma * sigma));
+        sum += textureSample(t, s, in.uv + texel * offset) * weight;
+        weights += weight;
+    }
+    return in.color * (sum / weights);
+}
@@ -0,0 +1,37 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct ColorGrading {
+    exposure: f32,
+    contrast: f32,
+    saturation: f32,
+    gamma: f32,
+    tint: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> grading: ColorGrading;
+
+const LUMA: vec3<f32> = vec3<f32>(0.2126, 0.7152, 0.0722);
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t, s, in.uv);
+
+    var rgb = color.rgb * exp2(grading.exposure);
+    rgb = (rgb - 0.5) * grading.contrast + 0.5;
+    rgb = mix(vec3<f32>(dot(rgb, LUMA)), rgb, grading.saturation);
+    rgb = pow(max(rgb, vec3<f32>(0.0)), vec3<f32>(1.0 / grading.gamma));
+    rgb *= grading.tint.rgb;
+
+    return in.color * vec4<f32>(rgb, color.a);
+}
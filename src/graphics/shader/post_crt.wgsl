@@ -0,0 +1,44 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct Crt {
+    curvature: f32,
+    scanlines: f32,
+    aberration: f32,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> crt: Crt;
+
+const PI: f32 = 3.14159265359;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(t));
+
+    // Bulge the picture outwards, more so towards the corners.
+    let centered = in.uv * 2.0 - 1.0;
+    let warped = centered + centered * (centered.yx * centered.yx) * crt.curvature;
+    let uv = warped * 0.5 + 0.5;
+
+    let shift = vec2<f32>(crt.aberration / size.x, 0.0);
+    let center = textureSample(t, s, uv);
+    let r = textureSample(t, s, uv + shift).r;
+    let b = textureSample(t, s, uv - shift).b;
+
+    let line = sin(uv.y * size.y * PI);
+    let scan = 1.0 - crt.scanlines * (1.0 - line * line);
+
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    let color = vec4<f32>(vec3<f32>(r, center.g, b) * scan, center.a);
+    return in.color * select(vec4<f32>(0.0, 0.0, 0.0, 1.0), color, inside);
+}
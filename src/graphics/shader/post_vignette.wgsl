@@ -0,0 +1,34 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct Vignette {
+    color: vec4<f32>,
+    radius: f32,
+    softness: f32,
+    intensity: f32,
+}
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s: sampler;
+
+@group(3) @binding(0)
+var<uniform> vignette: Vignette;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t, s, in.uv);
+
+    // 0 in the center, 1 in the corners.
+    let dist = length(in.uv - 0.5) * sqrt(2.0);
+    let softness = max(vignette.softness, 0.0001);
+    let edge = smoothstep(vignette.radius - softness, vignette.radius, dist);
+    let amount = edge * vignette.intensity * vignette.color.a;
+
+    return in.color * vec4<f32>(mix(color.rgb, vignette.color.rgb, amount), color.a);
+}
@@ -0,0 +1,310 @@
+use super::{Canvas, Color, Image, ImageEncodingFormat, ImageFormat};
+use crate::{Context, GameError, GameResult};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// The environment variable that makes snapshots write their references.
+const UPDATE_SNAPSHOTS_VAR: &str = "GGEZ_UPDATE_SNAPSHOTS";
+
+/// Golden-image ("snapshot") test for rendered output.
+///
+/// A `Snapshot` compares an [`Image`] against a reference PNG stored at a filesystem path,
+/// allowing each color channel of each pixel to differ by at most `tolerance`.
+///
+/// A missing reference is an error, so that a mistyped path or a reference that was never
+/// committed doesn't pass silently. To create or update references, set the
+/// `GGEZ_UPDATE_SNAPSHOTS` environment variable or use [`Snapshot::update`]; the image is then
+/// written to the user directory as the new reference, to be copied into your resources.
+/// If the image doesn't match, the actual image and a diff image, with mismatching pixels in
+/// red, are written next to it as `<name>.actual.png` and `<name>.diff.png`.
+///
+/// Together with [`ContextBuilder::build_headless`](crate::ContextBuilder::build_headless)
+/// this allows rendering tests on machines without a display:
+///
+/// ```rust,no_run
+/// # use ggez::{graphics::{Color, DrawParam, Image, Snapshot}, ContextBuilder, GameResult};
+/// # use glam::Vec2;
+/// // e.g. in a `#[test]` named `headless_test_*`, so CI picks it up
+/// fn headless_test_render_logo() -> GameResult {
+///     let mut ctx = ContextBuilder::new("snapshots", "ggez").build_headless(1, 1)?;
+///     let logo = Image::from_path(&ctx, "/logo.png")?;
+///
+///     Snapshot::new("/snapshots/logo.png")
+///         .tolerance(2)
+///         .check(&mut ctx, 128, 128, Color::BLACK, |canvas, _ctx| {
+///             canvas.draw(&logo, DrawParam::new().dest(Vec2::new(32., 32.)));
+///             Ok(())
+///         })
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    path: PathBuf,
+    tolerance: u8,
+    update: bool,
+}
+
+impl Snapshot {
+    /// Creates a new snapshot test against the reference PNG at `path`, with a tolerance of 0.
+    ///
+    /// It updates the reference instead if the `GGEZ_UPDATE_SNAPSHOTS` environment variable
+    /// is set.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Snapshot {
+            path: path.as_ref().to_path_buf(),
+            tolerance: 0,
+            update: env::var_os(UPDATE_SNAPSHOTS_VAR).is_some(),
+        }
+    }
+
+    /// Sets whether to write the image as the new reference instead of comparing against it.
+    #[must_use]
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Sets the maximum difference allowed per color channel, to account for
+    /// small differences between GPUs and drivers.
+    #[must_use]
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Returns the path of the reference image.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Renders into a new canvas image of the given size and returns the image.
+    ///
+    /// `draw` is called with a [`Canvas`] cleared to `clear`, which is finished afterwards.
+    /// This renders a frame of its own, so that the image can be read back right away, and
+    /// thus fails between [`begin_frame`](super::GraphicsContext::begin_frame) and
+    /// [`end_frame`](super::GraphicsContext::end_frame).
+    pub fn render(
+        ctx: &mut Context,
+        width: u32,
+        height: u32,
+        clear: impl Into<Option<Color>>,
+        draw: impl FnOnce(&mut Canvas, &mut Context) -> GameResult,
+    ) -> GameResult<Image> {
+        ctx.gfx.begin_frame()?;
+        let image = Image::new_canvas_image(ctx, width, height, 1);
+        let mut canvas = Canvas::from_image(ctx, image.clone(), clear);
+        let drawn = draw(&mut canvas, ctx).and_then(|()| canvas.finish(ctx));
+        // End the frame even if drawing failed, so that the context can be used again.
+        ctx.gfx.end_frame()?;
+        drawn?;
+        Ok(image)
+    }
+
+    /// Renders like [`Snapshot::render`] and compares the result against the reference.
+    pub fn check(
+        &self,
+        ctx: &mut Context,
+        width: u32,
+        height: u32,
+        clear: impl Into<Option<Color>>,
+        draw: impl FnOnce(&mut Canvas, &mut Context) -> GameResult,
+    ) -> GameResult {
+        let image = Self::render(ctx, width, height, clear, draw)?;
+        self.compare(ctx, &image)
+    }
+
+    /// Compares `image` against the reference, returning an error describing
+    /// the mismatch if they differ or the reference is missing. When updating, writes `image`
+    /// as the new reference instead.
+    ///
+    /// Only 8-bit RGBA and BGRA images can be compared.
+    pub fn compare(&self, ctx: &Context, image: &Image) -> GameResult {
+        if self.update {
+            self.create_parent_dir(ctx)?;
+            return image.encode(ctx, ImageEncodingFormat::Png, &self.path);
+        }
+        if !ctx.fs.is_file(&self.path) {
+            return Err(GameError::ResourceLoadError(format!(
+                "snapshot reference {:?} is missing; set {UPDATE_SNAPSHOTS_VAR} to create it",
+                self.path
+            )));
+        }
+
+        let (actual, color) = image.to_encodable_pixels(ctx)?;
+        if color != ::image::ExtendedColorType::Rgba8 {
+            return Err(GameError::RenderError(format!(
+                "cannot compare snapshots of the {:#?} GPU image format",
+                image.format()
+            )));
+        }
+
+        let expected = ::image::load_from_memory(&ctx.fs.read(&self.path)?)
+            .map_err(|e| {
+                GameError::ResourceLoadError(format!(
+                    "failed to load snapshot {:?}: {e}",
+                    self.path
+                ))
+            })?
+            .to_rgba8();
+
+        if (expected.width(), expected.height()) != (image.width(), image.height()) {
+            self.write_actual(ctx, image)?;
+            return Err(GameError::RenderError(format!(
+                "snapshot {:?} mismatch: expected a {}x{} image, got {}x{}",
+                self.path,
+                expected.width(),
+                expected.height(),
+                image.width(),
+                image.height()
+            )));
+        }
+
+        let (mismatches, diff) = diff_pixels(&actual, expected.as_raw(), self.tolerance);
+        if mismatches == 0 {
+            return Ok(());
+        }
+
+        self.write_actual(ctx, image)?;
+        let diff_path = self.sibling_path("diff");
+        Image::from_pixels(
+            ctx,
+            &diff,
+            ImageFormat::Rgba8UnormSrgb,
+            image.width(),
+            image.height(),
+        )
+        .encode(ctx, ImageEncodingFormat::Png, &diff_path)?;
+
+        Err(GameError::RenderError(format!(
+            "snapshot {:?} mismatch: {mismatches} of {} pixels differ by more than {}; diff written to {diff_path:?}",
+            self.path,
+            image.width() * image.height(),
+            self.tolerance
+        )))
+    }
+
+    fn write_actual(&self, ctx: &Context, image: &Image) -> GameResult {
+        self.create_parent_dir(ctx)?;
+        image.encode(ctx, ImageEncodingFormat::Png, self.sibling_path("actual"))
+    }
+
+    fn create_parent_dir(&self, ctx: &Context) -> GameResult {
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => ctx.fs.create_dir(parent),
+            _ => Ok(()),
+        }
+    }
+
+    /// `/a/b.png` -> `/a/b.<suffix>.png`
+    fn sibling_path(&self, suffix: &str) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.path.with_file_name(format!("{stem}.{suffix}.png"))
+    }
+}
+
+/// Compares two RGBA8 pixel buffers of equal size, returning the number of pixels with a
+/// channel differing by more than `tolerance` and a diff image, which has mismatching pixels
+/// in red and all others as a faded grayscale version of `actual`.
+fn diff_pixels(actual: &[u8], expected: &[u8], tolerance: u8) -> (usize, Vec<u8>) {
+    let mut mismatches = 0;
+    let mut diff = Vec::with_capacity(actual.len());
+
+    for (a, e) in actual.chunks_exact(4).zip(expected.chunks_exact(4)) {
+        if a.iter().zip(e).any(|(a, e)| a.abs_diff(*e) > tolerance) {
+            mismatches += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma = ((u16::from(a[0]) + u16::from(a[1]) + u16::from(a[2])) / 3) as u8;
+            let faded = 128 + luma / 4;
+            diff.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+
+    (mismatches, diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_diff_within_tolerance() {
+        let expected = [10, 20, 30, 255, 0, 0, 0, 255];
+        let actual = [12, 18, 30, 255, 0, 0, 1, 255];
+
+        let (mismatches, diff) = diff_pixels(&actual, &expected, 2);
+        assert_eq!(mismatches, 0);
+        assert_eq!(diff.len(), actual.len());
+
+        let (mismatches, _) = diff_pixels(&actual, &expected, 1);
+        assert_eq!(mismatches, 1);
+    }
+
+    #[test]
+    fn snapshot_diff_marks_mismatches() {
+        let expected = [0, 0, 0, 255, 255, 255, 255, 255];
+        let actual = [0, 0, 0, 255, 255, 255, 255, 0];
+
+        let (mismatches, diff) = diff_pixels(&actual, &expected, 0);
+        assert_eq!(mismatches, 1);
+        assert_ne!(&diff[..4], &[255, 0, 0, 255]);
+        assert_eq!(&diff[4..], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn snapshot_sibling_path() {
+        let snapshot = Snapshot::new("/snapshots/scene.png");
+        assert_eq!(
+            snapshot.sibling_path("diff"),
+            PathBuf::from("/snapshots/scene.diff.png")
+        );
+    }
+
+    #[test]
+    fn headless_test_snapshot_compare() {
+        let mut ctx =
+            match crate::ContextBuilder::new("ggez_snapshot_test", "ggez").build_headless(1, 1) {
+                Ok(ctx) => ctx,
+                // No adapter at all, not even a software one, or no audio device to open.
+                Err(GameError::GraphicsInitializationError | GameError::AudioError(_)) => return,
+                Err(e) => panic!("{e}"),
+            };
+        let path = "/snapshots/red.png";
+        let snapshot = Snapshot::new(path).update(false);
+        for file in [path, "/snapshots/red.actual.png", "/snapshots/red.diff.png"] {
+            let _ = ctx.fs.delete(file);
+        }
+        let fill = |_: &mut Canvas, _: &mut Context| Ok(());
+
+        // A missing reference fails until it's written on purpose.
+        assert!(matches!(
+            snapshot.check(&mut ctx, 2, 2, Color::RED, fill),
+            Err(GameError::ResourceLoadError(_))
+        ));
+        assert!(!ctx.fs.is_file(path));
+        snapshot
+            .clone()
+            .update(true)
+            .check(&mut ctx, 2, 2, Color::RED, fill)
+            .unwrap();
+
+        snapshot.check(&mut ctx, 2, 2, Color::RED, fill).unwrap();
+        assert!(matches!(
+            snapshot.check(&mut ctx, 2, 2, Color::BLUE, fill),
+            Err(GameError::RenderError(_))
+        ));
+        assert!(ctx.fs.is_file("/snapshots/red.diff.png"));
+
+        // Rendering a frame of its own, it can't be used within one.
+        ctx.gfx.begin_frame().unwrap();
+        assert!(snapshot.check(&mut ctx, 2, 2, Color::RED, fill).is_err());
+    }
+}
@@ -0,0 +1,1137 @@
+//! Tile maps made with the [Tiled](https://www.mapeditor.org/) map editor.
+//!
+//! A [`Tilemap`] is loaded from a `.tmx` (XML) or `.tmj` (JSON) file, including external
+//! `.tsx`/`.tsj` tilesets, and keeps the map data: its layers, tilesets, object groups and
+//! custom properties. It also renders its tile layers in chunks of [`InstanceArray`]s, only
+//! drawing those visible in the given view, with per-layer parallax and animated tiles.
+//!
+//! ```rust,no_run
+//! # use ggez::{graphics::{tilemap::Tilemap, Camera2d, Canvas, Color}, Context, GameResult};
+//! # fn t(ctx: &mut Context, camera: &Camera2d) -> GameResult {
+//! let mut map = Tilemap::from_path(ctx, "/maps/level1.tmx")?;
+//!
+//! // in `update`
+//! map.update(ctx.time.delta());
+//!
+//! // in `draw`
+//! let mut canvas = Canvas::from_frame(ctx, map.background_color().unwrap_or(Color::BLACK));
+//! camera.apply(&mut canvas);
+//! map.draw(&mut canvas, camera.visible_rect());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only orthogonal maps are supported. Image layers are skipped and group layers are flattened
+//! into their children. Objects aren't drawn; use [`Layer::objects`] to spawn your own entities.
+
+#[cfg(feature = "json")]
+mod tmj;
+mod tmx;
+
+use super::{
+    atlas::resolve_relative, context::GraphicsContext, Canvas, Color, DrawParam, Image,
+    InstanceArray, Rect,
+};
+use crate::{context::Has, glam::Vec2, GameError, GameResult};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+    path::Path,
+    time::Duration,
+};
+
+/// Width and height of the chunks tile layers are split into for culling, in tiles.
+const CHUNK_SIZE: i32 = 16;
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+const GID_MASK: u32 =
+    !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120);
+
+/// Custom properties of a map, tileset, tile, layer or object, by name.
+pub type Properties = BTreeMap<String, PropertyValue>;
+
+/// The value of a custom property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    /// A `bool` property.
+    Bool(bool),
+    /// An `int` property.
+    Int(i64),
+    /// A `float` property.
+    Float(f64),
+    /// A `string` property.
+    String(String),
+    /// A `color` property.
+    Color(Color),
+    /// A `file` property, with the path as written in the file.
+    File(String),
+    /// An `object` property, referring to an object by its ID.
+    Object(u32),
+    /// A property of a custom class, with its members.
+    Class(Properties),
+}
+
+/// A tileset of a [`Tilemap`], made from a single image.
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    /// The global tile ID of the first tile of this tileset in the map.
+    pub first_gid: u32,
+    /// The name of the tileset.
+    pub name: String,
+    /// The image containing the tiles.
+    pub image: Image,
+    /// The width of a tile in pixels.
+    pub tile_width: u32,
+    /// The height of a tile in pixels.
+    pub tile_height: u32,
+    /// The number of tiles per row of the image.
+    pub columns: u32,
+    /// The number of tiles in this tileset.
+    pub tile_count: u32,
+    /// The number of pixels around the tiles of the image.
+    pub margin: u32,
+    /// The number of pixels between the tiles of the image.
+    pub spacing: u32,
+    /// The offset the tiles are drawn with, in pixels.
+    pub offset: mint::Vector2<f32>,
+    /// Extra data of the tiles that have any, by their local tile ID.
+    pub tiles: BTreeMap<u32, TileData>,
+    /// The custom properties of the tileset.
+    pub properties: Properties,
+}
+
+impl Tileset {
+    /// Returns the pixel rect of the tile with the given local ID within [`Tileset::image`].
+    pub fn tile_rect(&self, id: u32) -> Rect {
+        let columns = self.columns.max(1);
+        Rect::new(
+            (self.margin + (id % columns) * (self.tile_width + self.spacing)) as f32,
+            (self.margin + (id / columns) * (self.tile_height + self.spacing)) as f32,
+            self.tile_width as f32,
+            self.tile_height as f32,
+        )
+    }
+
+    /// Returns the rect of the tile with the given local ID within [`Tileset::image`]
+    /// in UV coordinates.
+    pub fn tile_uv_rect(&self, id: u32) -> Rect {
+        let rect = self.tile_rect(id);
+        let (width, height) = (self.image.width() as f32, self.image.height() as f32);
+        Rect::new(
+            rect.x / width,
+            rect.y / height,
+            rect.w / width,
+            rect.h / height,
+        )
+    }
+}
+
+/// Extra data of a tile in a [`Tileset`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TileData {
+    /// The class (formerly type) of the tile, or an empty string.
+    pub class: String,
+    /// The frames of the tile's animation, if it's animated.
+    pub animation: Vec<TileFrame>,
+    /// The custom properties of the tile.
+    pub properties: Properties,
+}
+
+/// A frame of an animated tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileFrame {
+    /// The local ID of the tile shown, in the same tileset.
+    pub tile_id: u32,
+    /// How long the tile is shown.
+    pub duration: Duration,
+}
+
+/// A layer of a [`Tilemap`].
+///
+/// Group layers are flattened into their children, which inherit the group's offset,
+/// parallax, opacity, visibility and tint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    /// The unique ID of the layer.
+    pub id: u32,
+    /// The name of the layer.
+    pub name: String,
+    /// The class of the layer, or an empty string.
+    pub class: String,
+    /// Whether the layer is shown.
+    pub visible: bool,
+    /// The opacity of the layer, from `0.0` to `1.0`.
+    pub opacity: f32,
+    /// The color the layer is multiplied with.
+    pub tint: Color,
+    /// The offset the layer is drawn with, in pixels.
+    pub offset: mint::Vector2<f32>,
+    /// How fast the layer scrolls relative to the camera; `1.0` moves with the map,
+    /// `0.0` stays in place on the screen.
+    pub parallax: mint::Vector2<f32>,
+    /// The custom properties of the layer.
+    pub properties: Properties,
+    /// The tiles or objects of the layer.
+    pub kind: LayerKind,
+}
+
+impl Layer {
+    /// Returns the tiles if this is a tile layer.
+    pub fn tiles(&self) -> Option<&TileLayer> {
+        match &self.kind {
+            LayerKind::Tiles(tiles) => Some(tiles),
+            LayerKind::Objects(_) => None,
+        }
+    }
+
+    /// Returns the objects if this is an object group.
+    pub fn objects(&self) -> Option<&[MapObject]> {
+        match &self.kind {
+            LayerKind::Tiles(_) => None,
+            LayerKind::Objects(objects) => Some(objects),
+        }
+    }
+}
+
+/// The contents of a [`Layer`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerKind {
+    /// A grid of tiles.
+    Tiles(TileLayer),
+    /// An object group.
+    Objects(Vec<MapObject>),
+}
+
+/// The grid of tiles of a tile layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileLayer {
+    /// The column of the leftmost tile, which is negative if an infinite map extends
+    /// to the left of its origin.
+    pub x: i32,
+    /// The row of the topmost tile.
+    pub y: i32,
+    /// The number of columns.
+    pub width: u32,
+    /// The number of rows.
+    pub height: u32,
+    tiles: Vec<u32>,
+}
+
+impl TileLayer {
+    /// Returns the tile at the given column and row, if there is one.
+    pub fn tile(&self, x: i32, y: i32) -> Option<Tile> {
+        let (column, row) = (x - self.x, y - self.y);
+        if column < 0 || row < 0 || column >= self.width as i32 || row >= self.height as i32 {
+            return None;
+        }
+        Tile::from_raw(self.tiles[(row * self.width as i32 + column) as usize])
+    }
+
+    /// Returns an iterator over all tiles with their column and row.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32, Tile)> + '_ {
+        self.tiles.iter().enumerate().filter_map(|(i, &raw)| {
+            let (column, row) = (i as u32 % self.width, i as u32 / self.width);
+            Some((
+                self.x + column as i32,
+                self.y + row as i32,
+                Tile::from_raw(raw)?,
+            ))
+        })
+    }
+}
+
+/// A tile placed in a [`TileLayer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile {
+    /// The global tile ID, see [`Tilemap::tileset`].
+    pub gid: u32,
+    /// Whether the tile is flipped horizontally.
+    pub flip_horizontal: bool,
+    /// Whether the tile is flipped vertically.
+    pub flip_vertical: bool,
+    /// Whether the tile is flipped diagonally, i.e. its x and y axes are swapped.
+    /// This is applied before the other flips.
+    pub flip_diagonal: bool,
+}
+
+impl Tile {
+    fn from_raw(raw: u32) -> Option<Tile> {
+        let gid = raw & GID_MASK;
+        (gid != 0).then_some(Tile {
+            gid,
+            flip_horizontal: raw & FLIPPED_HORIZONTALLY != 0,
+            flip_vertical: raw & FLIPPED_VERTICALLY != 0,
+            flip_diagonal: raw & FLIPPED_DIAGONALLY != 0,
+        })
+    }
+
+    /// Returns the rotation and scale that draw a tile image centered on the origin
+    /// with this tile's flips applied.
+    fn rotation_and_scale(&self) -> (f32, mint::Vector2<f32>) {
+        // The flips as a 2x2 matrix: diagonal first, then horizontal, then vertical.
+        let [mut m00, mut m01, mut m10, mut m11] = if self.flip_diagonal {
+            [0., 1., 1., 0.]
+        } else {
+            [1., 0., 0., 1.]
+        };
+        if self.flip_horizontal {
+            (m00, m01) = (-m00, -m01);
+        }
+        if self.flip_vertical {
+            (m10, m11) = (-m10, -m11);
+        }
+
+        // DrawParam scales first and then rotates, so decompose into R(angle) * S(1, ±1).
+        let det: f32 = m00 * m11 - m01 * m10;
+        (f32::atan2(m10, m00), [1., det.signum()].into())
+    }
+}
+
+/// An object of an object group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapObject {
+    /// The unique ID of the object.
+    pub id: u32,
+    /// The name of the object.
+    pub name: String,
+    /// The class (formerly type) of the object, or an empty string.
+    pub class: String,
+    /// The position of the object in pixels. For tile objects this is the bottom left corner,
+    /// otherwise the top left one.
+    pub position: mint::Point2<f32>,
+    /// The size of the object in pixels.
+    pub size: mint::Vector2<f32>,
+    /// The clockwise rotation of the object around its position, in radians.
+    pub rotation: f32,
+    /// The tile shown by a tile object.
+    pub tile: Option<Tile>,
+    /// Whether the object is shown.
+    pub visible: bool,
+    /// The shape of the object.
+    pub shape: ObjectShape,
+    /// The custom properties of the object.
+    pub properties: Properties,
+}
+
+/// The shape of a [`MapObject`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectShape {
+    /// A rectangle of the object's size.
+    Rectangle,
+    /// An ellipse filling the object's size.
+    Ellipse,
+    /// A single point.
+    Point,
+    /// A closed polygon, with points relative to the object's position.
+    Polygon(Vec<mint::Point2<f32>>),
+    /// An open polyline, with points relative to the object's position.
+    Polyline(Vec<mint::Point2<f32>>),
+    /// A text box of the object's size.
+    Text(String),
+}
+
+/// A tile map loaded from Tiled, see the [module docs](self).
+#[derive(Debug)]
+pub struct Tilemap {
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    infinite: bool,
+    background_color: Option<Color>,
+    properties: Properties,
+    tilesets: Vec<Tileset>,
+    layers: Vec<Layer>,
+    /// The chunks of each layer, empty for object groups.
+    chunks: Vec<Vec<Chunk>>,
+    animations: Vec<TileAnimation>,
+    time: Duration,
+}
+
+impl Tilemap {
+    /// Loads a Tiled map through the [`Filesystem`](crate::filesystem::Filesystem).
+    ///
+    /// Files ending in `.tmx` or `.xml` are read as XML, anything else as JSON, which needs the
+    /// `json` feature. Tilesets and images are resolved relative to the file referring to them,
+    /// like Tiled does.
+    pub fn from_path(gfx: &impl Has<GraphicsContext>, path: impl AsRef<Path>) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        let path = path.as_ref();
+
+        let text = gfx.fs.read_to_string(path)?;
+        let map = if is_xml(path) {
+            tmx::parse_map(&text)
+        } else {
+            parse_tmj_map(&text)
+        }
+        .map_err(|e| GameError::ResourceLoadError(format!("invalid Tiled map {path:?}: {e}")))?;
+
+        if map.orientation != "orthogonal" {
+            return Err(GameError::ResourceLoadError(format!(
+                "cannot load Tiled map {path:?}: {} maps are not supported, only orthogonal ones",
+                map.orientation
+            )));
+        }
+
+        let mut tilesets = map
+            .tilesets
+            .into_iter()
+            .map(|tileset| load_tileset(gfx, path, tileset))
+            .collect::<GameResult<Vec<_>>>()?;
+        tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+        let mut tilemap = Tilemap {
+            width: map.width,
+            height: map.height,
+            tile_width: map.tile_width,
+            tile_height: map.tile_height,
+            infinite: map.infinite,
+            background_color: map.background_color,
+            properties: map.properties,
+            tilesets,
+            layers: map.layers,
+            chunks: Vec::new(),
+            animations: Vec::new(),
+            time: Duration::ZERO,
+        };
+        tilemap.chunks = tilemap.build_chunks(gfx);
+        Ok(tilemap)
+    }
+
+    /// Returns the width of the map in tiles. Infinite maps can extend beyond it.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the map in tiles. Infinite maps can extend beyond it.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the width of a grid cell in pixels.
+    #[inline]
+    pub fn tile_width(&self) -> u32 {
+        self.tile_width
+    }
+
+    /// Returns the height of a grid cell in pixels.
+    #[inline]
+    pub fn tile_height(&self) -> u32 {
+        self.tile_height
+    }
+
+    /// Returns whether this is an infinite map.
+    #[inline]
+    pub fn is_infinite(&self) -> bool {
+        self.infinite
+    }
+
+    /// Returns the background color of the map, if it has one.
+    #[inline]
+    pub fn background_color(&self) -> Option<Color> {
+        self.background_color
+    }
+
+    /// Returns the custom properties of the map.
+    #[inline]
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    /// Returns the tilesets of the map, ordered by their first global tile ID.
+    #[inline]
+    pub fn tilesets(&self) -> &[Tileset] {
+        &self.tilesets
+    }
+
+    /// Returns all layers of the map, from bottom to top.
+    #[inline]
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Returns the first layer with the given name.
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Returns the tileset containing the given global tile ID, and the tile's local ID in it.
+    pub fn tileset(&self, gid: u32) -> Option<(&Tileset, u32)> {
+        tileset_index(&self.tilesets, gid).map(|(i, id)| (&self.tilesets[i], id))
+    }
+
+    /// Returns the extra data of the tile with the given global tile ID, if it has any.
+    pub fn tile_data(&self, gid: u32) -> Option<&TileData> {
+        let (tileset, id) = self.tileset(gid)?;
+        tileset.tiles.get(&id)
+    }
+
+    /// Returns the pixel rect of the grid cell at the given column and row.
+    pub fn cell_rect(&self, x: i32, y: i32) -> Rect {
+        Rect::new(
+            (x * self.tile_width as i32) as f32,
+            (y * self.tile_height as i32) as f32,
+            self.tile_width as f32,
+            self.tile_height as f32,
+        )
+    }
+
+    /// Returns the column and row of the grid cell containing the given point in pixels.
+    pub fn cell_at(&self, point: impl Into<mint::Point2<f32>>) -> (i32, i32) {
+        let point = point.into();
+        (
+            (point.x / self.tile_width as f32).floor() as i32,
+            (point.y / self.tile_height as f32).floor() as i32,
+        )
+    }
+
+    /// Advances the animated tiles.
+    pub fn update(&mut self, dt: Duration) {
+        self.time += dt;
+
+        let mut changed = false;
+        for animation in &mut self.animations {
+            let frame = animation.frame_at(self.time);
+            changed |= frame != animation.frame;
+            animation.changed = frame != animation.frame;
+            animation.frame = frame;
+        }
+        if !changed {
+            return;
+        }
+
+        for batch in self
+            .chunks
+            .iter_mut()
+            .flatten()
+            .flat_map(|c| &mut c.batches)
+        {
+            let tileset = &self.tilesets[batch.tileset];
+            for &(index, animation) in &batch.animated {
+                let animation = &self.animations[animation];
+                if animation.changed {
+                    let tile_id = animation.frames[animation.frame].tile_id;
+                    let param = batch.array.instances()[index as usize];
+                    batch
+                        .array
+                        .update(index, param.src(tileset.tile_uv_rect(tile_id)));
+                }
+            }
+        }
+    }
+
+    /// Draws all visible tile layers, from bottom to top.
+    ///
+    /// `view` is the part of the map visible on the canvas, e.g. [`Camera2d::visible_rect`],
+    /// in pixels. Only the chunks overlapping it are drawn, and layer parallax is relative
+    /// to its center.
+    ///
+    /// [`Camera2d::visible_rect`]: super::Camera2d::visible_rect
+    pub fn draw(&self, canvas: &mut Canvas, view: Rect) {
+        for index in 0..self.layers.len() {
+            self.draw_layer(canvas, index, view);
+        }
+    }
+
+    /// Draws the layer at `index` of [`Tilemap::layers`] if it's a visible tile layer,
+    /// like [`Tilemap::draw`]. This allows drawing sprites in between layers.
+    pub fn draw_layer(&self, canvas: &mut Canvas, index: usize, view: Rect) {
+        let Some(layer) = self.layers.get(index) else {
+            return;
+        };
+        if !layer.visible || layer.opacity <= 0. {
+            return;
+        }
+
+        let offset = self.layer_offset(layer, view);
+        for chunk in &self.chunks[index] {
+            let mut bounds = chunk.bounds;
+            bounds.translate(offset);
+            if !bounds.overlaps(&view) {
+                continue;
+            }
+            for batch in &chunk.batches {
+                canvas.draw(&batch.array, DrawParam::new().dest(offset));
+            }
+        }
+    }
+
+    /// Returns where `layer` is drawn for the given view, including parallax.
+    fn layer_offset(&self, layer: &Layer, view: Rect) -> Vec2 {
+        let center = Vec2::from(view.center());
+        Vec2::from(layer.offset) + center * (Vec2::ONE - Vec2::from(layer.parallax))
+    }
+
+    fn build_chunks(&mut self, gfx: &GraphicsContext) -> Vec<Vec<Chunk>> {
+        let mut animation_indices = HashMap::new();
+        let mut chunks = Vec::with_capacity(self.layers.len());
+
+        for layer in &self.layers {
+            let Some(tiles) = layer.tiles() else {
+                chunks.push(Vec::new());
+                continue;
+            };
+
+            let mut color = layer.tint;
+            color.a *= layer.opacity;
+
+            let mut layer_chunks: BTreeMap<(i32, i32), ChunkBuilder> = BTreeMap::new();
+            for (x, y, tile) in tiles.iter() {
+                let Some((set, mut id)) = tileset_index(&self.tilesets, tile.gid) else {
+                    log::warn!(
+                        "tile {} of layer {:?} isn't in any tileset",
+                        tile.gid,
+                        layer.name
+                    );
+                    continue;
+                };
+                let tileset = &self.tilesets[set];
+
+                let animation = tileset
+                    .tiles
+                    .get(&id)
+                    .filter(|data| !data.animation.is_empty())
+                    .map(|data| {
+                        *animation_indices.entry((set, id)).or_insert_with(|| {
+                            self.animations.push(TileAnimation::new(&data.animation));
+                            self.animations.len() - 1
+                        })
+                    });
+                if let Some(animation) = animation {
+                    let animation = &self.animations[animation];
+                    id = animation.frames[animation.frame].tile_id;
+                }
+
+                // Tiles are aligned to the bottom left of their cell, so that bigger tiles
+                // stick out to the top and right.
+                let (rotation, scale) = tile.rotation_and_scale();
+                let mut size = Vec2::new(tileset.tile_width as f32, tileset.tile_height as f32);
+                if tile.flip_diagonal {
+                    size = Vec2::new(size.y, size.x);
+                }
+                let bottom_left = Vec2::new(
+                    (x * self.tile_width as i32) as f32,
+                    ((y + 1) * self.tile_height as i32) as f32,
+                ) + Vec2::from(tileset.offset);
+                let rect = Rect::new(bottom_left.x, bottom_left.y - size.y, size.x, size.y);
+
+                let param = DrawParam::new()
+                    .src(tileset.tile_uv_rect(id))
+                    .dest(rect.center())
+                    .offset([0.5, 0.5])
+                    .rotation(rotation)
+                    .scale(scale)
+                    .color(color);
+
+                let chunk = layer_chunks
+                    .entry((x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE)))
+                    .or_insert_with(|| ChunkBuilder {
+                        bounds: rect,
+                        batches: BTreeMap::new(),
+                    });
+                chunk.bounds = chunk.bounds.combine_with(rect);
+                let (params, animated) = chunk.batches.entry(set).or_default();
+                if let Some(animation) = animation {
+                    animated.push((params.len() as u32, animation));
+                }
+                params.push(param);
+            }
+
+            chunks.push(
+                layer_chunks
+                    .into_values()
+                    .map(|chunk| Chunk {
+                        bounds: chunk.bounds,
+                        batches: chunk
+                            .batches
+                            .into_iter()
+                            .map(|(tileset, (params, animated))| {
+                                let mut array =
+                                    InstanceArray::new(gfx, self.tilesets[tileset].image.clone());
+                                array.set(params);
+                                Batch {
+                                    tileset,
+                                    array,
+                                    animated,
+                                }
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            );
+        }
+
+        chunks
+    }
+}
+
+/// A square of tiles of a layer, drawn with one [`InstanceArray`] per tileset.
+#[derive(Debug)]
+struct Chunk {
+    /// The pixel rect covered by the tiles, before the layer's offset.
+    bounds: Rect,
+    batches: Vec<Batch>,
+}
+
+#[derive(Debug)]
+struct ChunkBuilder {
+    bounds: Rect,
+    batches: BTreeMap<usize, BatchBuilder>,
+}
+
+/// The instances of a [`Batch`] and its animated tiles, by tileset index.
+type BatchBuilder = (Vec<DrawParam>, Vec<(u32, usize)>);
+
+#[derive(Debug)]
+struct Batch {
+    tileset: usize,
+    array: InstanceArray,
+    /// Instance indices of animated tiles, with the index of their animation.
+    animated: Vec<(u32, usize)>,
+}
+
+/// Playback state of a tile animation, shared by all tiles showing it.
+#[derive(Debug)]
+struct TileAnimation {
+    frames: Vec<TileFrame>,
+    total: Duration,
+    frame: usize,
+    changed: bool,
+}
+
+impl TileAnimation {
+    fn new(frames: &[TileFrame]) -> Self {
+        TileAnimation {
+            frames: frames.to_vec(),
+            total: frames.iter().map(|f| f.duration).sum(),
+            frame: 0,
+            changed: false,
+        }
+    }
+
+    fn frame_at(&self, time: Duration) -> usize {
+        if self.total.is_zero() {
+            return 0;
+        }
+        let mut time = Duration::from_nanos((time.as_nanos() % self.total.as_nanos()) as u64);
+        for (i, frame) in self.frames.iter().enumerate() {
+            if time < frame.duration {
+                return i;
+            }
+            time -= frame.duration;
+        }
+        self.frames.len() - 1
+    }
+}
+
+/// Returns the index of the tileset containing `gid` and the local ID of the tile in it.
+fn tileset_index(tilesets: &[Tileset], gid: u32) -> Option<(usize, u32)> {
+    let index = tilesets
+        .partition_point(|t| t.first_gid <= gid)
+        .checked_sub(1)?;
+    let id = gid - tilesets[index].first_gid;
+    (id < tilesets[index].tile_count).then_some((index, id))
+}
+
+fn is_xml(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["tmx", "tsx", "xml"].contains(&e.to_ascii_lowercase().as_str()))
+}
+
+#[cfg(feature = "json")]
+use tmj::{parse_map as parse_tmj_map, parse_tileset as parse_tmj_tileset};
+
+#[cfg(not(feature = "json"))]
+fn parse_tmj_map(_text: &str) -> Result<RawMap, String> {
+    Err(String::from(
+        "reading JSON maps requires the `json` feature of ggez",
+    ))
+}
+
+#[cfg(not(feature = "json"))]
+fn parse_tmj_tileset(_text: &str) -> Result<TilesetData, String> {
+    Err(String::from(
+        "reading JSON tilesets requires the `json` feature of ggez",
+    ))
+}
+
+fn load_tileset(
+    gfx: &GraphicsContext,
+    map_path: &Path,
+    tileset: RawTileset,
+) -> GameResult<Tileset> {
+    let (data, path) = match tileset {
+        RawTileset::Embedded(data) => (data, map_path.to_path_buf()),
+        RawTileset::External { first_gid, source } => {
+            let path = resolve_relative(map_path, &source);
+            let text = gfx.fs.read_to_string(&path)?;
+            let mut data = if is_xml(&path) {
+                tmx::parse_tileset(&text)
+            } else {
+                parse_tmj_tileset(&text)
+            }
+            .map_err(|e| {
+                GameError::ResourceLoadError(format!("invalid Tiled tileset {path:?}: {e}"))
+            })?;
+            data.first_gid = first_gid;
+            (data, path)
+        }
+    };
+
+    let image = data.image.ok_or_else(|| {
+        GameError::ResourceLoadError(format!(
+            "cannot load tileset {:?} of {map_path:?}: image collection tilesets are not supported",
+            data.name
+        ))
+    })?;
+    let image = Image::from_path(gfx, resolve_relative(&path, &image))?;
+
+    // Both are optional in older files, so derive them from the image size if missing.
+    let columns = if data.columns > 0 {
+        data.columns
+    } else {
+        (image.width().saturating_sub(2 * data.margin) + data.spacing)
+            / (data.tile_width + data.spacing).max(1)
+    };
+    let tile_count = if data.tile_count > 0 {
+        data.tile_count
+    } else {
+        let rows = (image.height().saturating_sub(2 * data.margin) + data.spacing)
+            / (data.tile_height + data.spacing).max(1);
+        columns * rows
+    };
+
+    Ok(Tileset {
+        first_gid: data.first_gid,
+        name: data.name,
+        image,
+        tile_width: data.tile_width,
+        tile_height: data.tile_height,
+        columns,
+        tile_count,
+        margin: data.margin,
+        spacing: data.spacing,
+        offset: data.offset,
+        tiles: data.tiles,
+        properties: data.properties,
+    })
+}
+
+/// A map as read from a file, before its tilesets are loaded.
+#[derive(Debug)]
+struct RawMap {
+    orientation: String,
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    infinite: bool,
+    background_color: Option<Color>,
+    properties: Properties,
+    tilesets: Vec<RawTileset>,
+    layers: Vec<Layer>,
+}
+
+#[derive(Debug)]
+enum RawTileset {
+    Embedded(TilesetData),
+    External { first_gid: u32, source: String },
+}
+
+/// A tileset as read from a file, before its image is loaded.
+#[derive(Debug)]
+struct TilesetData {
+    first_gid: u32,
+    name: String,
+    /// `None` for image collection tilesets.
+    image: Option<String>,
+    tile_width: u32,
+    tile_height: u32,
+    columns: u32,
+    tile_count: u32,
+    margin: u32,
+    spacing: u32,
+    offset: mint::Vector2<f32>,
+    tiles: BTreeMap<u32, TileData>,
+    properties: Properties,
+}
+
+/// What a layer inherits from the group layers it's in.
+#[derive(Debug, Clone, Copy)]
+struct Inherited {
+    visible: bool,
+    opacity: f32,
+    tint: Color,
+    offset: Vec2,
+    parallax: Vec2,
+}
+
+impl Default for Inherited {
+    fn default() -> Self {
+        Inherited {
+            visible: true,
+            opacity: 1.,
+            tint: Color::WHITE,
+            offset: Vec2::ZERO,
+            parallax: Vec2::ONE,
+        }
+    }
+}
+
+impl Inherited {
+    /// Applies the inherited values to `layer`, returning what the children of `layer`
+    /// inherit if it's a group.
+    fn apply(&self, layer: &mut Layer) -> Inherited {
+        layer.visible &= self.visible;
+        layer.opacity *= self.opacity;
+        layer.tint = Color::new(
+            layer.tint.r * self.tint.r,
+            layer.tint.g * self.tint.g,
+            layer.tint.b * self.tint.b,
+            layer.tint.a * self.tint.a,
+        );
+        layer.offset = (Vec2::from(layer.offset) + self.offset).into();
+        layer.parallax = (Vec2::from(layer.parallax) * self.parallax).into();
+
+        Inherited {
+            visible: layer.visible,
+            opacity: layer.opacity,
+            tint: layer.tint,
+            offset: layer.offset.into(),
+            parallax: layer.parallax.into(),
+        }
+    }
+}
+
+/// Parses a Tiled color, `#RRGGBB` or `#AARRGGBB`.
+fn parse_color(color: &str) -> Result<Color, String> {
+    let hex = color.trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).map_err(|_| format!("invalid color {color:?}"))?;
+    match hex.len() {
+        6 => Ok(Color::from_rgb_u32(value)),
+        8 => Ok(Color::from_rgba_u32(value.rotate_left(8))),
+        _ => Err(format!("invalid color {color:?}")),
+    }
+}
+
+/// Decodes the tile data of a layer or chunk, returning the raw global tile IDs
+/// including the flip flags.
+fn decode_tiles(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, String> {
+    match encoding {
+        Some("csv") => data
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().map_err(|_| format!("invalid tile {s:?}")))
+            .collect(),
+        Some("base64") => {
+            use base64::Engine;
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|e| format!("invalid base64 tile data: {e}"))?;
+
+            let mut decompressed = Vec::new();
+            let bytes = match compression {
+                None | Some("") => bytes,
+                Some("zlib") => {
+                    let _ = flate2::read::ZlibDecoder::new(&bytes[..])
+                        .read_to_end(&mut decompressed)
+                        .map_err(|e| format!("invalid zlib tile data: {e}"))?;
+                    decompressed
+                }
+                Some("gzip") => {
+                    let _ = flate2::read::GzDecoder::new(&bytes[..])
+                        .read_to_end(&mut decompressed)
+                        .map_err(|e| format!("invalid gzip tile data: {e}"))?;
+                    decompressed
+                }
+                Some("zstd") => {
+                    let _ = ruzstd::decoding::StreamingDecoder::new(&bytes[..])
+                        .map_err(|e| format!("invalid zstd tile data: {e}"))?
+                        .read_to_end(&mut decompressed)
+                        .map_err(|e| format!("invalid zstd tile data: {e}"))?;
+                    decompressed
+                }
+                Some(other) => return Err(format!("unsupported tile compression {other:?}")),
+            };
+            if bytes.len() % 4 != 0 {
+                return Err(format!("{} bytes of tile data", bytes.len()));
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect())
+        }
+        Some(other) => Err(format!("unsupported tile encoding {other:?}")),
+        None => Err(String::from("missing tile encoding")),
+    }
+}
+
+/// A rectangle of raw tiles, e.g. a chunk of an infinite map.
+#[derive(Debug)]
+struct RawChunk {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    tiles: Vec<u32>,
+}
+
+/// Merges the chunks of an infinite map's layer into a single rectangle of tiles.
+fn merge_chunks(chunks: Vec<RawChunk>) -> Result<TileLayer, String> {
+    let Some(first) = chunks.first() else {
+        return Ok(TileLayer {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            tiles: Vec::new(),
+        });
+    };
+
+    let (mut left, mut top) = (first.x, first.y);
+    let (mut right, mut bottom) = (left, top);
+    for chunk in &chunks {
+        if chunk.tiles.len() != (chunk.width * chunk.height) as usize {
+            return Err(format!(
+                "chunk at {}, {} has {} tiles instead of {}x{}",
+                chunk.x,
+                chunk.y,
+                chunk.tiles.len(),
+                chunk.width,
+                chunk.height
+            ));
+        }
+        left = left.min(chunk.x);
+        top = top.min(chunk.y);
+        right = right.max(chunk.x + chunk.width as i32);
+        bottom = bottom.max(chunk.y + chunk.height as i32);
+    }
+
+    let width = (right - left) as u32;
+    let height = (bottom - top) as u32;
+    let mut tiles = vec![0; (width * height) as usize];
+    for chunk in &chunks {
+        for (row, data) in chunk.tiles.chunks_exact(chunk.width as usize).enumerate() {
+            let start =
+                ((chunk.y - top) as usize + row) * width as usize + (chunk.x - left) as usize;
+            tiles[start..start + data.len()].copy_from_slice(data);
+        }
+    }
+
+    Ok(TileLayer {
+        x: left,
+        y: top,
+        width,
+        height,
+        tiles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_tilemap_decode_tiles() {
+        assert_eq!(
+            decode_tiles("\n1,2,\n0,2147483651\n", Some("csv"), None),
+            Ok(vec![1, 2, 0, 0x8000_0003])
+        );
+        // [1, 2] as little-endian u32s
+        assert_eq!(
+            decode_tiles("AQAAAAIAAAA=", Some("base64"), None),
+            Ok(vec![1, 2])
+        );
+        // the same, zlib compressed
+        assert_eq!(
+            decode_tiles("eJxjZGBgYAJiAAAYAAQ=", Some("base64"), Some("zlib")),
+            Ok(vec![1, 2])
+        );
+        // and zstd compressed
+        assert_eq!(
+            decode_tiles("KLUv/SQIQQAAAQAAAAIAAAA6GOQe", Some("base64"), Some("zstd")),
+            Ok(vec![1, 2])
+        );
+        assert!(decode_tiles("AQAAAAIAAAA=", Some("base64"), Some("zstd")).is_err());
+    }
+
+    #[test]
+    fn headless_test_tilemap_merge_chunks() {
+        let layer = merge_chunks(vec![
+            RawChunk {
+                x: 0,
+                y: 0,
+                width: 2,
+                height: 1,
+                tiles: vec![1, 2],
+            },
+            RawChunk {
+                x: -2,
+                y: 1,
+                width: 2,
+                height: 1,
+                tiles: vec![3, 4],
+            },
+        ])
+        .unwrap();
+
+        assert_eq!((layer.x, layer.y, layer.width, layer.height), (-2, 0, 4, 2));
+        assert_eq!(layer.tile(1, 0).map(|t| t.gid), Some(2));
+        assert_eq!(layer.tile(-2, 1).map(|t| t.gid), Some(3));
+        assert_eq!(layer.tile(0, 1), None);
+        assert_eq!(layer.iter().count(), 4);
+    }
+
+    #[test]
+    fn headless_test_tilemap_flips() {
+        let transform = |raw| {
+            let (rotation, scale) = Tile::from_raw(raw).unwrap().rotation_and_scale();
+            let (sin, cos) = rotation.sin_cos();
+            // The matrix applied to the tile image, rounded to whole numbers.
+            [cos * scale.x, -sin * scale.y, sin * scale.x, cos * scale.y].map(f32::round)
+        };
+
+        assert_eq!(transform(1), [1., 0., 0., 1.]);
+        assert_eq!(transform(1 | FLIPPED_HORIZONTALLY), [-1., 0., 0., 1.]);
+        assert_eq!(transform(1 | FLIPPED_VERTICALLY), [1., 0., 0., -1.]);
+        assert_eq!(transform(1 | FLIPPED_DIAGONALLY), [0., 1., 1., 0.]);
+        // Rotated 90° clockwise in Tiled.
+        assert_eq!(
+            transform(1 | FLIPPED_DIAGONALLY | FLIPPED_HORIZONTALLY),
+            [0., -1., 1., 0.]
+        );
+    }
+
+    #[test]
+    fn headless_test_tilemap_animation_frames() {
+        let ms = Duration::from_millis;
+        let animation = TileAnimation::new(&[
+            TileFrame {
+                tile_id: 0,
+                duration: ms(100),
+            },
+            TileFrame {
+                tile_id: 1,
+                duration: ms(50),
+            },
+        ]);
+        assert_eq!(animation.frame_at(ms(0)), 0);
+        assert_eq!(animation.frame_at(ms(120)), 1);
+        assert_eq!(animation.frame_at(ms(160)), 0);
+        assert_eq!(
+            parse_color("#80ff0000"),
+            Ok(Color::from_rgba(255, 0, 0, 128))
+        );
+    }
+}
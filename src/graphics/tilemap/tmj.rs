@@ -0,0 +1,557 @@
+//! The JSON map (`.tmj`) and tileset (`.tsj`) formats.
+
+use super::{
+    decode_tiles, merge_chunks, parse_color, Inherited, LayerKind, MapObject, ObjectShape,
+    Properties, PropertyValue, RawChunk, RawMap, RawTileset, TileData, TileFrame, TileLayer,
+    TilesetData,
+};
+use crate::graphics::Color;
+use serde::Deserialize;
+use std::time::Duration;
+
+type Result<T> = std::result::Result<T, String>;
+
+pub(super) fn parse_map(text: &str) -> Result<RawMap> {
+    let map: Map = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+    let tilesets = map
+        .tilesets
+        .into_iter()
+        .map(|tileset| match tileset.source.clone() {
+            Some(source) => Ok(RawTileset::External {
+                first_gid: tileset.firstgid.unwrap_or(1),
+                source,
+            }),
+            None => tileset.into_data().map(RawTileset::Embedded),
+        })
+        .collect::<Result<_>>()?;
+
+    let mut layers = Vec::new();
+    parse_layers(map.layers, Inherited::default(), &mut layers)?;
+
+    Ok(RawMap {
+        orientation: map.orientation,
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        infinite: map.infinite,
+        background_color: map
+            .backgroundcolor
+            .as_deref()
+            .map(parse_color)
+            .transpose()?,
+        properties: properties(map.properties)?,
+        tilesets,
+        layers,
+    })
+}
+
+pub(super) fn parse_tileset(text: &str) -> Result<TilesetData> {
+    let tileset: Tileset = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    tileset.into_data()
+}
+
+fn parse_layers(
+    raw: Vec<Layer>,
+    inherited: Inherited,
+    layers: &mut Vec<super::Layer>,
+) -> Result<()> {
+    for mut layer in raw {
+        let kind = match layer.kind.as_str() {
+            "tilelayer" => LayerKind::Tiles(layer.tiles()?),
+            "objectgroup" => LayerKind::Objects(
+                std::mem::take(&mut layer.objects)
+                    .into_iter()
+                    .map(Object::into_object)
+                    .collect::<Result<_>>()?,
+            ),
+            "group" => {
+                let children = std::mem::take(&mut layer.layers);
+                let mut group = layer.into_layer(LayerKind::Objects(Vec::new()))?;
+                parse_layers(children, inherited.apply(&mut group), layers)?;
+                continue;
+            }
+            "imagelayer" => {
+                log::warn!(
+                    "skipping image layer {:?}, image layers are not supported",
+                    layer.name
+                );
+                continue;
+            }
+            _ => continue,
+        };
+
+        let mut parsed = layer.into_layer(kind)?;
+        let _ = inherited.apply(&mut parsed);
+        layers.push(parsed);
+    }
+    Ok(())
+}
+
+fn properties(raw: Vec<Property>) -> Result<Properties> {
+    raw.into_iter()
+        .map(|property| {
+            let value = property.value;
+            let invalid = || format!("invalid {} property {:?}", property.kind, property.name);
+            let value = match property.kind.as_str() {
+                "bool" => PropertyValue::Bool(value.as_bool().ok_or_else(invalid)?),
+                "int" => PropertyValue::Int(value.as_i64().ok_or_else(invalid)?),
+                "float" => PropertyValue::Float(value.as_f64().ok_or_else(invalid)?),
+                "color" => match value.as_str().ok_or_else(invalid)? {
+                    "" => PropertyValue::Color(Color::new(0., 0., 0., 0.)),
+                    color => PropertyValue::Color(parse_color(color)?),
+                },
+                "file" => PropertyValue::File(value.as_str().ok_or_else(invalid)?.to_string()),
+                "object" => PropertyValue::Object(
+                    value
+                        .as_u64()
+                        .and_then(|id| u32::try_from(id).ok())
+                        .ok_or_else(invalid)?,
+                ),
+                _ => untyped(value),
+            };
+            Ok((property.name, value))
+        })
+        .collect()
+}
+
+/// Converts a value without type information, i.e. a member of a class property.
+fn untyped(value: serde_json::Value) -> PropertyValue {
+    use serde_json::Value;
+    match value {
+        Value::Bool(b) => PropertyValue::Bool(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => PropertyValue::Int(i),
+            None => PropertyValue::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::Object(members) => PropertyValue::Class(
+            members
+                .into_iter()
+                .map(|(name, value)| (name, untyped(value)))
+                .collect(),
+        ),
+        Value::String(s) => PropertyValue::String(s),
+        other => PropertyValue::String(other.to_string()),
+    }
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn one() -> f32 {
+    1.
+}
+
+fn orthogonal() -> String {
+    String::from("orthogonal")
+}
+
+fn string() -> String {
+    String::from("string")
+}
+
+#[derive(Debug, Deserialize)]
+struct Map {
+    #[serde(default = "orthogonal")]
+    orientation: String,
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    backgroundcolor: Option<String>,
+    #[serde(default)]
+    properties: Vec<Property>,
+    #[serde(default)]
+    tilesets: Vec<Tileset>,
+    #[serde(default)]
+    layers: Vec<Layer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Property {
+    name: String,
+    #[serde(rename = "type", default = "string")]
+    kind: String,
+    value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tileset {
+    firstgid: Option<u32>,
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    image: Option<String>,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+    tileoffset: Option<Offset>,
+    #[serde(default)]
+    tiles: Vec<Tile>,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+impl Tileset {
+    fn into_data(self) -> Result<TilesetData> {
+        let tiles = self
+            .tiles
+            .into_iter()
+            .map(|tile| {
+                let data = TileData {
+                    class: tile.class,
+                    animation: tile
+                        .animation
+                        .into_iter()
+                        .map(|frame| TileFrame {
+                            tile_id: frame.tileid,
+                            duration: Duration::from_millis(frame.duration),
+                        })
+                        .collect(),
+                    properties: properties(tile.properties)?,
+                };
+                Ok((tile.id, data))
+            })
+            .collect::<Result<_>>()?;
+        let offset = self.tileoffset.unwrap_or(Offset { x: 0., y: 0. });
+
+        Ok(TilesetData {
+            first_gid: self.firstgid.unwrap_or(0),
+            name: self.name,
+            image: self.image,
+            tile_width: self.tilewidth,
+            tile_height: self.tileheight,
+            columns: self.columns,
+            tile_count: self.tilecount,
+            margin: self.margin,
+            spacing: self.spacing,
+            offset: mint::Vector2 {
+                x: offset.x,
+                y: offset.y,
+            },
+            tiles,
+            properties: properties(self.properties)?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Offset {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tile {
+    id: u32,
+    /// Called type before Tiled 1.9.
+    #[serde(default, alias = "type")]
+    class: String,
+    #[serde(default)]
+    animation: Vec<Frame>,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Frame {
+    tileid: u32,
+    duration: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Layer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default = "yes")]
+    visible: bool,
+    #[serde(default = "one")]
+    opacity: f32,
+    tintcolor: Option<String>,
+    #[serde(default)]
+    offsetx: f32,
+    #[serde(default)]
+    offsety: f32,
+    #[serde(default = "one")]
+    parallaxx: f32,
+    #[serde(default = "one")]
+    parallaxy: f32,
+    #[serde(default)]
+    properties: Vec<Property>,
+
+    // tile layers
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    data: Option<Data>,
+    #[serde(default)]
+    chunks: Vec<Chunk>,
+    encoding: Option<String>,
+    compression: Option<String>,
+
+    // object groups
+    #[serde(default)]
+    objects: Vec<Object>,
+
+    // groups
+    #[serde(default)]
+    layers: Vec<Layer>,
+}
+
+impl Layer {
+    fn tiles(&mut self) -> Result<TileLayer> {
+        let encoding = self.encoding.as_deref().or(Some("csv"));
+        let compression = self.compression.as_deref();
+
+        if let Some(data) = self.data.take() {
+            return merge_chunks(vec![RawChunk {
+                x: 0,
+                y: 0,
+                width: self.width,
+                height: self.height,
+                tiles: data.decode(encoding, compression)?,
+            }]);
+        }
+
+        merge_chunks(
+            std::mem::take(&mut self.chunks)
+                .into_iter()
+                .map(|chunk| {
+                    Ok(RawChunk {
+                        x: chunk.x,
+                        y: chunk.y,
+                        width: chunk.width,
+                        height: chunk.height,
+                        tiles: chunk.data.decode(encoding, compression)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        )
+    }
+
+    fn into_layer(self, kind: LayerKind) -> Result<super::Layer> {
+        Ok(super::Layer {
+            id: self.id,
+            name: self.name,
+            class: self.class,
+            visible: self.visible,
+            opacity: self.opacity,
+            tint: self
+                .tintcolor
+                .as_deref()
+                .map(parse_color)
+                .transpose()?
+                .unwrap_or(Color::WHITE),
+            offset: mint::Vector2 {
+                x: self.offsetx,
+                y: self.offsety,
+            },
+            parallax: mint::Vector2 {
+                x: self.parallaxx,
+                y: self.parallaxy,
+            },
+            properties: properties(self.properties)?,
+            kind,
+        })
+    }
+}
+
+/// Tile data, either as an array of global tile IDs or as a base64 string.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Data {
+    Tiles(Vec<u32>),
+    Encoded(String),
+}
+
+impl Data {
+    fn decode(self, encoding: Option<&str>, compression: Option<&str>) -> Result<Vec<u32>> {
+        match self {
+            Data::Tiles(tiles) => Ok(tiles),
+            Data::Encoded(data) => decode_tiles(&data, encoding, compression),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Chunk {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    data: Data,
+}
+
+#[derive(Debug, Deserialize)]
+struct Object {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    /// Called type before Tiled 1.9.
+    #[serde(default, alias = "type")]
+    class: String,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    rotation: f32,
+    gid: Option<u32>,
+    #[serde(default = "yes")]
+    visible: bool,
+    #[serde(default)]
+    ellipse: bool,
+    #[serde(default)]
+    point: bool,
+    polygon: Option<Vec<Offset>>,
+    polyline: Option<Vec<Offset>>,
+    text: Option<Text>,
+    #[serde(default)]
+    properties: Vec<Property>,
+}
+
+impl Object {
+    fn into_object(self) -> Result<MapObject> {
+        let points = |points: Vec<Offset>| {
+            points
+                .into_iter()
+                .map(|p| mint::Point2 { x: p.x, y: p.y })
+                .collect()
+        };
+        let shape = if let Some(polygon) = self.polygon {
+            ObjectShape::Polygon(points(polygon))
+        } else if let Some(polyline) = self.polyline {
+            ObjectShape::Polyline(points(polyline))
+        } else if let Some(text) = self.text {
+            ObjectShape::Text(text.text)
+        } else if self.ellipse {
+            ObjectShape::Ellipse
+        } else if self.point {
+            ObjectShape::Point
+        } else {
+            ObjectShape::Rectangle
+        };
+
+        Ok(MapObject {
+            id: self.id,
+            name: self.name,
+            class: self.class,
+            position: mint::Point2 {
+                x: self.x,
+                y: self.y,
+            },
+            size: mint::Vector2 {
+                x: self.width,
+                y: self.height,
+            },
+            rotation: self.rotation.to_radians(),
+            tile: self.gid.and_then(super::Tile::from_raw),
+            visible: self.visible,
+            shape,
+            properties: properties(self.properties)?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Text {
+    #[serde(default)]
+    text: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_tilemap_parse_tmj() {
+        let map = parse_map(
+            r##"{
+                "type": "map", "orientation": "orthogonal", "infinite": true,
+                "width": 4, "height": 4, "tilewidth": 8, "tileheight": 8,
+                "properties": [
+                    { "name": "boss", "type": "object", "value": 3 },
+                    { "name": "stats", "type": "class", "propertytype": "Stats",
+                      "value": { "hp": 10, "speed": 1.5 } }
+                ],
+                "tilesets": [
+                    { "firstgid": 1, "source": "terrain.tsj" },
+                    { "firstgid": 33, "name": "props", "image": "../props.png",
+                      "tilewidth": 8, "tileheight": 16, "tilecount": 8, "columns": 8,
+                      "tiles": [ { "id": 2, "type": "chest",
+                                   "properties": [ { "name": "loot", "value": "gold" } ] } ] }
+                ],
+                "layers": [
+                    { "type": "tilelayer", "id": 1, "name": "ground",
+                      "parallaxx": 0.25, "tintcolor": "#ff0000",
+                      "chunks": [
+                          { "x": -16, "y": 0, "width": 2, "height": 1, "data": [1, 0] },
+                          { "x": 0, "y": 0, "width": 1, "height": 1, "data": "IQAAAA==",
+                            "encoding": "base64" }
+                      ],
+                      "encoding": "base64" },
+                    { "type": "objectgroup", "id": 2, "name": "triggers", "visible": false,
+                      "objects": [ { "id": 7, "x": 1, "y": 2, "width": 3, "height": 4,
+                                     "ellipse": true } ] }
+                ]
+            }"##,
+        )
+        .unwrap();
+
+        assert!(map.infinite);
+        assert_eq!(map.properties["boss"], PropertyValue::Object(3));
+        let PropertyValue::Class(stats) = &map.properties["stats"] else {
+            panic!("expected a class property");
+        };
+        assert_eq!(stats["hp"], PropertyValue::Int(10));
+        assert_eq!(stats["speed"], PropertyValue::Float(1.5));
+
+        let RawTileset::Embedded(props) = &map.tilesets[1] else {
+            panic!("expected an embedded tileset");
+        };
+        assert_eq!(props.tiles[&2].class, "chest");
+        assert_eq!(
+            props.tiles[&2].properties["loot"],
+            PropertyValue::String(String::from("gold"))
+        );
+
+        let ground = &map.layers[0];
+        assert_eq!(ground.tint, Color::RED);
+        assert_eq!(ground.parallax, mint::Vector2 { x: 0.25, y: 1. });
+        let tiles = ground.tiles().unwrap();
+        assert_eq!((tiles.x, tiles.width), (-16, 17));
+        assert_eq!(tiles.tile(-16, 0).map(|t| t.gid), Some(1));
+        assert_eq!(tiles.tile(0, 0).map(|t| t.gid), Some(33));
+
+        let triggers = &map.layers[1];
+        assert!(!triggers.visible);
+        let objects = triggers.objects().unwrap();
+        assert_eq!(objects[0].shape, ObjectShape::Ellipse);
+        assert_eq!(objects[0].size, mint::Vector2 { x: 3., y: 4. });
+    }
+}
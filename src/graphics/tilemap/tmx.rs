@@ -0,0 +1,424 @@
+//! The XML map (`.tmx`) and tileset (`.tsx`) formats.
+
+use super::{
+    decode_tiles, merge_chunks, parse_color, Inherited, Layer, LayerKind, MapObject, ObjectShape,
+    Properties, PropertyValue, RawChunk, RawMap, RawTileset, Tile, TileData, TileFrame, TileLayer,
+    TilesetData,
+};
+use crate::graphics::Color;
+use roxmltree::{Document, Node};
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
+
+type Result<T> = std::result::Result<T, String>;
+
+pub(super) fn parse_map(text: &str) -> Result<RawMap> {
+    let doc = Document::parse(text).map_err(|e| e.to_string())?;
+    let map = doc.root_element();
+    if !map.has_tag_name("map") {
+        return Err(format!("expected <map>, found <{}>", map.tag_name().name()));
+    }
+
+    let tilesets = elements(map, "tileset")
+        .map(|node| match node.attribute("source") {
+            Some(source) => Ok(RawTileset::External {
+                first_gid: attr(node, "firstgid")?,
+                source: source.to_string(),
+            }),
+            None => tileset(node).map(RawTileset::Embedded),
+        })
+        .collect::<Result<_>>()?;
+
+    let mut layers = Vec::new();
+    parse_layers(map, Inherited::default(), &mut layers)?;
+
+    Ok(RawMap {
+        orientation: map
+            .attribute("orientation")
+            .unwrap_or("orthogonal")
+            .to_string(),
+        width: attr(map, "width")?,
+        height: attr(map, "height")?,
+        tile_width: attr(map, "tilewidth")?,
+        tile_height: attr(map, "tileheight")?,
+        infinite: opt_attr::<u8>(map, "infinite")?.unwrap_or(0) != 0,
+        background_color: map
+            .attribute("backgroundcolor")
+            .map(parse_color)
+            .transpose()?,
+        properties: properties(map)?,
+        tilesets,
+        layers,
+    })
+}
+
+pub(super) fn parse_tileset(text: &str) -> Result<TilesetData> {
+    let doc = Document::parse(text).map_err(|e| e.to_string())?;
+    let node = doc.root_element();
+    if !node.has_tag_name("tileset") {
+        return Err(format!(
+            "expected <tileset>, found <{}>",
+            node.tag_name().name()
+        ));
+    }
+    tileset(node)
+}
+
+fn tileset(node: Node) -> Result<TilesetData> {
+    let mut tiles = BTreeMap::new();
+    for tile in elements(node, "tile") {
+        let animation = match element(tile, "animation") {
+            Some(animation) => elements(animation, "frame")
+                .map(|frame| {
+                    Ok(TileFrame {
+                        tile_id: attr(frame, "tileid")?,
+                        duration: Duration::from_millis(attr(frame, "duration")?),
+                    })
+                })
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        let _ = tiles.insert(
+            attr(tile, "id")?,
+            TileData {
+                class: class(tile),
+                animation,
+                properties: properties(tile)?,
+            },
+        );
+    }
+
+    let offset = match element(node, "tileoffset") {
+        Some(offset) => mint::Vector2 {
+            x: attr(offset, "x")?,
+            y: attr(offset, "y")?,
+        },
+        None => mint::Vector2 { x: 0., y: 0. },
+    };
+
+    Ok(TilesetData {
+        first_gid: opt_attr(node, "firstgid")?.unwrap_or(0),
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        image: element(node, "image")
+            .map(|image| attr(image, "source"))
+            .transpose()?,
+        tile_width: attr(node, "tilewidth")?,
+        tile_height: attr(node, "tileheight")?,
+        columns: opt_attr(node, "columns")?.unwrap_or(0),
+        tile_count: opt_attr(node, "tilecount")?.unwrap_or(0),
+        margin: opt_attr(node, "margin")?.unwrap_or(0),
+        spacing: opt_attr(node, "spacing")?.unwrap_or(0),
+        offset,
+        tiles,
+        properties: properties(node)?,
+    })
+}
+
+fn parse_layers(parent: Node, inherited: Inherited, layers: &mut Vec<Layer>) -> Result<()> {
+    for node in parent.children().filter(Node::is_element) {
+        let kind = match node.tag_name().name() {
+            "layer" => LayerKind::Tiles(tile_layer(node)?),
+            "objectgroup" => LayerKind::Objects(
+                elements(node, "object")
+                    .map(object)
+                    .collect::<Result<_>>()?,
+            ),
+            "group" => {
+                let mut group = layer(node, LayerKind::Objects(Vec::new()))?;
+                parse_layers(node, inherited.apply(&mut group), layers)?;
+                continue;
+            }
+            "imagelayer" => {
+                log::warn!(
+                    "skipping image layer {:?}, image layers are not supported",
+                    node.attribute("name").unwrap_or_default()
+                );
+                continue;
+            }
+            _ => continue,
+        };
+
+        let mut parsed = layer(node, kind)?;
+        let _ = inherited.apply(&mut parsed);
+        layers.push(parsed);
+    }
+    Ok(())
+}
+
+fn layer(node: Node, kind: LayerKind) -> Result<Layer> {
+    Ok(Layer {
+        id: opt_attr(node, "id")?.unwrap_or(0),
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        class: class(node),
+        visible: opt_attr::<u8>(node, "visible")?.unwrap_or(1) != 0,
+        opacity: opt_attr(node, "opacity")?.unwrap_or(1.),
+        tint: node
+            .attribute("tintcolor")
+            .map(parse_color)
+            .transpose()?
+            .unwrap_or(Color::WHITE),
+        offset: mint::Vector2 {
+            x: opt_attr(node, "offsetx")?.unwrap_or(0.),
+            y: opt_attr(node, "offsety")?.unwrap_or(0.),
+        },
+        parallax: mint::Vector2 {
+            x: opt_attr(node, "parallaxx")?.unwrap_or(1.),
+            y: opt_attr(node, "parallaxy")?.unwrap_or(1.),
+        },
+        properties: properties(node)?,
+        kind,
+    })
+}
+
+fn tile_layer(node: Node) -> Result<TileLayer> {
+    let data = element(node, "data").ok_or("tile layer without <data>")?;
+    let encoding = data.attribute("encoding");
+    let compression = data.attribute("compression");
+
+    let chunks: Vec<_> = elements(data, "chunk").collect();
+    if chunks.is_empty() {
+        return merge_chunks(vec![RawChunk {
+            x: 0,
+            y: 0,
+            width: attr(node, "width")?,
+            height: attr(node, "height")?,
+            tiles: tiles(data, encoding, compression)?,
+        }]);
+    }
+
+    merge_chunks(
+        chunks
+            .into_iter()
+            .map(|chunk| {
+                Ok(RawChunk {
+                    x: attr(chunk, "x")?,
+                    y: attr(chunk, "y")?,
+                    width: attr(chunk, "width")?,
+                    height: attr(chunk, "height")?,
+                    tiles: tiles(chunk, encoding, compression)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    )
+}
+
+fn tiles(node: Node, encoding: Option<&str>, compression: Option<&str>) -> Result<Vec<u32>> {
+    match encoding {
+        // The deprecated unencoded format, one <tile> element per tile.
+        None => elements(node, "tile")
+            .map(|tile| Ok(opt_attr(tile, "gid")?.unwrap_or(0)))
+            .collect(),
+        Some(_) => decode_tiles(node.text().unwrap_or_default(), encoding, compression),
+    }
+}
+
+fn object(node: Node) -> Result<MapObject> {
+    let mut shape = ObjectShape::Rectangle;
+    for child in node.children().filter(Node::is_element) {
+        shape = match child.tag_name().name() {
+            "ellipse" => ObjectShape::Ellipse,
+            "point" => ObjectShape::Point,
+            "polygon" => ObjectShape::Polygon(points(child)?),
+            "polyline" => ObjectShape::Polyline(points(child)?),
+            "text" => ObjectShape::Text(child.text().unwrap_or_default().to_string()),
+            _ => continue,
+        };
+    }
+
+    Ok(MapObject {
+        id: opt_attr(node, "id")?.unwrap_or(0),
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        class: class(node),
+        position: mint::Point2 {
+            x: opt_attr(node, "x")?.unwrap_or(0.),
+            y: opt_attr(node, "y")?.unwrap_or(0.),
+        },
+        size: mint::Vector2 {
+            x: opt_attr(node, "width")?.unwrap_or(0.),
+            y: opt_attr(node, "height")?.unwrap_or(0.),
+        },
+        rotation: opt_attr::<f32>(node, "rotation")?
+            .unwrap_or(0.)
+            .to_radians(),
+        tile: opt_attr(node, "gid")?.and_then(Tile::from_raw),
+        visible: opt_attr::<u8>(node, "visible")?.unwrap_or(1) != 0,
+        shape,
+        properties: properties(node)?,
+    })
+}
+
+/// Parses the `points` attribute of a polygon or polyline, `"x1,y1 x2,y2 ..."`.
+fn points(node: Node) -> Result<Vec<mint::Point2<f32>>> {
+    attr::<String>(node, "points")?
+        .split_whitespace()
+        .map(|point| {
+            let (x, y) = point
+                .split_once(',')
+                .ok_or_else(|| format!("invalid point {point:?}"))?;
+            Ok(mint::Point2 {
+                x: parse(x)?,
+                y: parse(y)?,
+            })
+        })
+        .collect()
+}
+
+fn properties(node: Node) -> Result<Properties> {
+    let Some(list) = element(node, "properties") else {
+        return Ok(Properties::new());
+    };
+
+    elements(list, "property")
+        .map(|property| {
+            let name = attr::<String>(property, "name")?;
+            // Multiline strings are stored as the element's text instead.
+            let value = property
+                .attribute("value")
+                .or_else(|| property.text())
+                .unwrap_or_default();
+            let value = match property.attribute("type").unwrap_or("string") {
+                "bool" => PropertyValue::Bool(value == "true"),
+                "int" => PropertyValue::Int(parse(value)?),
+                "float" => PropertyValue::Float(parse(value)?),
+                "color" if value.is_empty() => PropertyValue::Color(Color::new(0., 0., 0., 0.)),
+                "color" => PropertyValue::Color(parse_color(value)?),
+                "file" => PropertyValue::File(value.to_string()),
+                "object" => PropertyValue::Object(parse(value)?),
+                "class" => PropertyValue::Class(properties(property)?),
+                _ => PropertyValue::String(value.to_string()),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+/// The class of an element, which was called type before Tiled 1.9.
+fn class(node: Node) -> String {
+    node.attribute("class")
+        .or_else(|| node.attribute("type"))
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn element<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn elements<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn attr<T: FromStr>(node: Node, name: &str) -> Result<T> {
+    opt_attr(node, name)?.ok_or_else(|| {
+        format!(
+            "<{}> is missing the {name:?} attribute",
+            node.tag_name().name()
+        )
+    })
+}
+
+fn opt_attr<T: FromStr>(node: Node, name: &str) -> Result<Option<T>> {
+    node.attribute(name)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                format!(
+                    "invalid {name:?} attribute {value:?} of <{}>",
+                    node.tag_name().name()
+                )
+            })
+        })
+        .transpose()
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid value {value:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_tilemap_parse_tmx() {
+        let map = parse_map(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" infinite="0" backgroundcolor="#336699">
+ <properties>
+  <property name="music" type="file" value="../music/level1.ogg"/>
+  <property name="gravity" type="float" value="9.5"/>
+ </properties>
+ <tileset firstgid="1" source="terrain.tsx"/>
+ <tileset firstgid="65" name="water" tilewidth="16" tileheight="16" tilecount="4" columns="4">
+  <image source="water.png" width="64" height="16"/>
+  <tile id="0">
+   <animation>
+    <frame tileid="0" duration="100"/>
+    <frame tileid="1" duration="100"/>
+   </animation>
+  </tile>
+ </tileset>
+ <group id="5" name="background" parallaxx="0.5" offsetx="8">
+  <layer id="1" name="ground" width="2" height="2" opacity="0.5">
+   <data encoding="csv">
+1,2,
+65,2147483649
+</data>
+  </layer>
+ </group>
+ <imagelayer id="6" name="sky"/>
+ <objectgroup id="2" name="spawns">
+  <object id="1" name="player" type="spawn" x="8" y="24">
+   <point/>
+  </object>
+  <object id="2" x="0" y="0" rotation="90">
+   <polygon points="0,0 16,0 16,16"/>
+  </object>
+ </objectgroup>
+</map>
+"##,
+        )
+        .unwrap();
+
+        assert_eq!(map.orientation, "orthogonal");
+        assert_eq!((map.width, map.tile_width), (2, 16));
+        assert_eq!(
+            map.background_color,
+            Some(Color::from_rgb(0x33, 0x66, 0x99))
+        );
+        assert_eq!(map.properties["gravity"], PropertyValue::Float(9.5));
+
+        assert!(matches!(
+            &map.tilesets[0],
+            RawTileset::External { first_gid: 1, source } if source == "terrain.tsx"
+        ));
+        let RawTileset::Embedded(water) = &map.tilesets[1] else {
+            panic!("expected an embedded tileset");
+        };
+        assert_eq!(water.first_gid, 65);
+        assert_eq!(water.image.as_deref(), Some("water.png"));
+        assert_eq!(water.tiles[&0].animation.len(), 2);
+
+        // The image layer is skipped and the group flattened into its child.
+        assert_eq!(map.layers.len(), 2);
+        let ground = &map.layers[0];
+        assert_eq!(ground.name, "ground");
+        assert_eq!(ground.offset, mint::Vector2 { x: 8., y: 0. });
+        assert_eq!(ground.parallax, mint::Vector2 { x: 0.5, y: 1. });
+        assert_eq!(ground.opacity, 0.5);
+        let tiles = ground.tiles().unwrap();
+        assert_eq!(tiles.tile(0, 1).map(|t| t.gid), Some(65));
+        assert!(tiles.tile(1, 1).unwrap().flip_horizontal);
+
+        let objects = map.layers[1].objects().unwrap();
+        assert_eq!(objects[0].class, "spawn");
+        assert_eq!(objects[0].shape, ObjectShape::Point);
+        assert_eq!(objects[1].rotation, 90f32.to_radians());
+        assert!(matches!(&objects[1].shape, ObjectShape::Polygon(points) if points.len() == 3));
+    }
+}
@@ -201,6 +201,7 @@ pub mod event;
 pub mod filesystem;
 pub mod graphics;
 pub mod input;
+mod rng;
 pub mod timer;
 mod vfs;
 
@@ -0,0 +1,60 @@
+//! A small random number generator for the randomness ggez needs itself, like particle
+//! spawning and sound variation, so that games can pick their own RNG crate.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A PCG32 (XSH RR) generator; fast, small and good enough for games, but not cryptographic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+impl Rng {
+    /// Creates a generator that always produces the same numbers for the same seed.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng { state: 0 };
+        let _ = rng.rand_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        let _ = rng.rand_u32();
+        rng
+    }
+
+    /// Creates a randomly seeded generator.
+    pub fn from_entropy() -> Self {
+        Rng::new(RandomState::new().build_hasher().finish())
+    }
+
+    pub fn rand_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    pub fn rand_float(&mut self) -> f32 {
+        // The top 24 bits, as many as an f32 can represent exactly.
+        (self.rand_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_rng() {
+        let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+        let values: Vec<_> = (0..1000).map(|_| a.rand_float()).collect();
+        assert!(values.iter().all(|v| (0. ..1.).contains(v)));
+        assert!(values.iter().zip(&values[1..]).any(|(a, b)| a != b));
+        assert!(values.iter().all(|&v| v == b.rand_float()));
+        assert_ne!(Rng::new(43).rand_u32(), Rng::new(42).rand_u32());
+    }
+}
@@ -91,6 +91,7 @@ pub struct TimeContext {
     frame_durations: LogBuffer<time::Duration>,
     residual_update_dt: time::Duration,
     frame_count: usize,
+    alpha: f64,
 }
 
 /// How many frames we log update times for.
@@ -106,6 +107,7 @@ impl TimeContext {
             frame_durations: LogBuffer::new(TIME_LOG_FRAMES, initial_dt),
             residual_update_dt: time::Duration::from_secs(0),
             frame_count: 0,
+            alpha: 1.0,
         }
     }
 
@@ -157,6 +159,8 @@ impl TimeContext {
     ///
     /// The intention is to use this in your `update` call to control
     /// how often game logic is updated per frame (see [the astroblasto example](https://github.com/ggez/ggez/blob/30ea4a4ead67557d2ebb39550e17339323fc9c58/examples/05_astroblasto.rs#L438-L442)).
+    /// Alternatively, let [`event::run()`](../event/fn.run.html) do this for you
+    /// with a [`Timestep::Fixed`](crate::conf::Timestep::Fixed).
     ///
     /// Calling this decreases a timer inside the context if the function returns true.
     /// If called in a loop it may therefore return true once, twice or not at all, depending on
@@ -195,6 +199,51 @@ impl TimeContext {
         self.residual_update_dt
     }
 
+    /// Returns how far along the time is between the last fixed update and the next one,
+    /// from `0.0` to `1.0`.
+    ///
+    /// When running with a [`Timestep::Fixed`](crate::conf::Timestep::Fixed), call this in your
+    /// [`draw()`](../event/trait.EventHandler.html#tymethod.draw) callback to interpolate
+    /// between the previous and the current game state, e.g. `prev + (current - prev) * alpha`.
+    /// With a variable timestep this is always `1.0`, i.e. the current state.
+    /// (see <https://gafferongames.com/post/fix_your_timestep/>)
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Consumes the time accumulated since the last frame in steps of `1 / updates_per_second`,
+    /// returning how many fixed updates should be run this frame, and updates
+    /// [`alpha()`](#method.alpha) accordingly.
+    ///
+    /// At most `max_updates` are returned; any time beyond that is dropped,
+    /// apart from the fraction of a step that goes into the alpha.
+    /// Both `updates_per_second` and `max_updates` are treated as at least 1.
+    ///
+    /// It's usually not necessary to call this function yourself,
+    /// [`event::run()`](../event/fn.run.html) will do it for you with a
+    /// [`Timestep::Fixed`](crate::conf::Timestep::Fixed).
+    /// This uses the same timer as [`check_update_time()`](#method.check_update_time),
+    /// so don't combine the two.
+    pub fn consume_fixed_updates(&mut self, updates_per_second: u32, max_updates: u32) -> u32 {
+        let target_dt = fps_as_duration(updates_per_second.max(1));
+        let max_updates = max_updates.max(1);
+        let mut updates = 0;
+        while self.residual_update_dt >= target_dt {
+            if updates == max_updates {
+                // We can't keep up, so drop the backlog instead of spiraling into ever more updates.
+                let residual_nanos = self.residual_update_dt.as_nanos() % target_dt.as_nanos();
+                // Fits, as it's smaller than `target_dt`.
+                self.residual_update_dt = time::Duration::from_nanos(residual_nanos as u64);
+                break;
+            }
+            self.residual_update_dt -= target_dt;
+            updates += 1;
+        }
+
+        self.alpha = self.residual_update_dt.as_secs_f64() / target_dt.as_secs_f64();
+        updates
+    }
+
     /// Update the state of the `TimeContext` to record that
     /// another frame has taken place.  Necessary for the FPS
     /// tracking and [`check_update_time()`](fn.check_update_time.html)
@@ -329,3 +378,38 @@ pub fn yield_now() {
 pub fn ticks(ctx: &Context) -> usize {
     ctx.time.frame_count
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_fixed_updates() {
+        let mut time = TimeContext::new();
+        time.residual_update_dt = time::Duration::from_millis(25);
+        assert_eq!(time.consume_fixed_updates(100, 5), 2);
+        assert!((time.alpha() - 0.5).abs() < 1e-6);
+
+        // Only a fraction of a step is left over.
+        assert_eq!(time.consume_fixed_updates(100, 5), 0);
+        assert!((time.alpha() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn headless_test_fixed_updates_spiral_of_death() {
+        let mut time = TimeContext::new();
+        time.residual_update_dt = time::Duration::from_millis(1005);
+        assert_eq!(time.consume_fixed_updates(100, 5), 5);
+        assert!(time.remaining_update_time() < time::Duration::from_millis(10));
+        assert_eq!(time.consume_fixed_updates(100, 5), 0);
+    }
+
+    #[test]
+    fn headless_test_fixed_updates_zero() {
+        let mut time = TimeContext::new();
+        time.residual_update_dt = time::Duration::from_millis(2500);
+        // Zero updates per second or per frame would panic or never update.
+        assert_eq!(time.consume_fixed_updates(0, 0), 1);
+        assert!((time.alpha() - 0.5).abs() < 1e-6);
+    }
+}
@@ -25,9 +25,9 @@ fn convenient_path_to_str(path: &path::Path) -> GameResult<&str> {
     })
 }
 
-pub trait VFile: Read + Write + Seek + Debug {}
+pub trait VFile: Read + Write + Seek + Debug + Send + Sync {}
 
-impl<T> VFile for T where T: Read + Write + Seek + Debug {}
+impl<T> VFile for T where T: Read + Write + Seek + Debug + Send + Sync {}
 
 /// Options for opening files
 ///
@@ -679,6 +679,142 @@ impl Debug for ZipFileWrapper {
     }
 }
 
+/// A zip entry read straight out of the archive file as it's needed,
+/// instead of being extracted into memory like a `ZipFileWrapper`.
+///
+/// Stored entries seek directly.  Deflated ones can only be decompressed
+/// front to back, so seeking backwards starts over from the beginning of the
+/// entry and skips forward to the target.
+pub struct ZipFileStream {
+    reader: ZipStreamReader,
+    start: u64,
+    compressed_len: u64,
+    len: u64,
+    pos: u64,
+}
+
+enum ZipStreamReader {
+    Stored(fs::File),
+    Deflated(flate2::read::DeflateDecoder<io::Take<fs::File>>),
+}
+
+impl ZipFileStream {
+    /// Opens `source` again for the entry `z`, or returns `None` if the entry
+    /// isn't one we can stream, e.g. because it's encrypted or compressed
+    /// with something other than deflate.
+    fn new(source: &Path, z: &zip::read::ZipFile<Box<dyn ReadSeek>>) -> GameResult<Option<Self>> {
+        let Some(start) = z.data_start() else {
+            return Ok(None);
+        };
+        if z.encrypted() {
+            return Ok(None);
+        }
+        let mut file = fs::File::open(source)?;
+        let _ = file.seek(io::SeekFrom::Start(start))?;
+        let compressed_len = z.compressed_size();
+        let reader = match z.compression() {
+            zip::CompressionMethod::Stored => ZipStreamReader::Stored(file),
+            zip::CompressionMethod::Deflated => ZipStreamReader::Deflated(
+                flate2::read::DeflateDecoder::new(file.take(compressed_len)),
+            ),
+            _ => return Ok(None),
+        };
+        Ok(Some(Self {
+            reader,
+            start,
+            compressed_len,
+            len: z.size(),
+            pos: 0,
+        }))
+    }
+
+    /// Starts decompressing again from the beginning of the entry.
+    fn restart(&mut self) -> io::Result<()> {
+        if let ZipStreamReader::Deflated(decoder) = &mut self.reader {
+            let mut file = decoder.get_ref().get_ref().try_clone()?;
+            let _ = file.seek(io::SeekFrom::Start(self.start))?;
+            let _ = decoder.reset(file.take(self.compressed_len));
+        }
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl io::Read for ZipFileStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let max = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        let read = match &mut self.reader {
+            ZipStreamReader::Stored(file) => file.read(&mut buf[..max])?,
+            ZipStreamReader::Deflated(decoder) => decoder.read(&mut buf[..max])?,
+        };
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl io::Write for ZipFileStream {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cannot write to a zip file!",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Seek for ZipFileStream {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            io::SeekFrom::Start(offset) => Some(offset),
+            io::SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            io::SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        match &mut self.reader {
+            ZipStreamReader::Stored(file) => {
+                let _ = file.seek(io::SeekFrom::Start(self.start + target.min(self.len)))?;
+                self.pos = target;
+            }
+            ZipStreamReader::Deflated(_) => {
+                if target < self.pos {
+                    self.restart()?;
+                }
+                let skip = target.min(self.len) - self.pos.min(self.len);
+                let skipped = io::copy(&mut Read::take(&mut *self, skip), &mut io::sink())?;
+                if skipped < skip {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "zip entry ended before its recorded size",
+                    ));
+                }
+                self.pos = target;
+            }
+        }
+        Ok(target)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+impl Debug for ZipFileStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<Zipfile stream>")
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 struct ZipMetadata {
     len: u64,
@@ -741,6 +877,13 @@ impl VFS for ZipFS {
         }
         let mut archive = self.archive.lock().unwrap();
         let mut f = archive_get_by_name(&mut archive, path)?;
+        // Archives on disk can be opened again and read piece by piece;
+        // anything else gets copied out whole.
+        if let Some(source) = &self.source {
+            if let Some(stream) = ZipFileStream::new(source, &f)? {
+                return Ok(Box::new(stream) as Box<dyn VFile>);
+            }
+        }
         let zipfile = ZipFileWrapper::new(&mut f)?;
         Ok(Box::new(zipfile) as Box<dyn VFile>)
     }
@@ -975,5 +1118,53 @@ mod tests {
         assert_eq!(contents, "Zip contents!");
     }
 
+    #[test]
+    fn headless_test_zip_streaming() {
+        let contents: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join(format!("ggez_zip_stream_{}.zip", std::process::id()));
+        {
+            let mut zip_archive = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+            for (name, method) in [
+                ("deflated.bin", zip::CompressionMethod::Deflated),
+                ("stored.bin", zip::CompressionMethod::Stored),
+            ] {
+                let options = zip::write::SimpleFileOptions::default().compression_method(method);
+                zip_archive.start_file(name, options).unwrap();
+                zip_archive.write_all(&contents).unwrap();
+            }
+            let _file = zip_archive.finish().unwrap();
+        }
+        let zfs = ZipFS::new(&path).unwrap();
+
+        for name in ["/deflated.bin", "/stored.bin"] {
+            let mut f = zfs.open(Path::new(name)).unwrap();
+            assert_eq!(format!("{f:?}"), "<Zipfile stream>");
+            let mut buf = vec![0; 10];
+
+            assert_eq!(f.seek(io::SeekFrom::Start(70_000)).unwrap(), 70_000);
+            f.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, contents[70_000..70_010]);
+
+            // Going backwards starts a deflated entry over.
+            assert_eq!(f.seek(io::SeekFrom::Current(-50_010)).unwrap(), 20_000);
+            f.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, contents[20_000..20_010]);
+
+            assert_eq!(f.seek(io::SeekFrom::End(-5)).unwrap(), 99_995);
+            let mut rest = Vec::new();
+            let _bytes = f.read_to_end(&mut rest).unwrap();
+            assert_eq!(rest, contents[99_995..]);
+            assert!(f.seek(io::SeekFrom::Current(-200_000)).is_err());
+
+            f.rewind().unwrap();
+            let mut all = Vec::new();
+            let _bytes = f.read_to_end(&mut all).unwrap();
+            assert_eq!(all, contents);
+        }
+
+        drop(zfs);
+        fs::remove_file(&path).unwrap();
+    }
+
     // BUGGO: TODO: Make sure all functions are tested for OverlayFS and ZipFS!!
 }